@@ -3,8 +3,9 @@
 use alloc::vec::Vec; // module is alloc only
 
 use crate::{
-    try_compact_note_decryption_inner, try_note_decryption_inner, BatchDomain, EphemeralKeyBytes,
-    ShieldedOutput, COMPACT_NOTE_SIZE, ENC_CIPHERTEXT_SIZE,
+    try_compact_note_decryption_inner, try_memoless_note_decryption_inner,
+    try_note_decryption_inner, BatchDomain, EphemeralKeyBytes, ShieldedOutput, COMPACT_NOTE_SIZE,
+    ENC_CIPHERTEXT_SIZE, MEMOLESS_CIPHERTEXT_SIZE,
 };
 
 /// Trial decryption of a batch of notes with a set of recipients.
@@ -39,6 +40,25 @@ pub fn try_compact_note_decryption<D: BatchDomain, Output: ShieldedOutput<D, COM
     batch_note_decryption(ivks, outputs, try_compact_note_decryption_inner)
 }
 
+/// Trial decryption of a batch of memo-less notes with a set of recipients.
+///
+/// This is the batched version of [`crate::try_memoless_note_decryption`].
+///
+/// Returns a vector containing the decrypted result for each output,
+/// with the same length and in the same order as the outputs were
+/// provided, along with the index in the `ivks` slice associated with
+/// the IVK that successfully decrypted the output.
+#[allow(clippy::type_complexity)]
+pub fn try_memoless_note_decryption<
+    D: BatchDomain,
+    Output: ShieldedOutput<D, MEMOLESS_CIPHERTEXT_SIZE>,
+>(
+    ivks: &[D::IncomingViewingKey],
+    outputs: &[(D, Output)],
+) -> Vec<Option<((D::Note, D::Recipient), usize)>> {
+    batch_note_decryption(ivks, outputs, try_memoless_note_decryption_inner)
+}
+
 fn batch_note_decryption<D: BatchDomain, Output: ShieldedOutput<D, CS>, F, FR, const CS: usize>(
     ivks: &[D::IncomingViewingKey],
     outputs: &[(D, Output)],
@@ -58,6 +58,13 @@ pub const OUT_PLAINTEXT_SIZE: usize = 32 + // pk_d
 const AEAD_TAG_SIZE: usize = 16;
 /// The size of an encrypted note plaintext.
 pub const ENC_CIPHERTEXT_SIZE: usize = NOTE_PLAINTEXT_SIZE + AEAD_TAG_SIZE;
+/// The size of an encrypted memo-less note plaintext.
+///
+/// Unlike a compact output, a memo-less ciphertext is authenticated: it
+/// carries the AEAD tag over the compact note fields, so it can stand in for
+/// a full output on chains that store memos off-band, saving
+/// [`NOTE_PLAINTEXT_SIZE`]` - `[`COMPACT_NOTE_SIZE`] bytes per output.
+pub const MEMOLESS_CIPHERTEXT_SIZE: usize = COMPACT_NOTE_SIZE + AEAD_TAG_SIZE;
 /// The size of an encrypted outgoing plaintext.
 pub const OUT_CIPHERTEXT_SIZE: usize = OUT_PLAINTEXT_SIZE + AEAD_TAG_SIZE;
 
@@ -343,7 +350,7 @@ pub trait BatchDomain: Domain {
 ///
 /// Implementations of this trait are required to define the length of their ciphertext
 /// field. In order to use the trial decryption APIs in this crate, the length must be
-/// either [`ENC_CIPHERTEXT_SIZE`] or [`COMPACT_NOTE_SIZE`].
+/// [`ENC_CIPHERTEXT_SIZE`], [`MEMOLESS_CIPHERTEXT_SIZE`] or [`COMPACT_NOTE_SIZE`].
 pub trait ShieldedOutput<D: Domain, const CIPHERTEXT_SIZE: usize> {
     /// Exposes the `ephemeral_key` field of the output.
     fn ephemeral_key(&self) -> EphemeralKeyBytes;
@@ -447,6 +454,28 @@ impl<D: Domain> NoteEncryption<D> {
         output
     }
 
+    /// Generates a memo-less `encCiphertext` for this note.
+    ///
+    /// Only the compact note fields (version, diversifier, value, asset type
+    /// and rseed) are encrypted; the memo this context was created with is
+    /// not transmitted. The resulting ciphertext is still authenticated, and
+    /// can be decrypted with [`try_memoless_note_decryption`].
+    pub fn encrypt_memoless_note_plaintext(&self) -> [u8; MEMOLESS_CIPHERTEXT_SIZE] {
+        let pk_d = D::get_pk_d(&self.note);
+        let shared_secret = D::ka_agree_enc(&self.esk, &pk_d);
+        let key = D::kdf(shared_secret, &D::epk_bytes(&self.epk));
+        let input = D::note_plaintext_bytes(&self.note, &self.to, &self.memo);
+
+        let mut output = [0u8; MEMOLESS_CIPHERTEXT_SIZE];
+        output[..COMPACT_NOTE_SIZE].copy_from_slice(&input.0[..COMPACT_NOTE_SIZE]);
+        let tag = ChaCha20Poly1305::new(key.as_ref().into())
+            .encrypt_in_place_detached([0u8; 12][..].into(), &[], &mut output[..COMPACT_NOTE_SIZE])
+            .unwrap();
+        output[COMPACT_NOTE_SIZE..].copy_from_slice(&tag);
+
+        output
+    }
+
     /// Generates `outCiphertext` for this note.
     pub fn encrypt_outgoing_plaintext<R: RngCore>(
         &self,
@@ -489,6 +518,14 @@ impl<D: Domain> NoteEncryption<D> {
 ///
 /// Implements section 4.19.2 of the
 /// [Zcash Protocol Specification](https://zips.z.cash/protocol/nu5.pdf#decryptivk).
+///
+/// # Timing
+///
+/// The key agreement, KDF, cipher, tag check, and plaintext parsing are all
+/// performed whether or not the output is addressed to `ivk`, and the tag is
+/// compared in constant time, so the failure path does the same work as the
+/// success path. (Plaintext parsing in the [`Domain`] implementation operates
+/// on the decrypted bytes and may still vary with their contents.)
 pub fn try_note_decryption<D: Domain, Output: ShieldedOutput<D, ENC_CIPHERTEXT_SIZE>>(
     domain: &D,
     ivk: &D::IncomingViewingKey,
@@ -515,25 +552,37 @@ fn try_note_decryption_inner<D: Domain, Output: ShieldedOutput<D, ENC_CIPHERTEXT
     let mut plaintext =
         NotePlaintextBytes(enc_ciphertext[..NOTE_PLAINTEXT_SIZE].try_into().unwrap());
 
-    ChaCha20Poly1305::new(key.as_ref().into())
-        .decrypt_in_place_detached(
-            [0u8; 12][..].into(),
-            &[],
-            &mut plaintext.0,
-            enc_ciphertext[NOTE_PLAINTEXT_SIZE..].into(),
-        )
-        .ok()?;
+    // Decrypt unconditionally with the ChaCha20 keystream (starting from block 1
+    // to skip over the Poly1305 keying output), then recompute the expected tag
+    // by re-encrypting the recovered plaintext. This does the same cipher and
+    // parsing work whether or not the tag is valid, so a failed trial decryption
+    // takes the same time as a successful one; see [`try_note_decryption`].
+    let mut keystream = ChaCha20::new(key.as_ref().into(), [0u8; 12][..].into());
+    keystream.seek(64);
+    keystream.apply_keystream(&mut plaintext.0);
 
-    let (note, to) = parse_note_plaintext_without_memo_ivk(
+    let mut reencrypted = plaintext.0;
+    let expected_tag = ChaCha20Poly1305::new(key.as_ref().into())
+        .encrypt_in_place_detached([0u8; 12][..].into(), &[], &mut reencrypted)
+        .expect("encryption into a correctly-sized buffer cannot fail");
+    let tag_is_valid: bool = expected_tag
+        .ct_eq(&enc_ciphertext[NOTE_PLAINTEXT_SIZE..])
+        .into();
+
+    let parsed = parse_note_plaintext_without_memo_ivk(
         domain,
         ivk,
         ephemeral_key,
         &output.cmstar_bytes(),
         &plaintext.0,
-    )?;
+    );
     let memo = domain.extract_memo(&plaintext);
 
-    Some((note, to, memo))
+    if tag_is_valid {
+        parsed.map(|(note, to)| (note, to, memo))
+    } else {
+        None
+    }
 }
 
 fn parse_note_plaintext_without_memo_ivk<D: Domain>(
@@ -557,22 +606,21 @@ fn check_note_validity<D: Domain>(
     ephemeral_key: &EphemeralKeyBytes,
     cmstar_bytes: &D::ExtractedCommitmentBytes,
 ) -> NoteValidity {
-    if &D::ExtractedCommitmentBytes::from(&D::cmstar(note)) == cmstar_bytes {
-        if let Some(derived_esk) = D::derive_esk(note) {
-            if D::epk_bytes(&D::ka_derive_public(note, &derived_esk))
-                .ct_eq(ephemeral_key)
-                .into()
-            {
-                NoteValidity::Valid
-            } else {
-                NoteValidity::Invalid
-            }
-        } else {
-            // Before ZIP 212
-            NoteValidity::Valid
-        }
+    // Check the commitment and the ephemeral key unconditionally, so that a
+    // mismatched commitment does not skip the ephemeral key derivation.
+    let cmstar_is_valid = &D::ExtractedCommitmentBytes::from(&D::cmstar(note)) == cmstar_bytes;
+    let epk_is_valid = if let Some(derived_esk) = D::derive_esk(note) {
+        D::epk_bytes(&D::ka_derive_public(note, &derived_esk))
+            .ct_eq(ephemeral_key)
+            .into()
+    } else {
+        // Before ZIP 212
+        true
+    };
+
+    if cmstar_is_valid && epk_is_valid {
+        NoteValidity::Valid
     } else {
-        // Published commitment doesn't match calculated commitment
         NoteValidity::Invalid
     }
 }
@@ -585,6 +633,9 @@ fn check_note_validity<D: Domain>(
 ///
 /// Implements the procedure specified in [`ZIP 307`].
 ///
+/// The same timing properties as [`try_note_decryption`] apply: every step is
+/// performed whether or not the output is addressed to `ivk`.
+///
 /// [`ZIP 307`]: https://zips.z.cash/zip-0307
 pub fn try_compact_note_decryption<D: Domain, Output: ShieldedOutput<D, COMPACT_NOTE_SIZE>>(
     domain: &D,
@@ -623,6 +674,74 @@ fn try_compact_note_decryption_inner<D: Domain, Output: ShieldedOutput<D, COMPAC
     )
 }
 
+/// Trial decryption of a memo-less note plaintext by the recipient.
+///
+/// Attempts to decrypt and validate the given memo-less shielded output using
+/// the given `ivk`. If successful, the corresponding note is returned, along
+/// with the address to which the note was sent. Unlike
+/// [`try_compact_note_decryption`], the AEAD tag is checked, so a successful
+/// decryption guarantees the ciphertext was not tampered with.
+pub fn try_memoless_note_decryption<
+    D: Domain,
+    Output: ShieldedOutput<D, MEMOLESS_CIPHERTEXT_SIZE>,
+>(
+    domain: &D,
+    ivk: &D::IncomingViewingKey,
+    output: &Output,
+) -> Option<(D::Note, D::Recipient)> {
+    let ephemeral_key = output.ephemeral_key();
+
+    let epk = D::prepare_epk(D::epk(&ephemeral_key)?);
+    let shared_secret = D::ka_agree_dec(ivk, &epk);
+    let key = D::kdf(shared_secret, &ephemeral_key);
+
+    try_memoless_note_decryption_inner(domain, ivk, &ephemeral_key, output, &key)
+}
+
+fn try_memoless_note_decryption_inner<
+    D: Domain,
+    Output: ShieldedOutput<D, MEMOLESS_CIPHERTEXT_SIZE>,
+>(
+    domain: &D,
+    ivk: &D::IncomingViewingKey,
+    ephemeral_key: &EphemeralKeyBytes,
+    output: &Output,
+    key: &D::SymmetricKey,
+) -> Option<(D::Note, D::Recipient)> {
+    let enc_ciphertext = output.enc_ciphertext();
+
+    let mut plaintext = [0u8; COMPACT_NOTE_SIZE];
+    plaintext.copy_from_slice(&enc_ciphertext[..COMPACT_NOTE_SIZE]);
+
+    // As in [`try_note_decryption_inner`], decrypt unconditionally and check
+    // the tag in constant time once all of the work has been done.
+    let mut keystream = ChaCha20::new(key.as_ref().into(), [0u8; 12][..].into());
+    keystream.seek(64);
+    keystream.apply_keystream(&mut plaintext);
+
+    let mut reencrypted = plaintext;
+    let expected_tag = ChaCha20Poly1305::new(key.as_ref().into())
+        .encrypt_in_place_detached([0u8; 12][..].into(), &[], &mut reencrypted)
+        .expect("encryption into a correctly-sized buffer cannot fail");
+    let tag_is_valid: bool = expected_tag
+        .ct_eq(&enc_ciphertext[COMPACT_NOTE_SIZE..])
+        .into();
+
+    let parsed = parse_note_plaintext_without_memo_ivk(
+        domain,
+        ivk,
+        ephemeral_key,
+        &output.cmstar_bytes(),
+        &plaintext,
+    );
+
+    if tag_is_valid {
+        parsed
+    } else {
+        None
+    }
+}
+
 /// Recovery of the full note plaintext by the sender.
 ///
 /// Attempts to decrypt and validate the given shielded output using the given `ovk`.
@@ -0,0 +1,73 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::Criterion;
+use masp_primitives::{
+    bench::{note_commitment_contents, populated_commitment_tree, trial_decryption_fixture},
+    consensus::{BlockHeight, TEST_NETWORK},
+    sapling::merkle_hash,
+    sapling::note_encryption::try_sapling_note_decryption,
+    sapling::pedersen_hash::{pedersen_hash, Personalization},
+    zip32::{ChildIndex, ExtendedSpendingKey},
+};
+use rand_core::{OsRng, RngCore};
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut rng = OsRng;
+    let height = BlockHeight::from(1_000_000);
+
+    {
+        let input = note_commitment_contents(&mut rng);
+        c.bench_function("pedersen hash", |b| {
+            b.iter(|| pedersen_hash(Personalization::NoteCommitment, input.iter().cloned()))
+        });
+    }
+
+    {
+        let mut lhs = [0u8; 32];
+        let mut rhs = [0u8; 32];
+        rng.fill_bytes(&mut lhs);
+        rng.fill_bytes(&mut rhs);
+        c.bench_function("merkle hash", |b| b.iter(|| merkle_hash(16, &lhs, &rhs)));
+    }
+
+    {
+        let (ivk, output) = trial_decryption_fixture(height, &mut rng);
+        c.bench_function("trial decryption (ours)", |b| {
+            b.iter(|| try_sapling_note_decryption(&TEST_NETWORK, height, &ivk, &output))
+        });
+
+        let (other_ivk, _) = trial_decryption_fixture(height, &mut rng);
+        c.bench_function("trial decryption (not ours)", |b| {
+            b.iter(|| try_sapling_note_decryption(&TEST_NETWORK, height, &other_ivk, &output))
+        });
+    }
+
+    {
+        let mut seed = [0u8; 32];
+        rng.fill_bytes(&mut seed);
+        let master = ExtendedSpendingKey::master(&seed);
+        let path = [
+            ChildIndex::Hardened(32),
+            ChildIndex::Hardened(1),
+            ChildIndex::Hardened(0),
+        ];
+        c.bench_function("zip32 derivation", |b| {
+            b.iter(|| ExtendedSpendingKey::from_path(&master, &path))
+        });
+    }
+
+    {
+        let tree = populated_commitment_tree(1024, &mut rng);
+        let node = masp_primitives::bench::random_commitment_nodes(1, &mut rng)[0];
+        c.bench_function("commitment tree append", |b| {
+            b.iter(|| {
+                let mut tree = tree.clone();
+                tree.append(node).unwrap()
+            })
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
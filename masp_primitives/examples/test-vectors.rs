@@ -0,0 +1,105 @@
+//! Regenerates deterministic test vectors from fixed seeds and writes them as
+//! JSON to stdout, so downstream chains can cross-check their own ports
+//! against this crate's personalizations (`MASP_IP32Sapling` etc.) without
+//! re-deriving them by hand.
+//!
+//! ```text
+//! cargo run --example test-vectors > masp-test-vectors.json
+//! ```
+
+use group::GroupEncoding;
+use masp_primitives::{
+    asset_type::AssetType,
+    consensus::{BlockHeight, BranchId, TestNetwork},
+    memo::MemoBytes,
+    sapling::{note_encryption::sapling_note_encryption, Rseed},
+    transaction::{Authorized, TransactionData, TxVersion},
+    zip32::{ChildIndex, ExtendedSpendingKey},
+};
+use rand_core::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde_json::json;
+
+fn main() {
+    let mut zip32_vectors = vec![];
+    let mut note_encryption_vectors = vec![];
+
+    for i in 0u8..3 {
+        let seed = [i; 32];
+
+        // ZIP 32 derivation along the MASP registered coin type path,
+        // m/32'/877'/i'.
+        let master = ExtendedSpendingKey::master(&seed);
+        let extsk = ExtendedSpendingKey::from_path(
+            &master,
+            &[
+                ChildIndex::Hardened(32),
+                ChildIndex::Hardened(877),
+                ChildIndex::Hardened(i as u32),
+            ],
+        );
+        #[allow(deprecated)]
+        let xfvk = extsk.to_extended_full_viewing_key();
+        let (j, addr) = extsk.default_address();
+
+        let mut xfvk_bytes = vec![];
+        xfvk.write(&mut xfvk_bytes).unwrap();
+        zip32_vectors.push(json!({
+            "seed": hex::encode(seed),
+            "path": format!("m/32'/877'/{}'", i),
+            "extsk": hex::encode(extsk.to_bytes()),
+            "xfvk": hex::encode(xfvk_bytes),
+            "default_diversifier_index": hex::encode(j.0),
+            "default_address": hex::encode(addr.to_bytes()),
+        }));
+
+        // Note encryption from deterministically derived materials.
+        let asset_type = AssetType::new(b"MASP-test-vectors").unwrap();
+        let value = 1000 + i as u64;
+        let note = addr
+            .create_note(asset_type, value, Rseed::AfterZip212([i; 32]))
+            .unwrap();
+        let cmu = note.cmstar();
+        let ovk = xfvk.fvk.ovk;
+        let ne = sapling_note_encryption::<TestNetwork>(
+            Some(ovk),
+            note,
+            addr,
+            MemoBytes::empty(),
+        );
+        let enc_ciphertext = ne.encrypt_note_plaintext();
+        // The outgoing ciphertext only uses the RNG for pre-ZIP-212 notes, but
+        // the API requires one; keep it seeded so reruns are reproducible.
+        let mut rng = XorShiftRng::from_seed([i; 16]);
+        let cv = asset_type.value_commitment(value, jubjub::Fr::from(u64::from(i) + 1));
+        let out_ciphertext = ne.encrypt_outgoing_plaintext(&cv.commitment().into(), &cmu, &mut rng);
+        note_encryption_vectors.push(json!({
+            "asset_type": hex::encode(asset_type.get_identifier()),
+            "value": value,
+            "rseed": hex::encode([i; 32]),
+            "cmu": hex::encode(cmu.to_bytes()),
+            "epk": hex::encode(ne.epk().to_bytes()),
+            "enc_ciphertext": hex::encode(enc_ciphertext),
+            "out_ciphertext": hex::encode(out_ciphertext),
+        }));
+    }
+
+    // The txid of an empty MASP transaction pins down the BLAKE2b txid
+    // personalizations.
+    let empty_tx: TransactionData<Authorized> = TransactionData::from_parts(
+        TxVersion::MASPv5,
+        BranchId::MASP,
+        0,
+        BlockHeight::from_u32(0),
+        None,
+        None,
+    );
+    let empty_txid = hex::encode(empty_tx.freeze().unwrap().txid().as_ref());
+
+    let vectors = json!({
+        "zip32": zip32_vectors,
+        "note_encryption": note_encryption_vectors,
+        "transaction": { "empty_txid": empty_txid },
+    });
+    println!("{}", serde_json::to_string_pretty(&vectors).unwrap());
+}
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use masp_primitives::transaction::components::{I128Sum, I32Sum, I64Sum};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = I32Sum::read(&mut &data[..]);
+    let _ = I64Sum::read(&mut &data[..]);
+    let _ = I128Sum::read(&mut &data[..]);
+});
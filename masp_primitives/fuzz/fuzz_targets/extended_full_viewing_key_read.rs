@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use masp_primitives::zip32::ExtendedFullViewingKey;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ExtendedFullViewingKey::read(data);
+});
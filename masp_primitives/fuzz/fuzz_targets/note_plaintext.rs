@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use masp_note_encryption::{Domain, COMPACT_NOTE_SIZE};
+use masp_primitives::{
+    consensus::{BlockHeight, TEST_NETWORK},
+    sapling::{
+        note_encryption::{PreparedIncomingViewingKey, SaplingDomain},
+        SaplingIvk,
+    },
+};
+
+fuzz_target!(|data: &[u8]| {
+    // `parse_note_plaintext_without_memo_ivk` requires at least a compact
+    // note's worth of bytes.
+    if data.len() < COMPACT_NOTE_SIZE {
+        return;
+    }
+
+    let domain = SaplingDomain::for_height(TEST_NETWORK, BlockHeight::from(1_000_000));
+    let ivk = PreparedIncomingViewingKey::new(&SaplingIvk(jubjub::Fr::from(1)));
+
+    let _ = domain.parse_note_plaintext_without_memo_ivk(&ivk, data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use masp_primitives::transaction::components::sapling::OutputDescriptionV5;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = OutputDescriptionV5::read(&mut &data[..]);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use masp_primitives::{consensus::BranchId, transaction::Transaction};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Transaction::read(data, BranchId::MASP);
+});
@@ -0,0 +1,103 @@
+//! Fixtures for benchmarking hot paths on realistic data.
+//!
+//! This module is feature-gated behind `benchmarks` and is intended solely for
+//! criterion benches in this workspace; it is not a stable API. The fixtures
+//! build the same shapes of data that node and wallet implementations process,
+//! so regressions in Pedersen hashing, trial decryption, and commitment tree
+//! appends are measurable against representative inputs.
+
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{
+    asset_type::AssetType,
+    consensus::{BlockHeight, TestNetwork, TEST_NETWORK},
+    keys::OutgoingViewingKey,
+    memo::MemoBytes,
+    merkle_tree::CommitmentTree,
+    sapling::{
+        note_encryption::{sapling_note_encryption, PreparedIncomingViewingKey},
+        util::generate_random_rseed,
+        Diversifier, Node, PaymentAddress, SaplingIvk,
+    },
+    transaction::components::{
+        sapling::{GrothProofBytes, OutputDescription},
+        GROTH_PROOF_SIZE,
+    },
+};
+
+use ff::{Field, PrimeField};
+use group::GroupEncoding;
+
+/// Returns a random bit string of the length hashed while computing a note
+/// commitment: the note contents (asset generator, value, g_d, and pk_d bit
+/// representations) preceded by the 6-bit personalization prefix.
+pub fn note_commitment_contents<R: RngCore + CryptoRng>(rng: &mut R) -> Vec<bool> {
+    (0..(6 + 256 + 64 + 256 + 256))
+        .map(|_| rng.next_u32() % 2 != 0)
+        .collect()
+}
+
+/// Builds an output description that decrypts under the returned incoming
+/// viewing key, for benchmarking trial decryption with
+/// [`try_sapling_note_decryption`].
+///
+/// [`try_sapling_note_decryption`]: crate::sapling::note_encryption::try_sapling_note_decryption
+pub fn trial_decryption_fixture<R: RngCore + CryptoRng>(
+    height: BlockHeight,
+    mut rng: &mut R,
+) -> (
+    PreparedIncomingViewingKey,
+    OutputDescription<GrothProofBytes>,
+) {
+    let ivk = SaplingIvk(jubjub::Fr::random(&mut rng));
+    let prepared_ivk = PreparedIncomingViewingKey::new(&ivk);
+
+    let diversifier = Diversifier([10u8; 11]);
+    let pk_d = diversifier.g_d().unwrap() * ivk.0;
+    let pa = PaymentAddress::from_parts(diversifier, pk_d).unwrap();
+
+    let value = 100u64;
+    let asset_type = AssetType::new("BTC".as_bytes()).unwrap();
+    let value_commitment = asset_type.value_commitment(value, jubjub::Fr::random(&mut rng));
+    let cv = value_commitment.commitment().into();
+
+    let rseed = generate_random_rseed(&TEST_NETWORK, height, &mut rng);
+    let note = pa.create_note(asset_type, value, rseed).unwrap();
+    let cmu = note.cmstar();
+
+    let ovk = OutgoingViewingKey([0; 32]);
+    let ne = sapling_note_encryption::<TestNetwork>(Some(ovk), note, pa, MemoBytes::empty());
+    let epk = *ne.epk();
+
+    let output = OutputDescription {
+        cv,
+        cmu,
+        ephemeral_key: epk.to_bytes().into(),
+        enc_ciphertext: ne.encrypt_note_plaintext(),
+        out_ciphertext: ne.encrypt_outgoing_plaintext(&cv, &cmu, &mut rng),
+        zkproof: [0u8; GROTH_PROOF_SIZE],
+    };
+
+    (prepared_ivk, output)
+}
+
+/// Returns `count` random note commitment nodes, for benchmarking
+/// [`CommitmentTree`] appends.
+pub fn random_commitment_nodes<R: RngCore + CryptoRng>(count: usize, rng: &mut R) -> Vec<Node> {
+    (0..count)
+        .map(|_| Node::new(bls12_381::Scalar::random(&mut *rng).to_repr()))
+        .collect()
+}
+
+/// Returns a commitment tree pre-populated with `size` random notes, so
+/// appends are benchmarked against a tree with realistic filled frontiers.
+pub fn populated_commitment_tree<R: RngCore + CryptoRng>(
+    size: usize,
+    rng: &mut R,
+) -> CommitmentTree<Node> {
+    let mut tree = CommitmentTree::empty();
+    for node in random_commitment_nodes(size, rng) {
+        tree.append(node).unwrap();
+    }
+    tree
+}
@@ -1,4 +1,6 @@
 use crate::{
+    asset_type::AssetType,
+    merkle_tree::{CommitmentTree, IncrementalWitness, MerklePath},
     sapling::{
         pedersen_hash::{pedersen_hash, Personalization},
         Node, ValueCommitment,
@@ -12,6 +14,7 @@ use borsh::schema::Fields;
 use borsh::BorshSchema;
 use borsh::{BorshDeserialize, BorshSerialize};
 use group::{Curve, GroupEncoding};
+use num_traits::CheckedAdd;
 use std::collections::BTreeMap;
 use std::{
     io::{self, Write},
@@ -75,6 +78,73 @@ impl AllowedConversion {
     pub fn commitment(&self) -> Node {
         Node::from_scalar(self.cmu())
     }
+
+    /// Constructs the canonical conversion for rolling an asset over from one
+    /// epoch to the next: `-1` of the old-epoch asset, `+1` of the new-epoch
+    /// asset, and `reward_rate` of the reward asset per unit converted.
+    ///
+    /// Returns `None` if the old and new assets coincide (the rollover would
+    /// be a no-op) or if accumulating the reward rate overflows, e.g. when the
+    /// reward asset coincides with one of the epoch assets.
+    pub fn epoch_rollover(
+        old_asset: AssetType,
+        new_asset: AssetType,
+        reward_asset: AssetType,
+        reward_rate: i128,
+    ) -> Option<Self> {
+        if old_asset == new_asset {
+            return None;
+        }
+        let assets = (I128Sum::from_pair(old_asset, -1) + I128Sum::from_pair(new_asset, 1))
+            .checked_add(&I128Sum::from_pair(reward_asset, reward_rate))?;
+        Some(assets.into())
+    }
+}
+
+/// Verifies that `root` is the root of the allowed-conversion commitment tree
+/// containing exactly the given conversions, in order, by recomputing the
+/// tree from scratch.
+///
+/// This lets light clients validate a conversion anchor served by an
+/// untrusted node against the conversion list it claims to commit to.
+pub fn verify_conversion_tree_root(
+    conversions: &[AllowedConversion],
+    root: &bls12_381::Scalar,
+) -> bool {
+    let mut tree = CommitmentTree::<Node>::empty();
+    for conversion in conversions {
+        if tree.append(conversion.commitment()).is_err() {
+            // The provided list overflows the commitment tree.
+            return false;
+        }
+    }
+    tree.root() == Node::from_scalar(*root)
+}
+
+/// Builds the allowed-conversion commitment tree over the given conversions,
+/// in order, returning the tree together with one Merkle path per conversion.
+///
+/// The tree's root is the conversion anchor to publish, and each path is the
+/// witness a prover needs to spend the corresponding conversion. Returns
+/// `Err(())` if the conversion list overflows the commitment tree.
+pub fn build_conversion_tree(
+    conversions: &[AllowedConversion],
+) -> Result<(CommitmentTree<Node>, Vec<MerklePath<Node>>), ()> {
+    let mut tree = CommitmentTree::<Node>::empty();
+    let mut witnesses: Vec<IncrementalWitness<Node>> = vec![];
+    for conversion in conversions {
+        let commitment = conversion.commitment();
+        tree.append(commitment)?;
+        for witness in &mut witnesses {
+            witness.append(commitment)?;
+        }
+        witnesses.push(IncrementalWitness::from_tree(&tree));
+    }
+    let paths = witnesses
+        .into_iter()
+        .map(|witness| witness.path().expect("tree is non-empty"))
+        .collect();
+    Ok((tree, paths))
 }
 
 impl From<AllowedConversion> for I128Sum {
@@ -205,7 +275,9 @@ impl Sum for AllowedConversion {
 #[cfg(test)]
 mod tests {
     use crate::asset_type::AssetType;
-    use crate::convert::AllowedConversion;
+    use crate::convert::{verify_conversion_tree_root, AllowedConversion};
+    use crate::merkle_tree::CommitmentTree;
+    use crate::sapling::Node;
     use crate::transaction::components::amount::ValueSum;
 
     /// Generate ZEC asset type
@@ -234,6 +306,79 @@ mod tests {
             AllowedConversion::from(a) + AllowedConversion::from(b)
         );
     }
+    #[test]
+    fn test_conversion_tree_root_verification() {
+        let conversions: Vec<AllowedConversion> = vec![
+            (ValueSum::from_pair(zec(), -1i128) + ValueSum::from_pair(btc(), 2i128)).into(),
+            (ValueSum::from_pair(btc(), -3i128) + ValueSum::from_pair(xan(), 1i128)).into(),
+        ];
+
+        // Recompute the reference tree root
+        let mut tree = CommitmentTree::<Node>::empty();
+        for conversion in &conversions {
+            tree.append(conversion.commitment()).unwrap();
+        }
+        let root: bls12_381::Scalar = tree.root().into();
+
+        assert!(verify_conversion_tree_root(&conversions, &root));
+        // A truncated list must not match the root
+        assert!(!verify_conversion_tree_root(&conversions[..1], &root));
+        // Nor a reordered one
+        let reordered = [conversions[1].clone(), conversions[0].clone()];
+        assert!(!verify_conversion_tree_root(&reordered, &root));
+    }
+
+    #[test]
+    fn test_conversion_tree_builder() {
+        use crate::convert::build_conversion_tree;
+
+        let conversions: Vec<AllowedConversion> = vec![
+            (ValueSum::from_pair(zec(), -1i128) + ValueSum::from_pair(btc(), 2i128)).into(),
+            (ValueSum::from_pair(btc(), -3i128) + ValueSum::from_pair(xan(), 1i128)).into(),
+            (ValueSum::from_pair(zec(), -2i128) + ValueSum::from_pair(xan(), 5i128)).into(),
+        ];
+
+        let (tree, paths) = build_conversion_tree(&conversions).unwrap();
+        assert_eq!(paths.len(), conversions.len());
+
+        // Every path witnesses its conversion's commitment under the root
+        let root: bls12_381::Scalar = tree.root().into();
+        assert!(verify_conversion_tree_root(&conversions, &root));
+        for (i, (conversion, path)) in conversions.iter().zip(&paths).enumerate() {
+            assert_eq!(path.position, i as u64);
+            assert_eq!(path.root(conversion.commitment()), tree.root());
+        }
+    }
+
+    #[test]
+    fn test_epoch_rollover() {
+        let old = AssetType::new(b"ZEC/0").unwrap();
+        let new = AssetType::new(b"ZEC/1").unwrap();
+
+        // The canonical rollover burns one old unit, mints one new unit, and
+        // pays out the reward rate in the reward asset
+        let conversion = AllowedConversion::epoch_rollover(old, new, xan(), 3).unwrap();
+        let expected: AllowedConversion = (ValueSum::from_pair(old, -1i128)
+            + ValueSum::from_pair(new, 1i128)
+            + ValueSum::from_pair(xan(), 3i128))
+        .into();
+        assert_eq!(conversion, expected);
+
+        // Rewards paid in the new-epoch asset accumulate onto its component
+        let conversion = AllowedConversion::epoch_rollover(old, new, new, 3).unwrap();
+        let expected: AllowedConversion =
+            (ValueSum::from_pair(old, -1i128) + ValueSum::from_pair(new, 4i128)).into();
+        assert_eq!(conversion, expected);
+
+        // A no-op rollover is rejected
+        assert_eq!(AllowedConversion::epoch_rollover(old, old, xan(), 3), None);
+        // As is a reward rate that overflows the new-epoch component
+        assert_eq!(
+            AllowedConversion::epoch_rollover(old, new, new, i128::MAX),
+            None
+        );
+    }
+
     #[test]
     fn test_serialization() {
         // Make conversion
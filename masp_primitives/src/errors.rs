@@ -0,0 +1,38 @@
+//! Crate-wide error types.
+
+use std::fmt;
+
+/// The error type for fallible `masp_primitives` operations whose failure
+/// modes carry no further data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// An amount that is required to be non-negative was negative.
+    NegativeAmount,
+    /// A hardened child index was used where only non-hardened derivation is
+    /// possible, such as deriving a child of a viewing key.
+    HardenedChildIndex,
+    /// The diversifier index space was exhausted without finding a valid
+    /// diversifier.
+    DiversifierSpaceExhausted,
+    /// The note commitment tree has no room for another leaf.
+    CommitmentTreeFull,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NegativeAmount => write!(f, "amount must be non-negative"),
+            Error::HardenedChildIndex => {
+                write!(f, "cannot derive a hardened child without a spending key")
+            }
+            Error::DiversifierSpaceExhausted => {
+                write!(f, "diversifier index space exhausted")
+            }
+            Error::CommitmentTreeFull => {
+                write!(f, "note commitment tree is full")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
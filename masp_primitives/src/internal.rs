@@ -0,0 +1,36 @@
+//! Unstable access to cryptographic building blocks, for audit tooling.
+//!
+//! This module exposes internal derivation functions so that differential
+//! testing harnesses can compare them, step by step, against independent
+//! implementations of the protocol. Nothing here is needed to build or
+//! verify transactions, and nothing here is covered by any stability
+//! promise: items may be renamed, change signature, or disappear in any
+//! release. Do not use this module outside of audit tooling.
+//!
+//! Requires the `internal-apis` feature.
+
+use blake2b_simd::Hash as Blake2bHash;
+use masp_note_encryption::EphemeralKeyBytes;
+
+use crate::sapling::{note_encryption, Rseed};
+
+pub use crate::keys::{prf_expand, prf_expand_vec};
+
+/// KDF^Sapling as used for note encryption: BLAKE2b-256 over the shared
+/// Diffie-Hellman secret and the ephemeral key bytes.
+///
+/// Implements section 5.4.4.4 of the Zcash Protocol Specification.
+pub fn kdf_sapling(
+    dhsecret: jubjub::SubgroupPoint,
+    ephemeral_key: &EphemeralKeyBytes,
+) -> Blake2bHash {
+    note_encryption::kdf_sapling(dhsecret, ephemeral_key)
+}
+
+/// Derives the note commitment trapdoor `rcm` from note randomness.
+///
+/// Before ZIP 212 the trapdoor is carried directly inside [`Rseed`]; after
+/// ZIP 212 it is `ToScalar(PRF^expand(rseed, [0x04]))`.
+pub fn rcm_from_rseed(rseed: &Rseed) -> jubjub::Fr {
+    rseed.rcm()
+}
@@ -16,15 +16,33 @@
 #![allow(clippy::derived_hash_with_manual_eq)]
 
 pub mod asset_type;
+#[cfg(feature = "benchmarks")]
+pub mod bench;
 pub mod consensus;
 pub mod constants;
 pub mod convert;
+pub mod errors;
+#[cfg(feature = "internal-apis")]
+#[cfg_attr(docsrs, doc(cfg(feature = "internal-apis")))]
+pub mod internal;
 pub mod keys;
+#[cfg(feature = "lightwalletd")]
+#[cfg_attr(docsrs, doc(cfg(feature = "lightwalletd")))]
+pub mod lightwalletd;
 pub mod memo;
 pub mod merkle_tree;
+#[cfg(feature = "pyo3")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pyo3")))]
+pub mod python;
 pub mod sapling;
+#[cfg(feature = "serde")]
+mod serde_impls;
 pub mod transaction;
+pub mod wallet;
 pub mod zip32;
+#[cfg(feature = "bip39")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bip39")))]
+pub mod zip339;
 
 pub use bls12_381;
 pub use ff;
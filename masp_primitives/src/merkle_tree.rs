@@ -18,10 +18,17 @@ use std::collections::BTreeMap;
 use std::collections::VecDeque;
 use std::io::{self, Read, Write};
 use std::iter::repeat;
-use zcash_encoding::{Optional, Vector};
+use zcash_encoding::{CompactSize, Optional, Vector};
 
 use crate::sapling::SAPLING_COMMITMENT_TREE_DEPTH;
 
+/// The leading byte of the compact commitment tree serialization format.
+///
+/// Legacy zcashd-compatible serializations begin with the presence byte of the
+/// `left` leaf, which is always `0x00` or `0x01`, so this marker also
+/// distinguishes the two formats when reading.
+const COMPACT_TREE_VERSION: u8 = 2;
+
 /// A hashable node within a Merkle tree.
 pub trait Hashable: Clone + Copy {
     /// Parses a node from the given byte source.
@@ -404,6 +411,117 @@ impl<Node: Hashable> CommitmentTree<Node> {
         })
     }
 
+    /// Serializes this tree in the compact versioned format, which stores the
+    /// leaf count followed by only the filled frontier nodes.
+    pub fn write_compact<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&[COMPACT_TREE_VERSION])?;
+        self.write_frontier(writer)
+    }
+
+    /// Reads a `CommitmentTree` from the compact versioned format.
+    pub fn read_compact<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != COMPACT_TREE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown commitment tree format {}", version[0]),
+            ));
+        }
+        Self::read_frontier(reader)
+    }
+
+    /// Reads a `CommitmentTree` in either the legacy or the compact format,
+    /// distinguishing them by their leading byte.
+    pub fn read_auto<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut lead = [0u8; 1];
+        reader.read_exact(&mut lead)?;
+        match lead[0] {
+            COMPACT_TREE_VERSION => Self::read_frontier(reader),
+            tag => Self::read_legacy_tail(reader, tag),
+        }
+    }
+
+    /// Reads the remainder of a legacy serialization whose leading byte (the
+    /// presence tag of the `left` leaf) has already been consumed.
+    fn read_legacy_tail<R: Read>(mut reader: R, left_tag: u8) -> io::Result<Self> {
+        let left = match left_tag {
+            0 => None,
+            1 => Some(Node::read(&mut reader)?),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "unknown commitment tree format",
+                ))
+            }
+        };
+        let right = Optional::read(&mut reader, Node::read)?;
+        let parents = Vector::read(&mut reader, |r| Optional::read(r, Node::read))?;
+
+        Ok(CommitmentTree {
+            left,
+            right,
+            parents,
+        })
+    }
+
+    /// Writes the frontier data: the leaf count followed by each filled node,
+    /// leaves first.
+    fn write_frontier<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        CompactSize::write(&mut writer, self.size())?;
+        for leaf in self.left.iter().chain(&self.right) {
+            leaf.write(&mut writer)?;
+        }
+        for parent in self.parents.iter().flatten() {
+            parent.write(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the frontier data written by [`Self::write_frontier`],
+    /// reconstructing the positions of the filled nodes from the leaf count.
+    fn read_frontier<R: Read>(mut reader: R) -> io::Result<Self> {
+        let size: usize = CompactSize::read_t(&mut reader)?;
+        if size > 1 << SAPLING_COMMITMENT_TREE_DEPTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "commitment tree leaf count exceeds tree capacity",
+            ));
+        }
+        if size == 0 {
+            return Ok(Self::empty());
+        }
+
+        let left = Some(Node::read(&mut reader)?);
+        let right = if size & 1 == 0 {
+            Some(Node::read(&mut reader)?)
+        } else {
+            None
+        };
+
+        // Bit `i` of the witnessed position indicates whether the parent at
+        // altitude `i` is filled; the parents vector extends exactly to its
+        // highest filled entry.
+        let position = size - 1;
+        let mut parents = vec![];
+        for i in 1.. {
+            if position >> i == 0 {
+                break;
+            }
+            parents.push(if position & (1 << i) != 0 {
+                Some(Node::read(&mut reader)?)
+            } else {
+                None
+            });
+        }
+
+        Ok(CommitmentTree {
+            left,
+            right,
+            parents,
+        })
+    }
+
     /// Adds a leaf node to the tree.
     ///
     /// Returns an error if the tree is full.
@@ -445,6 +563,97 @@ impl<Node: Hashable> CommitmentTree<Node> {
         Ok(())
     }
 
+    /// Appends many leaf nodes to the tree at once, hashing each level of the
+    /// new subtrees in parallel when the `multicore` feature is enabled.
+    ///
+    /// The resulting tree is identical to appending the leaves in order with
+    /// [`Self::append`], but initial sync over millions of commitments avoids
+    /// the per-leaf hashing of the one-by-one path.
+    ///
+    /// Returns an error if the leaves do not all fit in the tree.
+    pub fn extend_par(&mut self, leaves: &[Node]) -> Result<(), ()>
+    where
+        Node: Send + Sync,
+    {
+        let old_size = self.size();
+        if leaves.len() > (1usize << SAPLING_COMMITMENT_TREE_DEPTH) - old_size {
+            // Tree would overflow
+            return Err(());
+        }
+        if leaves.is_empty() {
+            return Ok(());
+        }
+
+        let new_size = old_size + leaves.len();
+        let position = new_size - 1;
+
+        // The run of nodes at the current altitude that are not yet settled
+        // in the old tree, starting at an even index within the level. At the
+        // leaf level this is the old partial pair (if any) followed by the
+        // new leaves.
+        let old_pair: Vec<Node> = self.left.iter().chain(&self.right).copied().collect();
+        // The index within the current level at which `run` starts.
+        let mut run_start = old_size - old_pair.len();
+        let mut run: Vec<Node> = old_pair.into_iter().chain(leaves.iter().copied()).collect();
+
+        // The final leaves: the last one or two leaves of the combined
+        // sequence remain unpaired exactly as under sequential appends.
+        let keep = if new_size & 1 == 0 { 2 } else { 1 };
+        let kept = &run[run.len() - keep..];
+        let (left, right) = (Some(kept[0]), kept.get(1).copied());
+        run.truncate(run.len() - keep);
+
+        let old_parents = std::mem::take(&mut self.parents);
+        let mut parents = vec![];
+        let mut altitude = 1;
+        while position >> altitude > 0 {
+            // Pair up the run into the next level. This is where almost all
+            // of the hashing happens, so it is done in parallel.
+            #[cfg(feature = "multicore")]
+            let combined = {
+                use rayon::prelude::*;
+                run.par_chunks(2)
+                    .map(|pair| Node::combine(altitude - 1, &pair[0], &pair[1]))
+                    .collect()
+            };
+            #[cfg(not(feature = "multicore"))]
+            let combined = run
+                .chunks(2)
+                .map(|pair| Node::combine(altitude - 1, &pair[0], &pair[1]))
+                .collect();
+            run = combined;
+            run_start /= 2;
+
+            // A run starting at an odd index is completed on the left by the
+            // settled subtree stored in the old tree at this altitude.
+            if run_start & 1 == 1 {
+                let settled = old_parents
+                    .get(altitude - 1)
+                    .copied()
+                    .flatten()
+                    .expect("old tree stores the left sibling of an odd-aligned run");
+                run.insert(0, settled);
+                run_start -= 1;
+            }
+
+            // Bit `altitude` of the final position indicates whether a parent
+            // is filled at this altitude; it is the last node of the run.
+            if position & (1 << altitude) != 0 {
+                parents.push(Some(run.pop().expect("run holds the filled parent")));
+            } else {
+                parents.push(None);
+            }
+
+            altitude += 1;
+        }
+
+        self.left = left;
+        self.right = right;
+        self.parents = parents;
+
+        Ok(())
+    }
+
     /// Returns the current root of the tree.
     pub fn root(&self) -> Node {
         self.root_inner(SAPLING_COMMITMENT_TREE_DEPTH, PathFiller::empty())
@@ -545,6 +754,71 @@ impl<Node: Hashable> IncrementalWitness<Node> {
         let filled = Vector::read(&mut reader, |r| Node::read(r))?;
         let cursor = Optional::read(&mut reader, CommitmentTree::read)?;
 
+        Ok(Self::from_parts(tree, filled, cursor))
+    }
+
+    /// Serializes this `IncrementalWitness` as an array of bytes.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.tree.write(&mut writer)?;
+        Vector::write(&mut writer, &self.filled, |w, n| n.write(w))?;
+        Optional::write(&mut writer, self.cursor.as_ref(), |w, t| t.write(w))
+    }
+
+    /// Serializes this `IncrementalWitness` in the compact versioned format,
+    /// which stores the tree and cursor as frontiers.
+    pub fn write_compact<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&[COMPACT_TREE_VERSION])?;
+        self.tree.write_frontier(&mut writer)?;
+        Vector::write(&mut writer, &self.filled, |w, n| n.write(w))?;
+        Optional::write(&mut writer, self.cursor.as_ref(), |w, t| {
+            t.write_frontier(w)
+        })
+    }
+
+    /// Reads an `IncrementalWitness` from the compact versioned format.
+    pub fn read_compact<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != COMPACT_TREE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown incremental witness format {}", version[0]),
+            ));
+        }
+        Self::read_compact_tail(reader)
+    }
+
+    /// Reads an `IncrementalWitness` in either the legacy or the compact
+    /// format, distinguishing them by their leading byte.
+    #[allow(clippy::redundant_closure)]
+    pub fn read_auto<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut lead = [0u8; 1];
+        reader.read_exact(&mut lead)?;
+        if lead[0] == COMPACT_TREE_VERSION {
+            Self::read_compact_tail(reader)
+        } else {
+            let tree = CommitmentTree::read_legacy_tail(&mut reader, lead[0])?;
+            let filled = Vector::read(&mut reader, |r| Node::read(r))?;
+            let cursor = Optional::read(&mut reader, CommitmentTree::read)?;
+            Ok(Self::from_parts(tree, filled, cursor))
+        }
+    }
+
+    /// Reads the remainder of a compact serialization whose version byte has
+    /// already been consumed.
+    #[allow(clippy::redundant_closure)]
+    fn read_compact_tail<R: Read>(mut reader: R) -> io::Result<Self> {
+        let tree = CommitmentTree::read_frontier(&mut reader)?;
+        let filled = Vector::read(&mut reader, |r| Node::read(r))?;
+        let cursor = Optional::read(&mut reader, CommitmentTree::read_frontier)?;
+        Ok(Self::from_parts(tree, filled, cursor))
+    }
+
+    fn from_parts(
+        tree: CommitmentTree<Node>,
+        filled: Vec<Node>,
+        cursor: Option<CommitmentTree<Node>>,
+    ) -> Self {
         let mut witness = IncrementalWitness {
             tree,
             filled,
@@ -554,14 +828,7 @@ impl<Node: Hashable> IncrementalWitness<Node> {
 
         witness.cursor_depth = witness.next_depth();
 
-        Ok(witness)
-    }
-
-    /// Serializes this `IncrementalWitness` as an array of bytes.
-    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
-        self.tree.write(&mut writer)?;
-        Vector::write(&mut writer, &self.filled, |w, n| n.write(w))?;
-        Optional::write(&mut writer, self.cursor.as_ref(), |w, t| t.write(w))
+        witness
     }
 
     /// Returns the position of the witnessed leaf node in the commitment tree.
@@ -715,6 +982,82 @@ impl<Node: Hashable> BorshDeserialize for IncrementalWitness<Node> {
     }
 }
 
+/// Applies a block's worth of note commitments to many [`IncrementalWitness`]es
+/// in one pass.
+///
+/// All witnesses must already have been advanced to the same underlying tree
+/// size `tree_size` (the number of leaves preceding `commitments[0]`); the
+/// effect on each witness is then identical to appending each commitment in
+/// order with [`IncrementalWitness::append`]. The roots of the complete
+/// subtrees formed by the new commitments are computed once and shared between
+/// all witnesses, so services tracking witnesses for many accounts avoid
+/// repeating the same hashing during each witness's tree replay.
+///
+/// Returns an error if any witness's tree is full.
+pub fn advance_witnesses<'a, Node: Hashable + 'a>(
+    tree_size: usize,
+    witnesses: impl IntoIterator<Item = &'a mut IncrementalWitness<Node>>,
+    commitments: &[Node],
+) -> Result<(), ()> {
+    // Precompute the root of every complete subtree that is aligned with the
+    // global tree and fully covered by the new commitments. The map for depth
+    // `d` subtrees is keyed by block-relative start index.
+    let mut subtree_roots: Vec<BTreeMap<usize, Node>> = Vec::new();
+    loop {
+        let depth = subtree_roots.len() + 1;
+        let size = 1usize << depth;
+        let half = size >> 1;
+        let mut level = BTreeMap::new();
+        // The first block-relative index aligned to `size` in the global tree.
+        let mut j = (size - (tree_size % size)) % size;
+        while j + size <= commitments.len() {
+            let (l, r) = if depth == 1 {
+                (commitments[j], commitments[j + 1])
+            } else {
+                (
+                    subtree_roots[depth - 2][&j],
+                    subtree_roots[depth - 2][&(j + half)],
+                )
+            };
+            level.insert(j, Node::combine(depth - 1, &l, &r));
+            j += size;
+        }
+        if level.is_empty() {
+            break;
+        }
+        subtree_roots.push(level);
+    }
+
+    for witness in witnesses {
+        let mut j = 0;
+        while j < commitments.len() {
+            if witness.cursor.is_none() {
+                let depth = witness.next_depth();
+                if depth >= SAPLING_COMMITMENT_TREE_DEPTH {
+                    // Tree is full
+                    return Err(());
+                }
+
+                // The next append would open a cursor covering the aligned
+                // subtree of depth `depth` starting at the current position.
+                // If that subtree lies entirely within this block, its root
+                // has already been computed above.
+                if depth > 0 && j + (1 << depth) <= commitments.len() {
+                    debug_assert_eq!((tree_size + j) % (1 << depth), 0);
+                    witness.filled.push(subtree_roots[depth - 1][&j]);
+                    j += 1 << depth;
+                    continue;
+                }
+            }
+
+            witness.append_inner(commitments[j], SAPLING_COMMITMENT_TREE_DEPTH)?;
+            j += 1;
+        }
+    }
+
+    Ok(())
+}
+
 /// A path from a position in a particular commitment tree to the root of that tree.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -765,6 +1108,15 @@ impl<Node: Hashable> MerklePath<Node> {
                 },
             )
     }
+
+    /// Returns whether this path proves that `leaf` is in the tree whose root
+    /// is `anchor`.
+    pub fn verify(&self, leaf: Node, anchor: Node) -> bool
+    where
+        Node: PartialEq,
+    {
+        self.root(leaf) == anchor
+    }
 }
 
 impl<Node: Hashable> BorshDeserialize for MerklePath<Node> {
@@ -875,6 +1227,76 @@ impl<Node: BorshSchema> BorshSchema for MerklePath<Node> {
     }
 }
 
+/// A compact, self-contained proof that a note commitment is included in the
+/// commitment tree under a historical anchor.
+///
+/// Wallets that prune full blocks can retain one of these per payment; it
+/// carries everything an auditor needs to check the payment against a known
+/// anchor: the commitment, its authentication path, the anchor itself, and the
+/// height at which the anchor was the root of the commitment tree. The auditor
+/// is expected to confirm out of band (e.g. against a block explorer or their
+/// own node) that the claimed anchor was indeed the tree root at the claimed
+/// height.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommitmentInclusionProof<Node> {
+    /// The note commitment whose inclusion is being proven.
+    pub commitment: Node,
+    /// The authentication path from the commitment to the anchor.
+    pub path: MerklePath<Node>,
+    /// The root of the commitment tree that the path commits to.
+    pub anchor: Node,
+    /// The height of the block at whose end the anchor was the tree root.
+    pub anchor_height: u64,
+}
+
+impl<Node: Hashable + PartialEq> CommitmentInclusionProof<Node> {
+    /// Produces an inclusion proof for the commitment tracked by the given
+    /// witness, against the witness's current root.
+    ///
+    /// Returns `None` if the witness cannot produce an authentication path
+    /// (i.e. its tree is empty).
+    pub fn from_witness(
+        witness: &IncrementalWitness<Node>,
+        commitment: Node,
+        anchor_height: u64,
+    ) -> Option<Self> {
+        witness.path().map(|path| CommitmentInclusionProof {
+            commitment,
+            path,
+            anchor: witness.root(),
+            anchor_height,
+        })
+    }
+
+    /// Checks that the authentication path connects the commitment to the
+    /// anchor.
+    pub fn verify(&self) -> bool {
+        self.path.root(self.commitment) == self.anchor
+    }
+
+    /// Reads an inclusion proof from its serialized form.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let commitment = Node::read(&mut reader)?;
+        let path = MerklePath::deserialize_reader(&mut reader)?;
+        let anchor = Node::read(&mut reader)?;
+        let anchor_height = reader.read_u64::<LittleEndian>()?;
+        Ok(CommitmentInclusionProof {
+            commitment,
+            path,
+            anchor,
+            anchor_height,
+        })
+    }
+
+    /// Serializes this inclusion proof as an array of bytes.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.commitment.write(&mut writer)?;
+        self.path.serialize(&mut writer)?;
+        self.anchor.write(&mut writer)?;
+        writer.write_u64::<LittleEndian>(self.anchor_height)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -888,7 +1310,8 @@ mod tests {
 
     use super::{
         testing::{arb_commitment_tree, TestNode},
-        CommitmentTree, FrozenCommitmentTree, Hashable, IncrementalWitness, MerklePath, PathFiller,
+        CommitmentInclusionProof, CommitmentTree, FrozenCommitmentTree, Hashable,
+        IncrementalWitness, MerklePath, PathFiller,
     };
 
     const HEX_EMPTY_ROOTS: [&str; 33] = [
@@ -973,6 +1396,184 @@ mod tests {
         }
     }
 
+    #[test]
+    fn commitment_inclusion_proof_verifies_and_roundtrips() {
+        let node = |i: u8| Node::new([i; 32]);
+
+        let mut tree = CommitmentTree::empty();
+        for i in 0..3 {
+            tree.append(node(i)).unwrap();
+        }
+        let mut witness = IncrementalWitness::from_tree(&tree);
+        for i in 3..7 {
+            witness.append(node(i)).unwrap();
+        }
+
+        let proof = CommitmentInclusionProof::from_witness(&witness, node(2), 1_000).unwrap();
+        assert!(proof.verify());
+        assert_eq!(proof.anchor, witness.root());
+
+        let mut bytes = vec![];
+        proof.write(&mut bytes).unwrap();
+        assert_eq!(
+            CommitmentInclusionProof::<Node>::read(&bytes[..]).unwrap(),
+            proof
+        );
+
+        // A proof for a different commitment must not verify against the path.
+        let mut bad = proof.clone();
+        bad.commitment = node(3);
+        assert!(!bad.verify());
+
+        // A proof against the wrong anchor must not verify.
+        let mut bad = proof;
+        bad.anchor = node(99);
+        assert!(!bad.verify());
+    }
+
+    #[test]
+    fn batch_extend_matches_sequential_appends() {
+        for initial in 0usize..20 {
+            for extra in 0usize..40 {
+                let leaves: Vec<TestNode> = (0..(initial + extra) as u64).map(TestNode).collect();
+
+                let mut expected = CommitmentTree::empty();
+                for leaf in &leaves {
+                    expected.append(*leaf).unwrap();
+                }
+
+                let mut tree = CommitmentTree::empty();
+                for leaf in &leaves[..initial] {
+                    tree.append(*leaf).unwrap();
+                }
+                tree.extend_par(&leaves[initial..]).unwrap();
+
+                assert_eq!(
+                    tree, expected,
+                    "extend_par diverged for {} + {} leaves",
+                    initial, extra
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn compact_serialization_round_trips_and_is_detected() {
+        let node = |i: u8| Node::new([i; 32]);
+
+        for leaves in 0usize..12 {
+            let mut tree = CommitmentTree::empty();
+            for i in 0..leaves {
+                tree.append(node(i as u8)).unwrap();
+            }
+
+            let mut legacy = vec![];
+            tree.write(&mut legacy).unwrap();
+            let mut compact = vec![];
+            tree.write_compact(&mut compact).unwrap();
+
+            // The compact format stores only the filled nodes, so it is never
+            // larger than the legacy format.
+            assert!(compact.len() <= legacy.len());
+            assert_eq!(
+                CommitmentTree::<Node>::read_compact(&compact[..]).unwrap(),
+                tree
+            );
+
+            // Both formats are recognized automatically.
+            assert_eq!(
+                CommitmentTree::<Node>::read_auto(&legacy[..]).unwrap(),
+                tree
+            );
+            assert_eq!(
+                CommitmentTree::<Node>::read_auto(&compact[..]).unwrap(),
+                tree
+            );
+        }
+
+        // An unknown leading byte is rejected by every reader.
+        assert!(CommitmentTree::<Node>::read(&[0xff][..]).is_err());
+        assert!(CommitmentTree::<Node>::read_compact(&[0xff][..]).is_err());
+        assert!(CommitmentTree::<Node>::read_auto(&[0xff][..]).is_err());
+    }
+
+    #[test]
+    fn compact_witness_serialization_round_trips_and_is_detected() {
+        let node = |i: u8| Node::new([i; 32]);
+
+        let mut tree = CommitmentTree::empty();
+        for i in 0..3 {
+            tree.append(node(i)).unwrap();
+        }
+        let mut witness = IncrementalWitness::from_tree(&tree);
+        for i in 3..9 {
+            witness.append(node(i)).unwrap();
+        }
+
+        let mut legacy = vec![];
+        witness.write(&mut legacy).unwrap();
+        let mut compact = vec![];
+        witness.write_compact(&mut compact).unwrap();
+        assert!(compact.len() <= legacy.len());
+
+        // Witnesses read back from either format serialize identically to the
+        // original in the legacy encoding.
+        for decoded in [
+            IncrementalWitness::<Node>::read_compact(&compact[..]).unwrap(),
+            IncrementalWitness::<Node>::read_auto(&compact[..]).unwrap(),
+            IncrementalWitness::<Node>::read_auto(&legacy[..]).unwrap(),
+        ] {
+            let mut bytes = vec![];
+            decoded.write(&mut bytes).unwrap();
+            assert_eq!(bytes, legacy);
+            assert_eq!(decoded.root(), witness.root());
+            assert_eq!(decoded.position(), witness.position());
+        }
+
+        assert!(IncrementalWitness::<Node>::read_compact(&legacy[..]).is_err());
+    }
+
+    #[test]
+    fn batch_witness_advancement_matches_sequential_appends() {
+        for tree_size in 1usize..17 {
+            for block_len in 0usize..17 {
+                let leaves: Vec<TestNode> =
+                    (0..(tree_size + block_len) as u64).map(TestNode).collect();
+
+                // Build the pre-block tree, with a witness for every position.
+                let mut tree = CommitmentTree::empty();
+                let mut witnesses: Vec<IncrementalWitness<TestNode>> = Vec::new();
+                for leaf in &leaves[..tree_size] {
+                    tree.append(*leaf).unwrap();
+                    for witness in &mut witnesses {
+                        witness.append(*leaf).unwrap();
+                    }
+                    witnesses.push(IncrementalWitness::from_tree(&tree));
+                }
+
+                // Advance a copy of every witness one commitment at a time.
+                let mut expected = witnesses.clone();
+                for leaf in &leaves[tree_size..] {
+                    for witness in &mut expected {
+                        witness.append(*leaf).unwrap();
+                    }
+                }
+
+                super::advance_witnesses(tree_size, witnesses.iter_mut(), &leaves[tree_size..])
+                    .unwrap();
+
+                for (witness, expected) in witnesses.iter().zip(&expected) {
+                    let mut a = vec![];
+                    witness.serialize(&mut a).unwrap();
+                    let mut b = vec![];
+                    expected.serialize(&mut b).unwrap();
+                    assert_eq!(a, b);
+                    assert_eq!(witness.root(), expected.root());
+                }
+            }
+        }
+    }
+
     const TESTING_DEPTH: usize = 4;
 
     struct TestCommitmentTree(CommitmentTree<Node>);
@@ -1515,6 +2116,33 @@ mod tests {
     }
 
     proptest! {
+        #[test]
+        fn prop_merkle_path_verifies_against_anchor(
+            leaves in prop::collection::vec(arb_node(), 1..32),
+            index in any::<prop::sample::Index>(),
+        ) {
+            let pos = index.index(leaves.len());
+
+            let mut tree = CommitmentTree::empty();
+            for leaf in &leaves[..=pos] {
+                tree.append(*leaf).unwrap();
+            }
+            let mut witness = IncrementalWitness::from_tree(&tree);
+            for leaf in &leaves[pos + 1..] {
+                tree.append(*leaf).unwrap();
+                witness.append(*leaf).unwrap();
+            }
+
+            let path = witness.path().unwrap();
+            prop_assert_eq!(path.root(leaves[pos]), tree.root());
+            prop_assert!(path.verify(leaves[pos], tree.root()));
+
+            // The path does not verify against a different anchor, nor for a
+            // different leaf.
+            prop_assert!(!path.verify(leaves[pos], Node::blank()));
+            prop_assert!(!path.verify(Node::blank(), tree.root()));
+        }
+
         #[test]
         fn prop_commitment_tree_roundtrip(ct in arb_commitment_tree(32, arb_node(), 8)) {
             let frontier: Frontier<Node, 8> = ct.to_frontier();
@@ -0,0 +1,250 @@
+//! Optional PyO3 bindings for the key, address, and amount APIs.
+//!
+//! These bindings exist so that Python tooling (test-vector generation,
+//! auditing scripts) can call the canonical Rust implementations instead of
+//! maintaining parallel ports such as `sapling_zip32.py`. They are gated
+//! behind the `pyo3` feature and expose thin wrappers over the native types;
+//! all encodings are the raw byte encodings used by the Rust APIs.
+
+// The #[pymethods] expansion wraps every fallible return in a PyErr
+// conversion, which newer clippy flags when the error already is a PyErr.
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::asset_type::AssetType;
+use crate::transaction::components::I128Sum;
+use crate::zip32::{
+    sapling::{ExtendedFullViewingKey, ExtendedSpendingKey},
+    ChildIndex, DiversifierIndex,
+};
+
+/// A ZIP 32 extended spending key.
+#[pyclass(name = "ExtendedSpendingKey")]
+#[derive(Clone)]
+pub struct PyExtendedSpendingKey(pub ExtendedSpendingKey);
+
+#[pymethods]
+impl PyExtendedSpendingKey {
+    /// Derives the master key of a MASP HD tree from a seed.
+    #[staticmethod]
+    fn master(seed: &[u8]) -> Self {
+        PyExtendedSpendingKey(ExtendedSpendingKey::master(seed))
+    }
+
+    /// Decodes an extended spending key from its 169-byte encoding.
+    #[staticmethod]
+    fn from_bytes(b: &[u8]) -> PyResult<Self> {
+        ExtendedSpendingKey::from_bytes(b)
+            .map(PyExtendedSpendingKey)
+            .map_err(|_| PyValueError::new_err("invalid extended spending key"))
+    }
+
+    /// Returns the 169-byte encoding of this extended spending key.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.0.to_bytes())
+    }
+
+    /// Derives the child key at the given index. Indices with the high bit
+    /// set (`i >= 2^31`) are hardened, matching path notation `(i - 2^31)'`.
+    fn derive_child(&self, i: u32) -> Self {
+        PyExtendedSpendingKey(self.0.derive_child(ChildIndex::from_index(i)))
+    }
+
+    /// Returns the extended full viewing key corresponding to this key.
+    fn to_extended_full_viewing_key(&self) -> PyExtendedFullViewingKey {
+        #[allow(deprecated)]
+        PyExtendedFullViewingKey(self.0.to_extended_full_viewing_key())
+    }
+
+    /// Derives the internal (change) spending key of this key.
+    fn derive_internal(&self) -> Self {
+        PyExtendedSpendingKey(self.0.derive_internal())
+    }
+
+    /// Returns the default address of this key with its diversifier index.
+    fn default_address(&self) -> (PyDiversifierIndex, PyPaymentAddress) {
+        let (j, addr) = self.0.default_address();
+        (PyDiversifierIndex(j), PyPaymentAddress(addr))
+    }
+}
+
+/// A ZIP 32 extended full viewing key.
+#[pyclass(name = "ExtendedFullViewingKey")]
+#[derive(Clone)]
+pub struct PyExtendedFullViewingKey(pub ExtendedFullViewingKey);
+
+#[pymethods]
+impl PyExtendedFullViewingKey {
+    /// Decodes an extended full viewing key from its 169-byte encoding.
+    #[staticmethod]
+    fn from_bytes(b: &[u8]) -> PyResult<Self> {
+        ExtendedFullViewingKey::read(b)
+            .map(PyExtendedFullViewingKey)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Returns the 169-byte encoding of this extended full viewing key.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let mut buf = Vec::with_capacity(169);
+        self.0
+            .write(&mut buf)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyBytes::new_bound(py, &buf))
+    }
+
+    /// Derives the non-hardened child key at the given index, or raises
+    /// `ValueError` for hardened indices (`i >= 2^31`).
+    fn derive_child(&self, i: u32) -> PyResult<Self> {
+        self.0
+            .derive_child(ChildIndex::from_index(i))
+            .map(PyExtendedFullViewingKey)
+            .map_err(|_| PyValueError::new_err("cannot derive hardened child from viewing key"))
+    }
+
+    /// Returns the payment address at the given diversifier index, if the
+    /// index produces a valid diversifier.
+    fn address(&self, j: &PyDiversifierIndex) -> Option<PyPaymentAddress> {
+        self.0.address(j.0).map(PyPaymentAddress)
+    }
+
+    /// Searches for the first valid diversifier index at or above `j`, and
+    /// returns it with its payment address.
+    fn find_address(
+        &self,
+        j: &PyDiversifierIndex,
+    ) -> Option<(PyDiversifierIndex, PyPaymentAddress)> {
+        self.0
+            .find_address(j.0)
+            .map(|(j, addr)| (PyDiversifierIndex(j), PyPaymentAddress(addr)))
+    }
+
+    /// Returns the default address of this key with its diversifier index.
+    fn default_address(&self) -> (PyDiversifierIndex, PyPaymentAddress) {
+        let (j, addr) = self.0.default_address();
+        (PyDiversifierIndex(j), PyPaymentAddress(addr))
+    }
+}
+
+/// A ZIP 32 diversifier index.
+#[pyclass(name = "DiversifierIndex")]
+#[derive(Clone)]
+pub struct PyDiversifierIndex(pub DiversifierIndex);
+
+#[pymethods]
+impl PyDiversifierIndex {
+    /// Constructs a diversifier index from an integer.
+    #[new]
+    fn new(i: u64) -> Self {
+        PyDiversifierIndex(DiversifierIndex::from(i))
+    }
+
+    /// Returns the 11-byte little-endian encoding of this index.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.0 .0)
+    }
+
+    /// Increments this index in place, raising `ValueError` on overflow.
+    fn increment(&mut self) -> PyResult<()> {
+        self.0
+            .increment()
+            .map_err(|_| PyValueError::new_err("diversifier index overflow"))
+    }
+}
+
+/// A Sapling payment address.
+#[pyclass(name = "PaymentAddress")]
+#[derive(Clone)]
+pub struct PyPaymentAddress(pub crate::sapling::PaymentAddress);
+
+#[pymethods]
+impl PyPaymentAddress {
+    /// Decodes a payment address from its 43-byte encoding, raising
+    /// `ValueError` if the bytes do not encode a valid address.
+    #[staticmethod]
+    fn from_bytes(b: &[u8]) -> PyResult<Self> {
+        let bytes: [u8; 43] = b
+            .try_into()
+            .map_err(|_| PyValueError::new_err("payment address must be 43 bytes"))?;
+        crate::sapling::PaymentAddress::from_bytes(&bytes)
+            .map(PyPaymentAddress)
+            .ok_or_else(|| PyValueError::new_err("invalid payment address"))
+    }
+
+    /// Returns the 43-byte encoding of this payment address.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.0.to_bytes())
+    }
+
+    /// Returns the 11-byte diversifier of this address.
+    fn diversifier<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.0.diversifier().0)
+    }
+}
+
+/// A multi-asset amount, keyed by 32-byte asset identifiers.
+#[pyclass(name = "Amount")]
+#[derive(Clone)]
+pub struct PyAmount(pub I128Sum);
+
+#[pymethods]
+impl PyAmount {
+    /// Constructs a zero-valued amount.
+    #[new]
+    fn new() -> Self {
+        PyAmount(I128Sum::zero())
+    }
+
+    /// Constructs an amount holding `value` of the asset with the given
+    /// 32-byte identifier.
+    #[staticmethod]
+    fn from_pair(identifier: &[u8], value: i128) -> PyResult<Self> {
+        let identifier: [u8; 32] = identifier
+            .try_into()
+            .map_err(|_| PyValueError::new_err("asset identifier must be 32 bytes"))?;
+        let atype = AssetType::from_identifier(&identifier)
+            .ok_or_else(|| PyValueError::new_err("invalid asset identifier"))?;
+        Ok(PyAmount(I128Sum::from_pair(atype, value)))
+    }
+
+    /// Returns the value held for the asset with the given 32-byte
+    /// identifier, which is zero for assets not present in the amount.
+    fn get(&self, identifier: &[u8]) -> PyResult<i128> {
+        let identifier: [u8; 32] = identifier
+            .try_into()
+            .map_err(|_| PyValueError::new_err("asset identifier must be 32 bytes"))?;
+        let atype = AssetType::from_identifier(&identifier)
+            .ok_or_else(|| PyValueError::new_err("invalid asset identifier"))?;
+        Ok(self.0.get(&atype))
+    }
+
+    /// Returns the components of this amount as a list of
+    /// `(identifier, value)` pairs.
+    fn components<'py>(&self, py: Python<'py>) -> Vec<(Bound<'py, PyBytes>, i128)> {
+        self.0
+            .components()
+            .map(|(atype, value)| (PyBytes::new_bound(py, atype.get_identifier()), *value))
+            .collect()
+    }
+
+    fn __add__(&self, other: &PyAmount) -> Self {
+        PyAmount(self.0.clone() + &other.0)
+    }
+
+    fn __sub__(&self, other: &PyAmount) -> Self {
+        PyAmount(self.0.clone() - &other.0)
+    }
+}
+
+/// The Python module definition.
+#[pymodule]
+fn masp_primitives(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyExtendedSpendingKey>()?;
+    m.add_class::<PyExtendedFullViewingKey>()?;
+    m.add_class::<PyDiversifierIndex>()?;
+    m.add_class::<PyPaymentAddress>()?;
+    m.add_class::<PyAmount>()?;
+    Ok(())
+}
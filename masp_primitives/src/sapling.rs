@@ -1,14 +1,16 @@
 //! Structs and constants specific to the Sapling shielded pool.
 
+pub mod address_proof;
+pub mod binding_sig;
 pub mod group_hash;
 pub mod keys;
 pub mod note_encryption;
 pub mod pedersen_hash;
 pub mod prover;
 pub mod redjubjub;
+pub mod scanning;
 pub mod util;
 
-use bitvec::{order::Lsb0, view::AsBits};
 use blake2s_simd::Params as Blake2sParams;
 use borsh::{BorshDeserialize, BorshSerialize};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
@@ -37,8 +39,7 @@ use crate::{
 };
 
 use self::{
-    group_hash::group_hash,
-    pedersen_hash::{pedersen_hash, Personalization},
+    pedersen_hash::{pedersen_hash, pedersen_hash_merkle, Personalization},
     redjubjub::{PrivateKey, PublicKey, Signature},
 };
 use borsh::schema::add_definition;
@@ -52,36 +53,10 @@ pub const SAPLING_COMMITMENT_TREE_DEPTH: usize = 32;
 
 /// Compute a parent node in the Sapling commitment tree given its two children.
 pub fn merkle_hash(depth: usize, lhs: &[u8; 32], rhs: &[u8; 32]) -> [u8; 32] {
-    let lhs = {
-        let mut tmp = [false; 256];
-        for (a, b) in tmp.iter_mut().zip(lhs.as_bits::<Lsb0>()) {
-            *a = *b;
-        }
-        tmp
-    };
-
-    let rhs = {
-        let mut tmp = [false; 256];
-        for (a, b) in tmp.iter_mut().zip(rhs.as_bits::<Lsb0>()) {
-            *a = *b;
-        }
-        tmp
-    };
-
-    jubjub::ExtendedPoint::from(pedersen_hash(
-        Personalization::MerkleTree(depth),
-        lhs.iter()
-            .copied()
-            .take(bls12_381::Scalar::NUM_BITS as usize)
-            .chain(
-                rhs.iter()
-                    .copied()
-                    .take(bls12_381::Scalar::NUM_BITS as usize),
-            ),
-    ))
-    .to_affine()
-    .get_u()
-    .to_repr()
+    jubjub::ExtendedPoint::from(pedersen_hash_merkle(depth, lhs, rhs))
+        .to_affine()
+        .get_u()
+        .to_repr()
 }
 
 /// A node within the Sapling commitment tree.
@@ -102,6 +77,13 @@ impl Node {
             repr: cmu.to_repr(),
         }
     }
+
+    /// Constructs a new note commitment tree node from an [`ExtractedNoteCommitment`]
+    pub fn from_cmu(cmu: &ExtractedNoteCommitment) -> Self {
+        Self {
+            repr: cmu.to_bytes(),
+        }
+    }
 }
 
 impl incrementalmerkletree::Hashable for Node {
@@ -195,6 +177,41 @@ impl ValueCommitment {
         (CofactorGroup::clear_cofactor(&self.asset_generator) * jubjub::Fr::from(self.value))
             + (constants::VALUE_COMMITMENT_RANDOMNESS_GENERATOR * self.randomness)
     }
+
+    /// Homomorphically adds `other` to this commitment's opening, so that the
+    /// commitment to the result is the sum of the two commitments.
+    ///
+    /// Returns `None` if the commitments are to different asset generators, or
+    /// if the combined value overflows.
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        if self.asset_generator != other.asset_generator {
+            return None;
+        }
+
+        Some(ValueCommitment {
+            asset_generator: self.asset_generator,
+            value: self.value.checked_add(other.value)?,
+            randomness: self.randomness + other.randomness,
+        })
+    }
+
+    /// Homomorphically subtracts `other` from this commitment's opening, so
+    /// that the commitment to the result is the difference of the two
+    /// commitments.
+    ///
+    /// Returns `None` if the commitments are to different asset generators, or
+    /// if the combined value underflows.
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        if self.asset_generator != other.asset_generator {
+            return None;
+        }
+
+        Some(ValueCommitment {
+            asset_generator: self.asset_generator,
+            value: self.value.checked_sub(other.value)?,
+            randomness: self.randomness - other.randomness,
+        })
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -347,6 +364,12 @@ impl ViewingKey {
         self.ivk().to_payment_address(diversifier)
     }
 
+    /// Computes the nullifier for the given note at the given position,
+    /// using this viewing key's nullifier deriving key.
+    pub fn nf(&self, note: &Note, position: u64) -> Nullifier {
+        note.nf(&self.nk, position)
+    }
+
     pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
         let ak = {
             let mut buf = [0u8; 32];
@@ -461,7 +484,7 @@ pub struct Diversifier(pub [u8; 11]);
 
 impl Diversifier {
     pub fn g_d(&self) -> Option<jubjub::SubgroupPoint> {
-        group_hash(&self.0, constants::KEY_DIVERSIFICATION_PERSONALIZATION)
+        group_hash::diversify_hash(&self.0)
     }
 }
 
@@ -550,13 +573,8 @@ impl PaymentAddress {
     }
 
     pub fn create_note(&self, asset_type: AssetType, value: u64, rseed: Rseed) -> Option<Note> {
-        self.g_d().map(|g_d| Note {
-            asset_type,
-            value,
-            rseed,
-            g_d,
-            pk_d: self.pk_d,
-        })
+        self.g_d()
+            .map(|g_d| Note::from_parts(asset_type, value, g_d, self.pk_d, rseed))
     }
 }
 
@@ -635,6 +653,21 @@ pub enum Rseed {
     AfterZip212([u8; 32]),
 }
 
+impl Rseed {
+    /// Derives the note commitment trapdoor `rcm`.
+    ///
+    /// Before ZIP 212 the trapdoor is carried directly; after ZIP 212 it is
+    /// derived from `rseed` via PRF^expand.
+    pub(crate) fn rcm(&self) -> jubjub::Fr {
+        match self {
+            Rseed::BeforeZip212(rcm) => *rcm,
+            Rseed::AfterZip212(rseed) => {
+                jubjub::Fr::from_bytes_wide(prf_expand(rseed, &[0x04]).as_array())
+            }
+        }
+    }
+}
+
 impl BorshSchema for Rseed {
     fn add_definitions_recursively(definitions: &mut BTreeMap<Declaration, Definition>) {
         let definition = Definition::Enum {
@@ -728,6 +761,54 @@ impl ConstantTimeEq for Nullifier {
     }
 }
 
+/// The u-coordinate of the note commitment, the form in which note commitments
+/// appear in output descriptions and the note commitment tree.
+///
+/// The wrapped scalar is guaranteed to be a canonical field element, so two
+/// extracted commitments are equal iff their byte encodings are equal.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExtractedNoteCommitment(pub(crate) bls12_381::Scalar);
+
+impl Hash for ExtractedNoteCommitment {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+impl ExtractedNoteCommitment {
+    /// Deserializes the extracted note commitment from a byte array.
+    ///
+    /// Returns `None` if the bytes are not a canonical encoding of a field
+    /// element.
+    pub fn from_bytes(bytes: &[u8; 32]) -> CtOption<Self> {
+        bls12_381::Scalar::from_repr(*bytes).map(ExtractedNoteCommitment)
+    }
+
+    /// Serializes the extracted note commitment to its canonical byte encoding.
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+}
+
+impl From<ExtractedNoteCommitment> for bls12_381::Scalar {
+    fn from(cmu: ExtractedNoteCommitment) -> Self {
+        cmu.0
+    }
+}
+
+impl From<&ExtractedNoteCommitment> for [u8; 32] {
+    fn from(cmu: &ExtractedNoteCommitment) -> Self {
+        cmu.to_bytes()
+    }
+}
+
+impl ConstantTimeEq for ExtractedNoteCommitment {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct NoteValue(u64);
 
@@ -764,6 +845,30 @@ pub struct Note<R = Rseed> {
     pub rseed: R,
 }
 
+impl<R> Note<R> {
+    /// Constructs a note from its constituent parts.
+    ///
+    /// Prefer this (or [`PaymentAddress::create_note`], which also checks that
+    /// the diversifier is valid) over filling in the struct fields directly;
+    /// `rcm` and `esk` are derived from `rseed` on demand and must not be
+    /// chosen independently of it.
+    pub fn from_parts(
+        asset_type: AssetType,
+        value: u64,
+        g_d: jubjub::SubgroupPoint,
+        pk_d: jubjub::SubgroupPoint,
+        rseed: R,
+    ) -> Self {
+        Note {
+            asset_type,
+            value,
+            g_d,
+            pk_d,
+            rseed,
+        }
+    }
+}
+
 impl PartialEq for Note {
     fn eq(&self, other: &Self) -> bool {
         self.value == other.value
@@ -775,6 +880,31 @@ impl PartialEq for Note {
 }
 
 impl Note {
+    /// Generates a random zero-valued note addressed to a throwaway address,
+    /// with a freshly sampled asset generator.
+    ///
+    /// Dummy notes are what the builder spends and outputs when padding a
+    /// bundle; exposing the sampling here lets test harnesses and padding
+    /// policies produce notes indistinguishable from the builder's own.
+    pub fn dummy<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let asset_type = {
+            let mut name = [0u8; 32];
+            rng.fill_bytes(&mut name);
+            AssetType::new(&name).expect("a valid asset generator nonce must exist")
+        };
+        let g_d = loop {
+            let mut d = [0u8; 11];
+            rng.fill_bytes(&mut d);
+            if let Some(g_d) = Diversifier(d).g_d() {
+                break g_d;
+            }
+        };
+        let pk_d = g_d * jubjub::Fr::random(&mut *rng);
+        let mut rseed = [0u8; 32];
+        rng.fill_bytes(&mut rseed);
+        Note::from_parts(asset_type, 0, g_d, pk_d, Rseed::AfterZip212(rseed))
+    }
+
     pub fn uncommitted() -> bls12_381::Scalar {
         // The smallest u-coordinate that is not on the curve
         // is one.
@@ -842,13 +972,14 @@ impl Note {
             .get_u()
     }
 
+    /// Computes the note commitment as it appears in output descriptions and
+    /// the note commitment tree.
+    pub fn cmstar(&self) -> ExtractedNoteCommitment {
+        ExtractedNoteCommitment(self.cmu())
+    }
+
     pub fn rcm(&self) -> jubjub::Fr {
-        match self.rseed {
-            Rseed::BeforeZip212(rcm) => rcm,
-            Rseed::AfterZip212(rseed) => {
-                jubjub::Fr::from_bytes_wide(prf_expand(&rseed, &[0x04]).as_array())
-            }
-        }
+        self.rseed.rcm()
     }
 
     pub fn generate_or_derive_esk<R: RngCore + CryptoRng>(&self, rng: &mut R) -> jubjub::Fr {
@@ -875,9 +1006,7 @@ impl Note {
     /// Returns [`self.cmu`] in the correct representation for inclusion in the Sapling
     /// note commitment tree.
     pub fn commitment(&self) -> Node {
-        Node {
-            repr: self.cmu().to_repr(),
-        }
+        Node::from_cmu(&self.cmstar())
     }
 }
 
@@ -1034,4 +1163,94 @@ mod tests {
             prop_assert_eq!(note, de_note);
         }
     }
+
+    #[test]
+    fn value_commitment_homomorphism() {
+        use crate::asset_type::AssetType;
+        use ff::Field;
+        use rand_core::OsRng;
+
+        let mut rng = OsRng;
+        let btc = AssetType::new(b"BTC").unwrap();
+        let eth = AssetType::new(b"ETH").unwrap();
+
+        let a = btc.value_commitment(60000, jubjub::Fr::random(&mut rng));
+        let b = btc.value_commitment(5000, jubjub::Fr::random(&mut rng));
+
+        let sum = a.checked_add(&b).unwrap();
+        assert_eq!(sum.value, 65000);
+        assert_eq!(sum.commitment(), a.commitment() + b.commitment());
+
+        let diff = a.checked_sub(&b).unwrap();
+        assert_eq!(diff.value, 55000);
+        assert_eq!(diff.commitment(), a.commitment() - b.commitment());
+
+        // Underflow and overflow are rejected.
+        assert!(b.checked_sub(&a).is_none());
+        let max = btc.value_commitment(u64::MAX, jubjub::Fr::random(&mut rng));
+        assert!(max.checked_add(&b).is_none());
+
+        // Commitments to different asset generators do not combine.
+        let c = eth.value_commitment(5000, jubjub::Fr::random(&mut rng));
+        assert!(a.checked_add(&c).is_none());
+        assert!(a.checked_sub(&c).is_none());
+    }
+
+    #[test]
+    fn payment_address_byte_encoding() {
+        use crate::sapling::{Diversifier, PaymentAddress};
+        use crate::zip32::ExtendedSpendingKey;
+        use group::{Group, GroupEncoding};
+
+        let addr = ExtendedSpendingKey::master(&[]).default_address().1;
+        let bytes = addr.to_bytes();
+        assert_eq!(PaymentAddress::from_bytes(&bytes), Some(addr));
+
+        // A diversifier with no corresponding group element is rejected.
+        let mut invalid_d = bytes;
+        let found = (0u8..=255).find(|&b| {
+            invalid_d[0] = b;
+            Diversifier(invalid_d[..11].try_into().unwrap())
+                .g_d()
+                .is_none()
+        });
+        assert!(found.is_some(), "no invalid diversifier found");
+        assert!(PaymentAddress::from_bytes(&invalid_d).is_none());
+
+        // `pk_d` must be a non-identity point of the prime-order subgroup.
+        let mut identity_pk_d = bytes;
+        identity_pk_d[11..].copy_from_slice(&jubjub::SubgroupPoint::identity().to_bytes());
+        assert!(PaymentAddress::from_bytes(&identity_pk_d).is_none());
+
+        // Non-canonical point encodings are rejected.
+        let mut bad_pk_d = bytes;
+        bad_pk_d[11..].copy_from_slice(&[0xff; 32]);
+        assert!(PaymentAddress::from_bytes(&bad_pk_d).is_none());
+    }
+
+    #[test]
+    fn extracted_note_commitment_canonical_bytes() {
+        use crate::sapling::{ExtractedNoteCommitment, Node};
+        use proptest::strategy::{Strategy, ValueTree};
+        use proptest::test_runner::TestRunner;
+
+        let mut runner = TestRunner::deterministic();
+        let note = arb_positive_note_value(MAX_MONEY)
+            .prop_flat_map(arb_note)
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+
+        // Extraction roundtrips through the canonical byte encoding and
+        // matches the commitment tree leaf.
+        let cmu = note.cmstar();
+        let parsed = ExtractedNoteCommitment::from_bytes(&cmu.to_bytes()).unwrap();
+        assert_eq!(parsed, cmu);
+        assert_eq!(Node::from_cmu(&cmu), note.commitment());
+
+        // Non-canonical field encodings are rejected.
+        assert!(bool::from(
+            ExtractedNoteCommitment::from_bytes(&[0xff; 32]).is_none()
+        ));
+    }
 }
@@ -0,0 +1,298 @@
+//! Binding signature construction over multi-asset value balances.
+//!
+//! The binding signature ties the value commitments of a bundle's spends,
+//! converts, and outputs to its declared value balance: the signing key `bsk`
+//! is the sum of the spend and convert commitment randomness minus the output
+//! commitment randomness, and its verification key `bvk` can be recomputed by
+//! anyone from the commitments and the value balance alone. [`TxProver`]
+//! implementations get this bookkeeping from their proving context; the
+//! [`BindingSigContext`] here exposes the same delicate arithmetic to
+//! alternative builders that assemble bundles without going through a prover.
+//!
+//! [`TxProver`]: crate::sapling::prover::TxProver
+
+use std::error;
+use std::fmt;
+
+use group::GroupEncoding;
+use rand_core::RngCore;
+
+use crate::asset_type::AssetType;
+use crate::constants::VALUE_COMMITMENT_RANDOMNESS_GENERATOR;
+use crate::sapling::{
+    redjubjub::{PrivateKey, PublicKey, Signature},
+    ValueCommitment,
+};
+use crate::transaction::components::I128Sum;
+
+/// Computes `value` in the exponent of the asset's value commitment base.
+///
+/// Returns `None` for the unrepresentable value `i128::MIN`.
+pub fn compute_value_balance(asset_type: AssetType, value: i128) -> Option<jubjub::ExtendedPoint> {
+    // Compute the absolute value (failing if -i128::MAX is the value)
+    let abs = match value.checked_abs() {
+        Some(a) => a as u128,
+        None => return None,
+    };
+
+    // Is it negative? We'll have to negate later if so.
+    let is_negative = value.is_negative();
+
+    // Compute it in the exponent
+    let mut abs_bytes = [0u8; 32];
+    abs_bytes[0..16].copy_from_slice(&abs.to_le_bytes());
+    let mut value_balance =
+        asset_type.value_commitment_generator() * jubjub::Fr::from_bytes(&abs_bytes).unwrap();
+
+    // Negate if necessary
+    if is_negative {
+        value_balance = -value_balance;
+    }
+
+    // Convert to unknown order point
+    Some(value_balance.into())
+}
+
+/// An error while producing or checking a binding signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingSigError {
+    /// A component of the value balance is the unrepresentable `i128::MIN`.
+    InvalidValueBalance,
+    /// The accumulated value commitments do not open to the declared value
+    /// balance, so `bsk` does not correspond to the recomputable `bvk`.
+    ValueBalanceMismatch,
+    /// The binding signature does not verify under the recomputed `bvk`.
+    InvalidSignature,
+}
+
+impl fmt::Display for BindingSigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingSigError::InvalidValueBalance => {
+                write!(f, "value balance component is out of range")
+            }
+            BindingSigError::ValueBalanceMismatch => write!(
+                f,
+                "value commitments do not open to the declared value balance"
+            ),
+            BindingSigError::InvalidSignature => write!(f, "invalid binding signature"),
+        }
+    }
+}
+
+impl error::Error for BindingSigError {}
+
+/// Recomputes `bvk` from a bundle's value commitments and declared value
+/// balance, as a verifier would.
+pub fn compute_bvk<'a>(
+    spend_cvs: impl IntoIterator<Item = &'a jubjub::ExtendedPoint>,
+    convert_cvs: impl IntoIterator<Item = &'a jubjub::ExtendedPoint>,
+    output_cvs: impl IntoIterator<Item = &'a jubjub::ExtendedPoint>,
+    value_balance: &I128Sum,
+) -> Result<PublicKey, BindingSigError> {
+    let mut cv_sum = jubjub::ExtendedPoint::identity();
+    for cv in spend_cvs {
+        cv_sum += cv;
+    }
+    for cv in convert_cvs {
+        cv_sum += cv;
+    }
+    for cv in output_cvs {
+        cv_sum -= cv;
+    }
+
+    let bvk = value_balance
+        .components()
+        .map(|(asset_type, value)| {
+            compute_value_balance(*asset_type, *value).ok_or(BindingSigError::InvalidValueBalance)
+        })
+        .try_fold(cv_sum, |tmp, value_balance| Ok(tmp - value_balance?))?;
+
+    Ok(PublicKey(bvk))
+}
+
+/// Checks a binding signature against a recomputed `bvk` and the sighash.
+pub fn verify_binding_sig(
+    bvk: &PublicKey,
+    sighash: &[u8; 32],
+    sig: &Signature,
+) -> Result<(), BindingSigError> {
+    if bvk.verify(
+        &binding_sig_message(bvk, sighash),
+        sig,
+        VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+    ) {
+        Ok(())
+    } else {
+        Err(BindingSigError::InvalidSignature)
+    }
+}
+
+/// Constructs the 64-byte message the binding signature commits to.
+fn binding_sig_message(bvk: &PublicKey, sighash: &[u8; 32]) -> [u8; 64] {
+    let mut data_to_be_signed = [0u8; 64];
+    data_to_be_signed[0..32].copy_from_slice(&bvk.0.to_bytes());
+    data_to_be_signed[32..64].copy_from_slice(sighash);
+    data_to_be_signed
+}
+
+/// Accumulates the value commitment openings of a bundle under construction
+/// and produces its binding signature.
+///
+/// Spends and converts enter positively, outputs negatively, matching the
+/// signs with which their commitments appear in `bvk` on the verifier side.
+pub struct BindingSigContext {
+    bsk: jubjub::Fr,
+    // (sum of the Spend and Convert value commitments) - (sum of the Output
+    // value commitments)
+    cv_sum: jubjub::ExtendedPoint,
+}
+
+impl Default for BindingSigContext {
+    fn default() -> Self {
+        BindingSigContext::new()
+    }
+}
+
+impl BindingSigContext {
+    /// Constructs an empty context for a single bundle.
+    pub fn new() -> Self {
+        BindingSigContext {
+            bsk: jubjub::Fr::zero(),
+            cv_sum: jubjub::ExtendedPoint::identity(),
+        }
+    }
+
+    /// Accumulates the value commitment of a spend description.
+    pub fn add_spend(&mut self, value_commitment: &ValueCommitment) {
+        self.bsk += value_commitment.randomness;
+        self.cv_sum += jubjub::ExtendedPoint::from(value_commitment.commitment());
+    }
+
+    /// Accumulates the value commitment of a convert description.
+    pub fn add_convert(&mut self, value_commitment: &ValueCommitment) {
+        self.add_spend(value_commitment);
+    }
+
+    /// Accumulates the value commitment of an output description.
+    pub fn add_output(&mut self, value_commitment: &ValueCommitment) {
+        self.bsk -= value_commitment.randomness;
+        self.cv_sum -= jubjub::ExtendedPoint::from(value_commitment.commitment());
+    }
+
+    /// The binding signing key accumulated so far.
+    pub fn bsk(&self) -> PrivateKey {
+        PrivateKey(self.bsk)
+    }
+
+    /// The binding verification key corresponding to [`Self::bsk`].
+    pub fn bvk(&self) -> PublicKey {
+        PublicKey::from_private(&self.bsk(), VALUE_COMMITMENT_RANDOMNESS_GENERATOR)
+    }
+
+    /// Signs the binding signature for the accumulated commitments and the
+    /// given value balance. All spends, converts, and outputs must have been
+    /// accumulated before calling this function.
+    ///
+    /// As an internal consistency check, the accumulated value commitments
+    /// are opened against `value_balance` as a verifier would; an error here
+    /// means the declared balance does not match the accumulated openings.
+    pub fn sign<R: RngCore>(
+        &self,
+        value_balance: &I128Sum,
+        sighash: &[u8; 32],
+        rng: &mut R,
+    ) -> Result<Signature, BindingSigError> {
+        let bsk = self.bsk();
+        let bvk = self.bvk();
+
+        let final_bvk = value_balance
+            .components()
+            .map(|(asset_type, value)| {
+                compute_value_balance(*asset_type, *value)
+                    .ok_or(BindingSigError::InvalidValueBalance)
+            })
+            .try_fold(self.cv_sum, |tmp, value_balance| Ok(tmp - value_balance?))?;
+
+        if bvk.0 != final_bvk {
+            return Err(BindingSigError::ValueBalanceMismatch);
+        }
+
+        Ok(bsk.sign(
+            &binding_sig_message(&bvk, sighash),
+            rng,
+            VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use rand_core::OsRng;
+
+    use super::{compute_bvk, verify_binding_sig, BindingSigContext, BindingSigError};
+    use crate::asset_type::AssetType;
+    use crate::transaction::components::I128Sum;
+
+    #[test]
+    fn binding_sig_roundtrip() {
+        let mut rng = OsRng;
+        let btc = AssetType::new(b"BTC").unwrap();
+        let eth = AssetType::new(b"ETH").unwrap();
+
+        // Spend 10 BTC, convert 3 ETH in, send 4 BTC and 3 ETH to outputs;
+        // the rest is the transparent value balance.
+        let spend = btc.value_commitment(10, jubjub::Fr::random(&mut rng));
+        let convert = eth.value_commitment(3, jubjub::Fr::random(&mut rng));
+        let output_btc = btc.value_commitment(4, jubjub::Fr::random(&mut rng));
+        let output_eth = eth.value_commitment(3, jubjub::Fr::random(&mut rng));
+        let value_balance = I128Sum::from_pair(btc, 6);
+
+        let mut ctx = BindingSigContext::new();
+        ctx.add_spend(&spend);
+        ctx.add_convert(&convert);
+        ctx.add_output(&output_btc);
+        ctx.add_output(&output_eth);
+
+        let sighash = [7u8; 32];
+        let sig = ctx.sign(&value_balance, &sighash, &mut rng).unwrap();
+
+        // A verifier recomputes bvk from the commitments alone.
+        let spend_cvs = [spend.commitment().into()];
+        let convert_cvs = [convert.commitment().into()];
+        let output_cvs = [
+            output_btc.commitment().into(),
+            output_eth.commitment().into(),
+        ];
+        let bvk = compute_bvk(&spend_cvs, &convert_cvs, &output_cvs, &value_balance).unwrap();
+
+        assert_eq!(bvk.0, ctx.bvk().0);
+        assert!(verify_binding_sig(&bvk, &sighash, &sig).is_ok());
+        assert_eq!(
+            verify_binding_sig(&bvk, &[8u8; 32], &sig).unwrap_err(),
+            BindingSigError::InvalidSignature
+        );
+    }
+
+    #[test]
+    fn sign_rejects_mismatched_value_balance() {
+        let mut rng = OsRng;
+        let btc = AssetType::new(b"BTC").unwrap();
+
+        let mut ctx = BindingSigContext::new();
+        ctx.add_spend(&btc.value_commitment(10, jubjub::Fr::random(&mut rng)));
+
+        let sighash = [7u8; 32];
+        assert_eq!(
+            ctx.sign(&I128Sum::from_pair(btc, 9), &sighash, &mut rng)
+                .unwrap_err(),
+            BindingSigError::ValueBalanceMismatch
+        );
+        assert_eq!(
+            ctx.sign(&I128Sum::from_pair(btc, i128::MIN), &sighash, &mut rng)
+                .unwrap_err(),
+            BindingSigError::InvalidValueBalance
+        );
+    }
+}
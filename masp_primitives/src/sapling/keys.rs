@@ -28,6 +28,7 @@ use subtle::CtOption;
 use super::{NullifierDerivingKey, ProofGenerationKey, ViewingKey};
 
 /// Errors that can occur in the decoding of Sapling spending keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DecodingError {
     /// The length of the byte slice provided for decoding was incorrect.
     LengthInvalid { expected: usize, actual: usize },
@@ -35,6 +36,8 @@ pub enum DecodingError {
     InvalidAsk,
     /// Could not decode the `nsk` bytes to a jubjub field element.
     InvalidNsk,
+    /// Could not decode the full viewing key bytes to valid group elements.
+    InvalidFvk,
 }
 
 /// An outgoing viewing key
@@ -108,7 +111,7 @@ impl ExpandedSpendingKey {
             DecodingError::InvalidNsk => {
                 io::Error::new(io::ErrorKind::InvalidData, "nsk not in field")
             }
-            DecodingError::LengthInvalid { .. } => unreachable!(),
+            DecodingError::LengthInvalid { .. } | DecodingError::InvalidFvk => unreachable!(),
         })
     }
 
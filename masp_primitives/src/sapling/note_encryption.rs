@@ -2,26 +2,38 @@
 use blake2b_simd::{Hash as Blake2bHash, Params as Blake2bParams};
 use byteorder::{LittleEndian, WriteBytesExt};
 use ff::PrimeField;
-use group::{cofactor::CofactorGroup, GroupEncoding, WnafBase, WnafScalar};
+use group::{cofactor::CofactorGroup, Group, GroupEncoding, WnafBase, WnafScalar};
 use jubjub::{AffinePoint, ExtendedPoint};
+use lazy_static::lazy_static;
 use memuse::DynamicUsage;
 use std::convert::TryInto;
 
 use crate::asset_type::AssetType;
 use masp_note_encryption::{
-    try_compact_note_decryption, try_note_decryption, try_output_recovery_with_ock,
-    try_output_recovery_with_ovk, BatchDomain, Domain, EphemeralKeyBytes, NoteEncryption,
-    NotePlaintextBytes, OutPlaintextBytes, OutgoingCipherKey, ShieldedOutput, COMPACT_NOTE_SIZE,
-    ENC_CIPHERTEXT_SIZE, NOTE_PLAINTEXT_SIZE, OUT_PLAINTEXT_SIZE,
+    try_compact_note_decryption, try_memoless_note_decryption, try_note_decryption,
+    try_output_recovery_with_ock, try_output_recovery_with_ovk, BatchDomain, Domain,
+    EphemeralKeyBytes, NoteEncryption, NotePlaintextBytes, OutPlaintextBytes, OutgoingCipherKey,
+    ShieldedOutput, COMPACT_NOTE_SIZE, ENC_CIPHERTEXT_SIZE, MEMOLESS_CIPHERTEXT_SIZE,
+    NOTE_PLAINTEXT_SIZE, OUT_PLAINTEXT_SIZE,
 };
 
 use crate::{
     consensus::{self, BlockHeight, NetworkUpgrade::MASP},
     memo::MemoBytes,
-    sapling::{keys::OutgoingViewingKey, Diversifier, Note, PaymentAddress, Rseed, SaplingIvk},
+    sapling::{
+        keys::OutgoingViewingKey, Diversifier, ExtractedNoteCommitment, Note, PaymentAddress,
+        Rseed, SaplingIvk,
+    },
     transaction::{components::sapling::OutputDescription, GrothProofBytes},
 };
 
+lazy_static! {
+    /// A fixed asset type substituted for invalid asset identifiers, so that
+    /// plaintext parsing performs the same work either way.
+    static ref FALLBACK_ASSET_TYPE: AssetType =
+        AssetType::new(b"fallback").expect("fallback asset type can be derived");
+}
+
 pub const KDF_SAPLING_PERSONALIZATION: &[u8; 16] = b"MASP__SaplingKDF";
 pub const PRF_OCK_PERSONALIZATION: &[u8; 16] = b"MASP__Derive_ock";
 
@@ -73,7 +85,10 @@ fn sapling_ka_agree_prepared(esk: &PreparedScalar, pk_d: &PreparedBase) -> jubju
 /// Sapling KDF for note encryption.
 ///
 /// Implements section 5.4.4.4 of the Zcash Protocol Specification.
-fn kdf_sapling(dhsecret: jubjub::SubgroupPoint, ephemeral_key: &EphemeralKeyBytes) -> Blake2bHash {
+pub(crate) fn kdf_sapling(
+    dhsecret: jubjub::SubgroupPoint,
+    ephemeral_key: &EphemeralKeyBytes,
+) -> Blake2bHash {
     Blake2bParams::new()
         .hash_length(32)
         .personal(KDF_SAPLING_PERSONALIZATION)
@@ -122,29 +137,66 @@ where
 {
     assert!(plaintext.len() >= COMPACT_NOTE_SIZE);
 
-    // Check note plaintext version
-    if !plaintext_version_is_valid(&domain.params, domain.height, plaintext[0]) {
-        return None;
-    }
+    // Failures are accumulated and applied at the single exit point, instead of
+    // returning early, so that plaintexts failing a cheap check (such as the
+    // version byte) still perform the group operations below and trial
+    // decryption takes the same time either way.
+    let version_is_valid = plaintext_version_is_valid(&domain.params, domain.height, plaintext[0]);
 
     // The unwraps below are guaranteed to succeed by the assertion above
     let diversifier = Diversifier(plaintext[1..12].try_into().unwrap());
     let value = u64::from_le_bytes(plaintext[12..20].try_into().unwrap());
-    let asset_type = AssetType::from_identifier(plaintext[20..52].try_into().unwrap())?;
+    let (asset_type, asset_type_is_valid) =
+        match AssetType::from_identifier(plaintext[20..52].try_into().unwrap()) {
+            Some(asset_type) => (asset_type, true),
+            None => (*FALLBACK_ASSET_TYPE, false),
+        };
     let r: [u8; 32] = plaintext[52..COMPACT_NOTE_SIZE].try_into().unwrap();
 
-    let rseed = if plaintext[0] == 0x01 {
-        let rcm = Option::from(jubjub::Fr::from_repr(r))?;
-        Rseed::BeforeZip212(rcm)
+    let (rseed, rseed_is_valid) = if plaintext[0] == 0x01 {
+        match Option::from(jubjub::Fr::from_repr(r)) {
+            Some(rcm) => (Rseed::BeforeZip212(rcm), true),
+            None => (Rseed::BeforeZip212(jubjub::Fr::zero()), false),
+        }
     } else {
-        Rseed::AfterZip212(r)
+        (Rseed::AfterZip212(r), true)
     };
 
-    let pk_d = get_validated_pk_d(&diversifier)?;
+    let (pk_d, pk_d_is_valid) = match get_validated_pk_d(&diversifier) {
+        Some(pk_d) => (pk_d, true),
+        None => (jubjub::SubgroupPoint::generator(), false),
+    };
+
+    let to = PaymentAddress::from_parts(diversifier, pk_d);
+    let (note, note_is_valid) = match to
+        .as_ref()
+        .and_then(|to| to.create_note(asset_type, value, rseed))
+    {
+        Some(note) => (note, true),
+        None => (
+            Note::from_parts(
+                asset_type,
+                value,
+                jubjub::SubgroupPoint::generator(),
+                pk_d,
+                rseed,
+            ),
+            false,
+        ),
+    };
 
-    let to = PaymentAddress::from_parts(diversifier, pk_d)?;
-    let note = to.create_note(asset_type, value, rseed)?;
-    Some((note, to))
+    if version_is_valid && asset_type_is_valid && rseed_is_valid && pk_d_is_valid && note_is_valid {
+        Some((note, to.expect("checked via note_is_valid")))
+    } else {
+        // Mirror the commitment and ephemeral-key work that
+        // `check_note_validity` performs on notes that parse successfully, so
+        // that rejected plaintexts take the same time as accepted ones.
+        let _ = note.cmstar();
+        if let Some(esk) = note.derive_esk() {
+            let _ = note.g_d * esk;
+        }
+        None
+    }
 }
 
 pub struct SaplingDomain<P: consensus::Parameters> {
@@ -188,7 +240,7 @@ impl<P: consensus::Parameters> Domain for SaplingDomain<P> {
     type IncomingViewingKey = PreparedIncomingViewingKey;
     type OutgoingViewingKey = OutgoingViewingKey;
     type ValueCommitment = jubjub::ExtendedPoint;
-    type ExtractedCommitment = bls12_381::Scalar;
+    type ExtractedCommitment = ExtractedNoteCommitment;
     type ExtractedCommitmentBytes = [u8; 32];
     type Memo = MemoBytes;
 
@@ -306,7 +358,14 @@ impl<P: consensus::Parameters> Domain for SaplingDomain<P> {
         plaintext: &[u8],
     ) -> Option<(Self::Note, Self::Recipient)> {
         sapling_parse_note_plaintext_without_memo(self, plaintext, |diversifier| {
-            Some(&PreparedBaseSubgroup::new(diversifier.g_d()?) * &ivk.0)
+            // Fall back to the subgroup generator for invalid diversifiers, so
+            // that the scalar multiplication is performed in either case.
+            let (g_d, diversifier_is_valid) = match diversifier.g_d() {
+                Some(g_d) => (g_d, true),
+                None => (jubjub::SubgroupPoint::generator(), false),
+            };
+            let pk_d = &PreparedBaseSubgroup::new(g_d) * &ivk.0;
+            diversifier_is_valid.then_some(pk_d)
         })
     }
 
@@ -327,7 +386,7 @@ impl<P: consensus::Parameters> Domain for SaplingDomain<P> {
     }
 
     fn cmstar(note: &Self::Note) -> Self::ExtractedCommitment {
-        note.cmu()
+        note.cmstar()
     }
 
     fn extract_pk_d(op: &OutPlaintextBytes) -> Option<Self::DiversifiedTransmissionKey> {
@@ -441,7 +500,7 @@ impl<P: consensus::Parameters> BatchDomain for SaplingDomain<P> {
 /// let height = TEST_NETWORK.activation_height(NetworkUpgrade::MASP).unwrap();
 /// let rseed = generate_random_rseed(&TEST_NETWORK, height, &mut rng);
 /// let note = to.create_note(asset_type, value, rseed).unwrap();
-/// let cmu = note.cmu();
+/// let cmu = note.cmstar();
 ///
 /// let mut enc = sapling_note_encryption::<TestNetwork>(ovk, note, to, MemoBytes::empty());
 /// let encCiphertext = enc.encrypt_note_plaintext();
@@ -474,6 +533,11 @@ pub fn plaintext_version_is_valid<P: consensus::Parameters>(
     }
 }
 
+/// Trial decryption of a full Sapling note by the recipient.
+///
+/// Failed trial decryptions perform the same key agreement, cipher, tag-check,
+/// and parsing work as successful ones (see [`try_note_decryption`]), so the
+/// time spent scanning an output does not reveal whether it belongs to `ivk`.
 pub fn try_sapling_note_decryption<
     P: consensus::Parameters,
     Output: ShieldedOutput<SaplingDomain<P>, ENC_CIPHERTEXT_SIZE>,
@@ -507,6 +571,23 @@ pub fn try_sapling_compact_note_decryption<
     try_compact_note_decryption(&domain, ivk, output)
 }
 
+pub fn try_sapling_memoless_note_decryption<
+    P: consensus::Parameters,
+    Output: ShieldedOutput<SaplingDomain<P>, MEMOLESS_CIPHERTEXT_SIZE>,
+>(
+    params: &P,
+    height: BlockHeight,
+    ivk: &PreparedIncomingViewingKey,
+    output: &Output,
+) -> Option<(Note, PaymentAddress)> {
+    let domain = SaplingDomain {
+        params: params.clone(),
+        height,
+    };
+
+    try_memoless_note_decryption(&domain, ivk, output)
+}
+
 /// Recovery of the full note plaintext by the sender.
 ///
 /// Attempts to decrypt and validate the given `enc_ciphertext` using the given `ock`.
@@ -571,8 +652,9 @@ mod tests {
 
     use super::{
         epk_bytes, kdf_sapling, prf_ock, sapling_ka_agree, sapling_note_encryption,
-        try_sapling_compact_note_decryption, try_sapling_note_decryption,
-        try_sapling_output_recovery, try_sapling_output_recovery_with_ock, SaplingDomain,
+        try_sapling_compact_note_decryption, try_sapling_memoless_note_decryption,
+        try_sapling_note_decryption, try_sapling_output_recovery,
+        try_sapling_output_recovery_with_ock, SaplingDomain,
     };
 
     use crate::{
@@ -586,9 +668,11 @@ mod tests {
             note_encryption::{AssetType, PreparedIncomingViewingKey},
             util::generate_random_rseed,
         },
-        sapling::{Diversifier, PaymentAddress, Rseed, SaplingIvk},
+        sapling::{Diversifier, ExtractedNoteCommitment, PaymentAddress, Rseed, SaplingIvk},
         transaction::components::{
-            sapling::{self, CompactOutputDescription, OutputDescription},
+            sapling::{
+                self, CompactOutputDescription, MemolessOutputDescription, OutputDescription,
+            },
             GROTH_PROOF_SIZE,
         },
     };
@@ -653,12 +737,12 @@ mod tests {
         let rseed = generate_random_rseed(&TEST_NETWORK, height, &mut rng);
 
         let note = pa.create_note(asset_type, value, rseed).unwrap();
-        let cmu = note.cmu();
+        let cmu = note.cmstar();
 
         let ovk = OutgoingViewingKey([0; 32]);
         let ne = sapling_note_encryption::<TestNetwork>(Some(ovk), note, pa, MemoBytes::empty());
         let epk = *ne.epk();
-        let ock = prf_ock(&ovk, &cv, &cmu.to_repr(), &epk_bytes(&epk));
+        let ock = prf_ock(&ovk, &cv, &cmu.to_bytes(), &epk_bytes(&epk));
 
         let output = OutputDescription {
             cv,
@@ -675,13 +759,13 @@ mod tests {
     fn reencrypt_enc_ciphertext(
         ovk: &OutgoingViewingKey,
         cv: &jubjub::ExtendedPoint,
-        cmu: &bls12_381::Scalar,
+        cmu: &ExtractedNoteCommitment,
         ephemeral_key: &EphemeralKeyBytes,
         enc_ciphertext: &mut [u8; ENC_CIPHERTEXT_SIZE],
         out_ciphertext: &[u8; OUT_CIPHERTEXT_SIZE],
         modify_plaintext: impl Fn(&mut [u8; NOTE_PLAINTEXT_SIZE]),
     ) {
-        let ock = prf_ock(ovk, cv, &cmu.to_repr(), ephemeral_key);
+        let ock = prf_ock(ovk, cv, &cmu.to_bytes(), ephemeral_key);
 
         let mut op = [0; OUT_PLAINTEXT_SIZE];
         op.copy_from_slice(&out_ciphertext[..OUT_PLAINTEXT_SIZE]);
@@ -778,6 +862,49 @@ mod tests {
         }
     }
 
+    /// Statistical check that a failed trial decryption takes about as long as
+    /// a successful one. Timing measurements are noisy under load, so this is
+    /// ignored by default; run it manually with
+    /// `cargo test --release -- --ignored decryption_failure_timing`.
+    #[test]
+    #[ignore]
+    fn decryption_failure_timing_matches_success() {
+        use std::time::Instant;
+
+        let mut rng = OsRng;
+        let height = TEST_NETWORK.activation_height(MASP).unwrap();
+
+        let (_, _, ivk, output) = random_enc_ciphertext(height, &mut rng);
+        let other_ivk = PreparedIncomingViewingKey::new(&SaplingIvk(jubjub::Fr::random(&mut rng)));
+
+        const SAMPLES: usize = 100;
+        let median = |ivk: &PreparedIncomingViewingKey| {
+            let mut times: Vec<_> = (0..SAMPLES)
+                .map(|_| {
+                    let start = Instant::now();
+                    let _ = try_sapling_note_decryption(&TEST_NETWORK, height, ivk, &output);
+                    start.elapsed()
+                })
+                .collect();
+            times.sort();
+            times[SAMPLES / 2]
+        };
+
+        // Warm up caches before measuring.
+        let _ = (median(&ivk), median(&other_ivk));
+
+        let success = median(&ivk);
+        let failure = median(&other_ivk);
+        let ratio = failure.as_secs_f64() / success.as_secs_f64();
+        assert!(
+            (0.8..1.25).contains(&ratio),
+            "failure/success timing ratio {} is outside tolerance (success {:?}, failure {:?})",
+            ratio,
+            success,
+            failure,
+        );
+    }
+
     #[test]
     fn decryption_with_invalid_epk() {
         let mut rng = OsRng;
@@ -802,7 +929,7 @@ mod tests {
 
         for &height in heights.iter() {
             let (_, _, ivk, mut output) = random_enc_ciphertext(height, &mut rng);
-            output.cmu = bls12_381::Scalar::random(&mut rng);
+            output.cmu = ExtractedNoteCommitment(bls12_381::Scalar::random(&mut rng));
 
             assert_eq!(
                 try_sapling_note_decryption(&TEST_NETWORK, height, &ivk, &output),
@@ -953,7 +1080,7 @@ mod tests {
 
         for &height in heights.iter() {
             let (_, _, ivk, mut output) = random_enc_ciphertext(height, &mut rng);
-            output.cmu = bls12_381::Scalar::random(&mut rng);
+            output.cmu = ExtractedNoteCommitment(bls12_381::Scalar::random(&mut rng));
 
             assert_eq!(
                 try_sapling_compact_note_decryption(
@@ -1059,6 +1186,93 @@ mod tests {
         }
     }
 
+    fn random_memoless_output<R: RngCore + CryptoRng>(
+        height: BlockHeight,
+        ivk: &SaplingIvk,
+        mut rng: &mut R,
+    ) -> (
+        crate::sapling::Note,
+        PaymentAddress,
+        MemolessOutputDescription,
+    ) {
+        let diversifier = Diversifier([10u8; 11]);
+        let pk_d = diversifier.g_d().unwrap() * ivk.0;
+        let pa = PaymentAddress::from_parts_unchecked(diversifier, pk_d);
+
+        let asset_type = AssetType::new("BTC".as_bytes()).unwrap();
+        let rseed = generate_random_rseed(&TEST_NETWORK, height, &mut rng);
+        let note = pa.create_note(asset_type, 100, rseed).unwrap();
+        let cmu = note.cmstar();
+
+        let ne = sapling_note_encryption::<TestNetwork>(None, note, pa, MemoBytes::empty());
+        let output = MemolessOutputDescription {
+            ephemeral_key: ne.epk().to_bytes().into(),
+            cmu,
+            enc_ciphertext: ne.encrypt_memoless_note_plaintext(),
+        };
+
+        (note, pa, output)
+    }
+
+    #[test]
+    fn memoless_decryption() {
+        let mut rng = OsRng;
+        let height = TEST_NETWORK.activation_height(MASP).unwrap();
+
+        let ivk = SaplingIvk(jubjub::Fr::random(&mut rng));
+        let (note, pa, output) = random_memoless_output(height, &ivk, &mut rng);
+
+        assert_eq!(
+            try_sapling_memoless_note_decryption(
+                &TEST_NETWORK,
+                height,
+                &PreparedIncomingViewingKey::new(&ivk),
+                &output,
+            ),
+            Some((note, pa))
+        );
+    }
+
+    #[test]
+    fn memoless_decryption_with_invalid_ivk() {
+        let mut rng = OsRng;
+        let height = TEST_NETWORK.activation_height(MASP).unwrap();
+
+        let ivk = SaplingIvk(jubjub::Fr::random(&mut rng));
+        let (_, _, output) = random_memoless_output(height, &ivk, &mut rng);
+
+        assert_eq!(
+            try_sapling_memoless_note_decryption(
+                &TEST_NETWORK,
+                height,
+                &PreparedIncomingViewingKey::new(&SaplingIvk(jubjub::Fr::random(&mut rng))),
+                &output,
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn memoless_decryption_with_invalid_tag() {
+        let mut rng = OsRng;
+        let height = TEST_NETWORK.activation_height(MASP).unwrap();
+
+        let ivk = SaplingIvk(jubjub::Fr::random(&mut rng));
+        let (_, _, mut output) = random_memoless_output(height, &ivk, &mut rng);
+        output.enc_ciphertext[masp_note_encryption::MEMOLESS_CIPHERTEXT_SIZE - 1] ^= 0xff;
+
+        // Unlike a compact output, a memo-less output is authenticated.
+        assert_eq!(
+            try_sapling_memoless_note_decryption(
+                &TEST_NETWORK,
+                height,
+                &PreparedIncomingViewingKey::new(&ivk),
+                &output,
+            ),
+            None
+        );
+    }
+
     #[test]
     fn recovery_with_invalid_ovk() {
         let mut rng = OsRng;
@@ -1118,7 +1332,7 @@ mod tests {
 
         for &height in heights.iter() {
             let (ovk, ock, _, mut output) = random_enc_ciphertext(height, &mut rng);
-            output.cmu = bls12_381::Scalar::random(&mut rng);
+            output.cmu = ExtractedNoteCommitment(bls12_381::Scalar::random(&mut rng));
 
             assert_eq!(
                 try_sapling_output_recovery(&TEST_NETWORK, height, &ovk, &output,),
@@ -1364,7 +1578,7 @@ mod tests {
 
             let output = OutputDescription {
                 cv,
-                cmu,
+                cmu: ExtractedNoteCommitment(cmu),
                 ephemeral_key,
                 enc_ciphertext: tv.c_enc,
                 out_ciphertext: tv.c_out,
@@ -1463,7 +1677,7 @@ mod tests {
 
             assert_eq!(ne.encrypt_note_plaintext().as_ref(), &tv.c_enc[..]);
             assert_eq!(
-                &ne.encrypt_outgoing_plaintext(&cv, &cmu, &mut OsRng)[..],
+                &ne.encrypt_outgoing_plaintext(&cv, &ExtractedNoteCommitment(cmu), &mut OsRng)[..],
                 &tv.c_out[..]
             );
         }
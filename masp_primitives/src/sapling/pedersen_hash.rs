@@ -80,42 +80,145 @@ where
             break;
         }
 
-        let mut table: &[Vec<jubjub::SubgroupPoint>] =
-            generators.next().expect("we don't have enough generators");
-        let window = PEDERSEN_HASH_EXP_WINDOW_SIZE as usize;
-        let window_mask = (1u64 << window) - 1;
-
-        let acc = acc.to_repr();
-        let num_limbs: usize = acc.as_ref().len() / 8;
-        let mut limbs = vec![0u64; num_limbs + 1];
-        LittleEndian::read_u64_into(acc.as_ref(), &mut limbs[..num_limbs]);
-
-        let mut tmp = jubjub::SubgroupPoint::identity();
-
-        let mut pos = 0;
-        while pos < jubjub::Fr::NUM_BITS as usize {
-            let u64_idx = pos / 64;
-            let bit_idx = pos % 64;
-            let i = (if bit_idx + window < 64 {
-                // This window's bits are contained in a single u64.
+        result += generator_exp(
+            generators.next().expect("we don't have enough generators"),
+            &acc,
+        );
+    }
+
+    result
+}
+
+/// Multiplies a chunk-accumulated scalar by a fixed generator using its
+/// precomputed window table.
+fn generator_exp(table: &[Vec<jubjub::SubgroupPoint>], acc: &jubjub::Fr) -> jubjub::SubgroupPoint {
+    let mut table = table;
+    let window = PEDERSEN_HASH_EXP_WINDOW_SIZE as usize;
+    let window_mask = (1u64 << window) - 1;
+
+    let acc = acc.to_repr();
+    let num_limbs: usize = acc.as_ref().len() / 8;
+    let mut limbs = vec![0u64; num_limbs + 1];
+    LittleEndian::read_u64_into(acc.as_ref(), &mut limbs[..num_limbs]);
+
+    let mut tmp = jubjub::SubgroupPoint::identity();
+
+    let mut pos = 0;
+    while pos < jubjub::Fr::NUM_BITS as usize {
+        let u64_idx = pos / 64;
+        let bit_idx = pos % 64;
+        let i = (if bit_idx + window < 64 {
+            // This window's bits are contained in a single u64.
+            limbs[u64_idx] >> bit_idx
+        } else {
+            // Combine the current u64's bits with the bits from the next u64.
+            (limbs[u64_idx] >> bit_idx) | (limbs[u64_idx + 1] << (64 - bit_idx))
+        } & window_mask) as usize;
+
+        tmp += table[0][i];
+
+        pos += window;
+        table = &table[1..];
+    }
+
+    tmp
+}
+
+/// The number of bits hashed by [`pedersen_hash_merkle`]: the 6-bit
+/// personalization followed by two 255-bit child representations.
+const MERKLE_HASH_BITS: usize = 6 + 2 * 255;
+
+/// Computes the Pedersen hash of a Merkle tree parent node directly from the
+/// packed little-endian representations of its children.
+///
+/// This is equivalent to calling [`pedersen_hash`] with
+/// [`Personalization::MerkleTree`] and the low 255 bits of each child, but
+/// draws the 3-bit chunks straight from the packed words instead of
+/// materializing per-bit booleans, which makes it noticeably faster on the
+/// commitment tree hot path.
+pub fn pedersen_hash_merkle(depth: usize, lhs: &[u8; 32], rhs: &[u8; 32]) -> jubjub::SubgroupPoint {
+    assert!(depth < 63);
+
+    // Pack personalization ++ lhs[..255] ++ rhs[..255] into little-endian
+    // words, with one spare word so window reads never run off the end.
+    let mut limbs = [0u64; 10];
+    limbs[0] = depth as u64;
+    or_bits_at(&mut limbs, 6, lhs);
+    or_bits_at(&mut limbs, 6 + 255, rhs);
+
+    let mut result = jubjub::SubgroupPoint::identity();
+    let mut generators = PEDERSEN_HASH_EXP_TABLE.iter();
+
+    let mut chunk = 0;
+    let total_chunks = (MERKLE_HASH_BITS + 2) / 3;
+    while chunk < total_chunks {
+        let mut acc = jubjub::Fr::zero();
+        let mut cur = jubjub::Fr::one();
+
+        for _ in 0..PEDERSEN_HASH_CHUNKS_PER_GENERATOR {
+            if chunk == total_chunks {
+                break;
+            }
+
+            // Grab three bits from the packed input. Bits past the end of the
+            // input read as zero, matching the iterator-based implementation.
+            let bit_pos = chunk * 3;
+            let u64_idx = bit_pos / 64;
+            let bit_idx = bit_pos % 64;
+            let bits = if bit_idx + 3 <= 64 {
                 limbs[u64_idx] >> bit_idx
             } else {
-                // Combine the current u64's bits with the bits from the next u64.
                 (limbs[u64_idx] >> bit_idx) | (limbs[u64_idx + 1] << (64 - bit_idx))
-            } & window_mask) as usize;
+            };
 
-            tmp += table[0][i];
+            // Start computing this portion of the scalar
+            let mut tmp = cur;
+            if bits & 1 != 0 {
+                tmp.add_assign(&cur);
+            }
+            cur = cur.double(); // 2^1 * cur
+            if bits & 2 != 0 {
+                tmp.add_assign(&cur);
+            }
+
+            // conditionally negate
+            if bits & 4 != 0 {
+                tmp = tmp.neg();
+            }
+
+            acc.add_assign(&tmp);
 
-            pos += window;
-            table = &table[1..];
+            chunk += 1;
+            cur = cur.double().double().double(); // 2^4 * cur
         }
 
-        result += tmp;
+        result += generator_exp(
+            generators.next().expect("we don't have enough generators"),
+            &acc,
+        );
     }
 
     result
 }
 
+/// ORs the low 255 bits of a packed little-endian 32-byte value into `limbs`
+/// starting at bit `offset`.
+fn or_bits_at(limbs: &mut [u64; 10], offset: usize, bytes: &[u8; 32]) {
+    let mut src = [0u64; 4];
+    LittleEndian::read_u64_into(bytes, &mut src);
+    // Drop the 256th bit; only the low 255 bits are hashed.
+    src[3] &= u64::MAX >> 1;
+
+    let u64_idx = offset / 64;
+    let bit_idx = offset % 64;
+    for (i, s) in src.iter().enumerate() {
+        limbs[u64_idx + i] |= s << bit_idx;
+        if bit_idx > 0 {
+            limbs[u64_idx + i + 1] |= s >> (64 - bit_idx);
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use group::Curve;
@@ -152,4 +255,34 @@ pub mod test {
             assert_eq!(p.get_v().to_string(), v.hash_v);
         }
     }
+
+    #[test]
+    fn test_pedersen_hash_merkle_matches_generic() {
+        let patterns: [[u8; 32]; 4] = [
+            [0u8; 32],
+            [0xffu8; 32],
+            core::array::from_fn(|i| i as u8),
+            core::array::from_fn(|i| 0xa5u8.wrapping_mul(i as u8 + 1)),
+        ];
+
+        for depth in [0, 1, 31, 62] {
+            for lhs in &patterns {
+                for rhs in &patterns {
+                    let expected = pedersen_hash(
+                        Personalization::MerkleTree(depth),
+                        lhs.iter()
+                            .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+                            .take(255)
+                            .chain(
+                                rhs.iter()
+                                    .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+                                    .take(255),
+                            ),
+                    );
+
+                    assert_eq!(pedersen_hash_merkle(depth, lhs, rhs), expected);
+                }
+            }
+        }
+    }
 }
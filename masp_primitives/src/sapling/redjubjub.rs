@@ -144,6 +144,29 @@ impl PrivateKey {
         // r = H*(T || M)
         let r = h_star(&t[..], msg);
 
+        self.sign_with_nonce(r, msg, p_g)
+    }
+
+    /// Signs `msg` with a nonce derived deterministically from the key and
+    /// the message, in the spirit of [RFC 6979].
+    ///
+    /// The nonce is `r = H(sk || M)` under an instance of the hash
+    /// domain-separated from the verification hash, so the same key and
+    /// message always yield the same signature while distinct messages yield
+    /// independent nonces. This lets air-gapped signers without a reliable
+    /// entropy source produce spendAuthSig and binding signatures with no
+    /// risk of nonce reuse. Signatures are indistinguishable from (and
+    /// verify identically to) those produced by [`Self::sign`].
+    ///
+    /// [RFC 6979]: https://www.rfc-editor.org/rfc/rfc6979
+    pub fn sign_deterministic(&self, msg: &[u8], p_g: SubgroupPoint) -> Signature {
+        // r = H(sk || M)
+        let r = hash_to_scalar(b"MASP__RedJubjubN", self.0.to_repr().as_ref(), msg);
+
+        self.sign_with_nonce(r, msg, p_g)
+    }
+
+    fn sign_with_nonce(&self, r: jubjub::Fr, msg: &[u8], p_g: SubgroupPoint) -> Signature {
         // R = r . P_G
         let r_g = p_g * r;
         let rbar = r_g.to_bytes();
@@ -229,6 +252,112 @@ impl PublicKey {
     }
 }
 
+/// A signer's secret nonce for one round of aggregated signing.
+///
+/// A nonce authorizes exactly one signature share; [`PrivateKey::sign_share`]
+/// consumes it so it cannot be reused, which would leak the key share.
+pub struct SigningNonce(jubjub::Fr);
+
+/// The public commitment to a [`SigningNonce`], shared with co-signers in the
+/// first round of aggregated signing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NonceCommitment(ExtendedPoint);
+
+impl SigningNonce {
+    /// Samples a fresh nonce for one signing round.
+    pub fn random<R: RngCore>(rng: &mut R) -> Self {
+        // T = (l_H + 128) bits of randomness, reduced like the nonce in
+        // single-signer signing
+        let mut t = [0u8; 80];
+        rng.fill_bytes(&mut t[..]);
+        SigningNonce(h_star(&t[..], &[]))
+    }
+
+    /// Returns the commitment to broadcast to co-signers.
+    pub fn commitment(&self, p_g: SubgroupPoint) -> NonceCommitment {
+        NonceCommitment((p_g * self.0).into())
+    }
+}
+
+impl NonceCommitment {
+    pub fn read<R: Read>(reader: R) -> io::Result<Self> {
+        PublicKey::read(reader).map(|pk| NonceCommitment(pk.0))
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.0.to_bytes())
+    }
+}
+
+/// A signer's second-round contribution to an aggregated signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignatureShare(jubjub::Fr);
+
+impl SignatureShare {
+    pub fn read<R: Read>(reader: R) -> io::Result<Self> {
+        read_scalar(reader).map(SignatureShare)
+    }
+
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        write_scalar(&self.0, writer)
+    }
+}
+
+/// Aggregates the co-signers' first-round commitments into the commitment of
+/// the final signature.
+pub fn aggregate_nonce_commitments(commitments: &[NonceCommitment]) -> NonceCommitment {
+    NonceCommitment(
+        commitments
+            .iter()
+            .fold(ExtendedPoint::identity(), |acc, c| acc + c.0),
+    )
+}
+
+/// Combines the co-signers' shares into a [`Signature`] that verifies under
+/// the sum of their verification keys.
+///
+/// This implements the second half of a two-round aggregated signing
+/// protocol for keys that are additive shares of a joint key, as the binding
+/// signature key of a multi-party transaction is (each builder's share is
+/// determined by its own `rcv` values). No MuSig-style key-aggregation
+/// coefficients are needed: verifiers recompute the joint verification key
+/// from the transaction's value commitments, so a contributor cannot choose
+/// its key share as a function of the others'.
+pub fn aggregate_signature_shares(
+    aggregated_nonce: &NonceCommitment,
+    shares: &[SignatureShare],
+) -> Signature {
+    let s = shares
+        .iter()
+        .fold(jubjub::Fr::zero(), |acc, share| acc + share.0);
+    let mut sbar = [0u8; 32];
+    write_scalar::<&mut [u8]>(&s, &mut sbar[..])
+        .expect("Jubjub scalars should serialize to 32 bytes");
+    Signature {
+        rbar: aggregated_nonce.0.to_bytes(),
+        sbar,
+    }
+}
+
+impl PrivateKey {
+    /// Produces this signer's share of an aggregated signature over `msg`,
+    /// consuming the nonce whose commitment entered `aggregated_nonce`.
+    ///
+    /// All signers must pass the same aggregated nonce commitment, obtained
+    /// from [`aggregate_nonce_commitments`] over every co-signer's
+    /// first-round commitment.
+    pub fn sign_share(
+        &self,
+        nonce: SigningNonce,
+        aggregated_nonce: &NonceCommitment,
+        msg: &[u8],
+    ) -> SignatureShare {
+        // s_i = r_i + H*(Rbar || M) . sk_i
+        let c = h_star(&aggregated_nonce.0.to_bytes(), msg);
+        SignatureShare(nonce.0 + c * self.0)
+    }
+}
+
 pub struct BatchEntry<'a> {
     vk: PublicKey,
     msg: &'a [u8],
@@ -281,7 +410,7 @@ mod tests {
     use rand_xorshift::XorShiftRng;
 
     use super::*;
-    use crate::constants::SPENDING_KEY_GENERATOR;
+    use crate::constants::{SPENDING_KEY_GENERATOR, VALUE_COMMITMENT_RANDOMNESS_GENERATOR};
 
     #[test]
     fn test_batch_verify() {
@@ -400,6 +529,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn aggregated_binding_signature() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let p_g = VALUE_COMMITMENT_RANDOMNESS_GENERATOR;
+        let msg = b"binding sighash";
+
+        // Two builders each hold an additive share of bsk; verifiers see
+        // only the joint verification key.
+        let sk1 = PrivateKey(jubjub::Fr::random(&mut rng));
+        let sk2 = PrivateKey(jubjub::Fr::random(&mut rng));
+        let joint_vk =
+            PublicKey(PublicKey::from_private(&sk1, p_g).0 + PublicKey::from_private(&sk2, p_g).0);
+
+        // Round 1: exchange nonce commitments.
+        let nonce1 = SigningNonce::random(&mut rng);
+        let nonce2 = SigningNonce::random(&mut rng);
+        let aggregated_nonce =
+            aggregate_nonce_commitments(&[nonce1.commitment(p_g), nonce2.commitment(p_g)]);
+
+        // Round 2: exchange signature shares and combine.
+        let share1 = sk1.sign_share(nonce1, &aggregated_nonce, msg);
+        let share2 = sk2.sign_share(nonce2, &aggregated_nonce, msg);
+        let sig = aggregate_signature_shares(&aggregated_nonce, &[share1, share2]);
+        assert!(joint_vk.verify(msg, &sig, p_g));
+
+        // A missing or tampered share yields an invalid signature.
+        assert!(!joint_vk.verify(
+            msg,
+            &aggregate_signature_shares(&aggregated_nonce, &[share1]),
+            p_g
+        ));
+        let mut share_bytes = [0u8; 32];
+        share2.write(&mut share_bytes[..]).unwrap();
+        share_bytes[0] ^= 1;
+        let tampered = SignatureShare::read(&share_bytes[..]).unwrap();
+        assert!(!joint_vk.verify(
+            msg,
+            &aggregate_signature_shares(&aggregated_nonce, &[share1, tampered]),
+            p_g
+        ));
+    }
+
+    #[test]
+    fn deterministic_signatures() {
+        let mut rng = XorShiftRng::from_seed([
+            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
+            0xbc, 0xe5,
+        ]);
+        let p_g = SPENDING_KEY_GENERATOR;
+
+        let sk = PrivateKey(jubjub::Fr::random(&mut rng));
+        let vk = PublicKey::from_private(&sk, p_g);
+
+        let msg1 = b"Foo bar";
+        let msg2 = b"Spam eggs";
+
+        // The same key and message always yield the same signature, and it
+        // verifies like a randomized one.
+        let sig1 = sk.sign_deterministic(msg1, p_g);
+        assert_eq!(sig1, sk.sign_deterministic(msg1, p_g));
+        assert!(vk.verify(msg1, &sig1, p_g));
+        assert!(!vk.verify(msg2, &sig1, p_g));
+
+        // Distinct messages and distinct keys yield distinct nonces.
+        let sig2 = sk.sign_deterministic(msg2, p_g);
+        assert!(vk.verify(msg2, &sig2, p_g));
+        assert_ne!(sig1.rbar, sig2.rbar);
+
+        let sk2 = PrivateKey(jubjub::Fr::random(&mut rng));
+        assert_ne!(sig1.rbar, sk2.sign_deterministic(msg1, p_g).rbar);
+    }
+
     #[test]
     fn random_signatures() {
         let mut rng = XorShiftRng::from_seed([
@@ -0,0 +1,279 @@
+//! Serde implementations for addresses and viewing keys.
+//!
+//! Human-readable serializers (e.g. JSON, TOML) see bech32m strings with a
+//! type-specific prefix; binary serializers see the raw byte encodings used
+//! by the `read`/`write` APIs. This lets these types live directly in
+//! JSON-RPC responses and config files without bespoke wrapper types.
+
+use bech32::{FromBase32, ToBase32, Variant};
+use ff::PrimeField;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::sapling::{
+    keys::{FullViewingKey, OutgoingViewingKey},
+    Nullifier, PaymentAddress, SaplingIvk,
+};
+use crate::zip32::sapling::ExtendedFullViewingKey;
+
+/// The bech32m human-readable prefix for payment addresses.
+const HRP_PAYMENT_ADDRESS: &str = "masp";
+
+/// The bech32m human-readable prefix for full viewing keys.
+const HRP_FULL_VIEWING_KEY: &str = "maspfvk";
+
+/// The bech32m human-readable prefix for extended full viewing keys.
+const HRP_EXTENDED_FULL_VIEWING_KEY: &str = "maspxfvk";
+
+/// The bech32m human-readable prefix for incoming-only viewing keys.
+const HRP_INCOMING_VIEWING_KEY: &str = "maspivk";
+
+/// The bech32m human-readable prefix for outgoing-only viewing keys.
+const HRP_OUTGOING_VIEWING_KEY: &str = "maspovk";
+
+fn serialize_encoded<S: Serializer>(
+    serializer: S,
+    hrp: &str,
+    bytes: &[u8],
+) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        let encoded =
+            bech32::encode(hrp, bytes.to_base32(), Variant::Bech32m).map_err(ser::Error::custom)?;
+        serializer.serialize_str(&encoded)
+    } else {
+        serializer.serialize_bytes(bytes)
+    }
+}
+
+struct BytesVisitor;
+
+impl<'de> de::Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a byte array")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element()? {
+            bytes.push(b);
+        }
+        Ok(bytes)
+    }
+}
+
+fn deserialize_encoded<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    hrp: &str,
+) -> Result<Vec<u8>, D::Error> {
+    if deserializer.is_human_readable() {
+        let encoded = String::deserialize(deserializer)?;
+        let (decoded_hrp, data, variant) = bech32::decode(&encoded).map_err(de::Error::custom)?;
+        if decoded_hrp != hrp {
+            return Err(de::Error::custom(format!(
+                "unexpected bech32 prefix: expected {}, got {}",
+                hrp, decoded_hrp
+            )));
+        }
+        if variant != Variant::Bech32m {
+            return Err(de::Error::custom("expected a bech32m encoding"));
+        }
+        Vec::<u8>::from_base32(&data).map_err(de::Error::custom)
+    } else {
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+impl Serialize for PaymentAddress {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_encoded(serializer, HRP_PAYMENT_ADDRESS, &self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for PaymentAddress {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize_encoded(deserializer, HRP_PAYMENT_ADDRESS)?;
+        let bytes: [u8; 43] = bytes
+            .try_into()
+            .map_err(|_| de::Error::custom("payment address must be 43 bytes"))?;
+        PaymentAddress::from_bytes(&bytes)
+            .ok_or_else(|| de::Error::custom("invalid payment address"))
+    }
+}
+
+impl Serialize for FullViewingKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_encoded(serializer, HRP_FULL_VIEWING_KEY, &self.to_bytes())
+    }
+}
+
+impl<'de> Deserialize<'de> for FullViewingKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize_encoded(deserializer, HRP_FULL_VIEWING_KEY)?;
+        FullViewingKey::read(&bytes[..]).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ExtendedFullViewingKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::with_capacity(169);
+        self.write(&mut bytes).map_err(ser::Error::custom)?;
+        serialize_encoded(serializer, HRP_EXTENDED_FULL_VIEWING_KEY, &bytes)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtendedFullViewingKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize_encoded(deserializer, HRP_EXTENDED_FULL_VIEWING_KEY)?;
+        ExtendedFullViewingKey::read(&bytes[..]).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for SaplingIvk {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_encoded(serializer, HRP_INCOMING_VIEWING_KEY, &self.to_repr())
+    }
+}
+
+impl<'de> Deserialize<'de> for SaplingIvk {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize_encoded(deserializer, HRP_INCOMING_VIEWING_KEY)?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| de::Error::custom("incoming viewing key must be 32 bytes"))?;
+        Option::from(jubjub::Fr::from_repr(bytes))
+            .map(SaplingIvk)
+            .ok_or_else(|| de::Error::custom("invalid incoming viewing key"))
+    }
+}
+
+impl Serialize for OutgoingViewingKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_encoded(serializer, HRP_OUTGOING_VIEWING_KEY, &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for OutgoingViewingKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserialize_encoded(deserializer, HRP_OUTGOING_VIEWING_KEY)?;
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| de::Error::custom("outgoing viewing key must be 32 bytes"))?;
+        Ok(OutgoingViewingKey(bytes))
+    }
+}
+
+impl Serialize for Nullifier {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&hex::encode(self.0))
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Nullifier {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = if deserializer.is_human_readable() {
+            let encoded = String::deserialize(deserializer)?;
+            hex::decode(encoded).map_err(de::Error::custom)?
+        } else {
+            deserializer.deserialize_byte_buf(BytesVisitor)?
+        };
+        Nullifier::from_slice(&bytes).map_err(|_| de::Error::custom("nullifier must be 32 bytes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sapling::PaymentAddress;
+    use crate::zip32::sapling::ExtendedFullViewingKey;
+    use crate::zip32::ExtendedSpendingKey;
+
+    #[test]
+    fn payment_address_json_roundtrip() {
+        let extsk = ExtendedSpendingKey::master(&[]);
+        let addr = extsk.default_address().1;
+
+        let encoded = serde_json::to_string(&addr).unwrap();
+        assert!(encoded.starts_with("\"masp1"), "got {}", encoded);
+        let decoded: PaymentAddress = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(addr, decoded);
+    }
+
+    #[test]
+    fn full_viewing_key_json_roundtrip() {
+        let extsk = ExtendedSpendingKey::master(&[]);
+        #[allow(deprecated)]
+        let xfvk = extsk.to_extended_full_viewing_key();
+
+        let encoded = serde_json::to_string(&xfvk.fvk).unwrap();
+        assert!(encoded.starts_with("\"maspfvk1"), "got {}", encoded);
+        let decoded: crate::sapling::keys::FullViewingKey = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(xfvk.fvk.to_bytes(), decoded.to_bytes());
+
+        let encoded = serde_json::to_string(&xfvk).unwrap();
+        assert!(encoded.starts_with("\"maspxfvk1"), "got {}", encoded);
+        let decoded: ExtendedFullViewingKey = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(xfvk, decoded);
+    }
+
+    #[test]
+    fn scoped_viewing_key_json_roundtrips() {
+        use crate::sapling::{keys::OutgoingViewingKey, SaplingIvk};
+
+        let extsk = ExtendedSpendingKey::master(&[]);
+        #[allow(deprecated)]
+        let xfvk = extsk.to_extended_full_viewing_key();
+
+        let ivk = xfvk.fvk.vk.ivk();
+        let encoded = serde_json::to_string(&ivk).unwrap();
+        assert!(encoded.starts_with("\"maspivk1"), "got {}", encoded);
+        let decoded: SaplingIvk = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(ivk.to_repr(), decoded.to_repr());
+
+        let ovk = xfvk.fvk.ovk;
+        let encoded = serde_json::to_string(&ovk).unwrap();
+        assert!(encoded.starts_with("\"maspovk1"), "got {}", encoded);
+        let decoded: OutgoingViewingKey = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(ovk, decoded);
+
+        // An incoming-only key is not accepted where an outgoing-only key is
+        // expected, and vice versa.
+        let ivk_encoded = serde_json::to_string(&ivk).unwrap();
+        assert!(serde_json::from_str::<OutgoingViewingKey>(&ivk_encoded).is_err());
+        let ovk_encoded = serde_json::to_string(&ovk).unwrap();
+        assert!(serde_json::from_str::<SaplingIvk>(&ovk_encoded).is_err());
+    }
+
+    #[test]
+    fn nullifier_json_roundtrip() {
+        use crate::sapling::Nullifier;
+
+        let nf = Nullifier([7; 32]);
+        let encoded = serde_json::to_string(&nf).unwrap();
+        assert_eq!(encoded, format!("\"{}\"", hex::encode([7u8; 32])));
+        let decoded: Nullifier = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(nf, decoded);
+
+        assert!(serde_json::from_str::<Nullifier>("\"0707\"").is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_prefix() {
+        let extsk = ExtendedSpendingKey::master(&[]);
+        let addr = extsk.default_address().1;
+
+        let encoded = serde_json::to_string(&addr).unwrap();
+        assert!(serde_json::from_str::<ExtendedFullViewingKey>(&encoded).is_err());
+    }
+}
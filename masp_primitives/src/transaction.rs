@@ -1,14 +1,16 @@
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 
+pub mod broadcast;
 pub mod builder;
 pub mod components;
 pub mod fees;
 pub mod sighash;
 pub mod sighash_v5;
+pub mod streaming;
 pub mod txid;
+pub mod uniqueness;
 use blake2b_simd::Hash as Blake2bHash;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use ff::PrimeField;
 use memuse::DynamicUsage;
 use std::collections::BTreeMap;
 use std::{
@@ -26,7 +28,7 @@ use crate::{
 
 use self::{
     components::{
-        amount::{I128Sum, ValueSum},
+        amount::{BalanceError, I128Sum, U64Sum, ValueSum},
         sapling::{
             self, ConvertDescriptionV5, OutputDescriptionV5, SpendDescription, SpendDescriptionV5,
         },
@@ -122,6 +124,18 @@ impl TxId {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum TxVersion {
     MASPv5,
+    /// A transaction version from a future soft upgrade, carrying the version
+    /// number from its header.
+    ///
+    /// Future minor versions keep the MASPv5 version group id and are required
+    /// to encode the full MASPv5 transaction body first, followed by a single
+    /// [`CompactSize`]-prefixed opaque extension area holding any new bundle
+    /// sections. An old node can therefore still deserialize such a
+    /// transaction, retain the extension bytes verbatim, and re-serialize it
+    /// for relay, even though it cannot interpret the new sections. Note that
+    /// the transaction ID computed by such a node commits only to the
+    /// recognized sections.
+    MASPFuture(u32),
 }
 
 impl TxVersion {
@@ -131,6 +145,9 @@ impl TxVersion {
 
         match (version, reader.read_u32::<LittleEndian>()?) {
             (MASPV5_TX_VERSION, MASPV5_VERSION_GROUP_ID) => Ok(TxVersion::MASPv5),
+            (version, MASPV5_VERSION_GROUP_ID) if version > MASPV5_TX_VERSION => {
+                Ok(TxVersion::MASPFuture(version))
+            }
             _ => Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "Unknown transaction format",
@@ -141,12 +158,13 @@ impl TxVersion {
     pub fn header(&self) -> u32 {
         match self {
             TxVersion::MASPv5 => MASPV5_TX_VERSION,
+            TxVersion::MASPFuture(version) => *version,
         }
     }
 
     pub fn version_group_id(&self) -> u32 {
         match self {
-            TxVersion::MASPv5 => MASPV5_VERSION_GROUP_ID,
+            TxVersion::MASPv5 | TxVersion::MASPFuture(_) => MASPV5_VERSION_GROUP_ID,
         }
     }
 
@@ -235,6 +253,10 @@ impl<K: crate::zip32::ExtendedKey + PartialEq + Clone + Debug + for<'a> MaybeArb
 pub struct Transaction {
     txid: TxId,
     data: TransactionData<Authorized>,
+    /// The opaque trailing extension area of a [`TxVersion::MASPFuture`]
+    /// transaction, preserved verbatim so the transaction can be re-serialized
+    /// and relayed. Always empty for [`TxVersion::MASPv5`].
+    extension_sections: Vec<u8>,
 }
 
 impl Deref for Transaction {
@@ -341,12 +363,150 @@ impl<A: Authorization> TransactionData<A> {
             .as_ref()
             .map_or(ValueSum::zero(), |b| b.value_balance.clone())
     }
+
+    /// The amount of value added to or removed from the transparent pool by
+    /// this transaction. A positive component means value flowing out of the
+    /// transparent pool into the shielded pools or to fees; a negative
+    /// component means value flowing into the transparent pool from the
+    /// shielded pools.
+    pub fn transparent_value_balance(&self) -> I128Sum {
+        self.transparent_bundle
+            .as_ref()
+            .map_or(ValueSum::zero(), |b| b.value_balance::<BalanceError, ()>())
+    }
+
+    /// The net amount of value leaving this transaction, summed over both
+    /// pools. In a balanced transaction every component is nonnegative, since
+    /// value entering one pool must come out of the other, and the result is
+    /// exactly the fee paid.
+    pub fn value_balance(&self) -> I128Sum {
+        self.transparent_value_balance() + self.sapling_value_balance()
+    }
+
+    /// The total fee paid by this transaction per asset type, i.e. the value
+    /// that leaves the transaction without arriving in either pool. Unlike
+    /// Bitcoin-derived chains, MASP transparent inputs carry their value
+    /// inline, so no outpoint lookup is needed.
+    ///
+    /// Returns [`BalanceError::Underflow`] if any component of the overall
+    /// balance is negative, which means the transaction creates value and
+    /// could not have passed balance verification.
+    pub fn fee_paid(&self) -> Result<U64Sum, BalanceError> {
+        U64Sum::try_from_sum(self.value_balance()).map_err(|_| BalanceError::Underflow)
+    }
+}
+
+/// The serialized size of a transaction, broken down by section.
+///
+/// Produced by [`TransactionData::size_breakdown`]; the sections sum to the
+/// exact encoded length without serializing anything.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionSize {
+    /// The size of the common header fields.
+    pub header: usize,
+    /// The size of the transparent bundle, including the empty-bundle
+    /// markers when it is absent.
+    pub transparent: usize,
+    /// The size of the Sapling bundle, including the empty-bundle markers
+    /// when it is absent.
+    pub sapling: usize,
+}
+
+impl TransactionSize {
+    /// The total serialized size.
+    pub fn total(&self) -> usize {
+        self.header + self.transparent + self.sapling
+    }
+}
+
+/// Returns the encoded length of a [`CompactSize`] count.
+fn compact_size_len(n: usize) -> usize {
+    match n {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
 }
 
 impl TransactionData<Authorized> {
     pub fn freeze(self) -> io::Result<Transaction> {
         Transaction::from_data(self)
     }
+
+    /// Computes the serialized size of this transaction per section, without
+    /// serializing it, so fee estimation and block packing can reason about
+    /// transaction weight cheaply.
+    ///
+    /// The sizes match the encoding produced by [`Transaction::write`]
+    /// byte for byte, except that the extension area of a
+    /// [`TxVersion::MASPFuture`] transaction is not counted here, since it
+    /// is carried on [`Transaction`] rather than on the transaction data;
+    /// use [`Transaction::serialized_size`] to include it.
+    pub fn size_breakdown(&self) -> TransactionSize {
+        // version, version group id, branch id, lock time, expiry height
+        let header = 4 * 5;
+
+        let transparent = match &self.transparent_bundle {
+            Some(bundle) => {
+                // asset type + value + address
+                const TX_IN_OUT_SIZE: usize = 32 + 8 + 20;
+                compact_size_len(bundle.vin.len())
+                    + bundle.vin.len() * TX_IN_OUT_SIZE
+                    + compact_size_len(bundle.vout.len())
+                    + bundle.vout.len() * TX_IN_OUT_SIZE
+            }
+            None => 2,
+        };
+
+        let sapling = match &self.sapling_bundle {
+            Some(bundle) => {
+                let n_spends = bundle.shielded_spends.len();
+                let n_converts = bundle.shielded_converts.len();
+                let n_outputs = bundle.shielded_outputs.len();
+
+                // cv + nullifier + rk
+                let mut size = compact_size_len(n_spends) + n_spends * (32 + 32 + 32);
+                // cv
+                size += compact_size_len(n_converts) + n_converts * 32;
+                // cv + cmu + ephemeral key + enc and out ciphertexts
+                size += compact_size_len(n_outputs) + n_outputs * (32 + 32 + 32 + 612 + 80);
+
+                if n_spends > 0 || n_converts > 0 || n_outputs > 0 {
+                    // value balance: asset type + i128 value per component
+                    let n_assets = bundle.value_balance.components().count();
+                    size += compact_size_len(n_assets) + n_assets * (32 + 16);
+                    // binding signature
+                    size += 64;
+                }
+                if n_spends > 0 {
+                    // spend anchor
+                    size += 32;
+                }
+                if n_converts > 0 {
+                    // convert anchor
+                    size += 32;
+                }
+                // proofs, and a spend authorization signature per spend
+                size += n_spends * (GROTH_PROOF_SIZE + 64);
+                size += n_converts * GROTH_PROOF_SIZE;
+                size += n_outputs * GROTH_PROOF_SIZE;
+                size
+            }
+            None => 3,
+        };
+
+        TransactionSize {
+            header,
+            transparent,
+            sapling,
+        }
+    }
+
+    /// The total serialized size of this transaction, without serializing it.
+    pub fn serialized_size(&self) -> usize {
+        self.size_breakdown().total()
+    }
 }
 
 impl BorshSerialize for Transaction {
@@ -555,7 +715,9 @@ impl BorshSchema for Transaction {
 impl Transaction {
     fn from_data(data: TransactionData<Authorized>) -> io::Result<Self> {
         match data.version {
-            TxVersion::MASPv5 => Ok(Self::from_data_v5(data)),
+            // A locally assembled future-version transaction carries no
+            // extension sections; an empty extension area is valid.
+            TxVersion::MASPv5 | TxVersion::MASPFuture(_) => Ok(Self::from_data_v5(data)),
         }
     }
     fn from_data_v5(data: TransactionData<Authorized>) -> Self {
@@ -565,7 +727,11 @@ impl Transaction {
             &data.digest(TxIdDigester),
         );
 
-        Transaction { txid, data }
+        Transaction {
+            txid,
+            data,
+            extension_sections: vec![],
+        }
     }
 
     pub fn into_data(self) -> TransactionData<Authorized> {
@@ -576,10 +742,31 @@ impl Transaction {
         self.txid
     }
 
+    /// Returns the opaque trailing extension area parsed from a
+    /// [`TxVersion::MASPFuture`] transaction. Always empty for
+    /// [`TxVersion::MASPv5`].
+    pub fn extension_sections(&self) -> &[u8] {
+        &self.extension_sections
+    }
+
+    /// The total serialized size of this transaction, without serializing it.
+    ///
+    /// Unlike [`TransactionData::serialized_size`], this includes the
+    /// extension area of a [`TxVersion::MASPFuture`] transaction.
+    pub fn serialized_size(&self) -> usize {
+        let extension = match self.data.version {
+            TxVersion::MASPv5 => 0,
+            TxVersion::MASPFuture(_) => {
+                compact_size_len(self.extension_sections.len()) + self.extension_sections.len()
+            }
+        };
+        self.data.serialized_size() + extension
+    }
+
     pub fn read<R: Read>(mut reader: R, _consensus_branch_id: BranchId) -> io::Result<Self> {
         let version = TxVersion::read(&mut reader)?;
         match version {
-            TxVersion::MASPv5 => Self::read_v5(reader, version),
+            TxVersion::MASPv5 | TxVersion::MASPFuture(_) => Self::read_v5(reader, version),
         }
     }
 
@@ -614,6 +801,13 @@ impl Transaction {
         let transparent_bundle = Self::read_transparent(&mut reader)?;
         let sapling_bundle = Self::read_v5_sapling(&mut reader)?;
 
+        // A future minor version appends its unknown bundle sections as a
+        // single length-prefixed extension area; retain it verbatim.
+        let extension_sections = match version {
+            TxVersion::MASPv5 => vec![],
+            TxVersion::MASPFuture(_) => Vector::read(&mut reader, |r| r.read_u8())?,
+        };
+
         let data = TransactionData {
             version,
             consensus_branch_id,
@@ -623,7 +817,10 @@ impl Transaction {
             sapling_bundle,
         };
 
-        Ok(Self::from_data_v5(data))
+        Ok(Transaction {
+            extension_sections,
+            ..Self::from_data_v5(data)
+        })
     }
 
     fn read_v5_header_fragment<R: Read>(mut reader: R) -> io::Result<(BranchId, u32, BlockHeight)> {
@@ -657,13 +854,13 @@ impl Transaction {
         };
 
         let spend_anchor = if n_spends > 0 {
-            Some(sapling::read_base(&mut reader, "spend anchor")?)
+            Some(sapling::read_base(&mut reader, "spend anchor").map(sapling::Anchor)?)
         } else {
             None
         };
 
         let convert_anchor = if n_converts > 0 {
-            Some(sapling::read_base(&mut reader, "convert anchor")?)
+            Some(sapling::read_base(&mut reader, "convert anchor").map(sapling::ConvertAnchor)?)
         } else {
             None
         };
@@ -710,9 +907,13 @@ impl Transaction {
             authorization: sapling::Authorized { binding_sig },
         }))
     }
-    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         match self.version {
             TxVersion::MASPv5 => self.write_v5(writer),
+            TxVersion::MASPFuture(_) => {
+                self.write_v5(&mut writer)?;
+                Vector::write(&mut writer, &self.extension_sections, |w, b| w.write_u8(*b))
+            }
         }
     }
     pub fn write_transparent<W: Write>(&self, mut writer: W) -> io::Result<()> {
@@ -763,10 +964,10 @@ impl Transaction {
                 bundle.value_balance.write(&mut writer)?;
             }
             if !bundle.shielded_spends.is_empty() {
-                writer.write_all(bundle.shielded_spends[0].anchor.to_repr().as_ref())?;
+                writer.write_all(&bundle.shielded_spends[0].anchor.to_bytes())?;
             }
             if !bundle.shielded_converts.is_empty() {
-                writer.write_all(bundle.shielded_converts[0].anchor.to_repr().as_ref())?;
+                writer.write_all(&bundle.shielded_converts[0].anchor.to_bytes())?;
             }
 
             Array::write(
@@ -862,6 +1063,184 @@ pub enum DigestError {
     NotSigned,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::{
+        components::{
+            amount::{BalanceError, U64Sum, ValueSum},
+            transparent::{self, TxIn, TxOut},
+        },
+        Authorized, Transaction, TransactionData, TransparentAddress, TxVersion,
+    };
+    use crate::asset_type::AssetType;
+    use crate::consensus::BranchId;
+    use zcash_encoding::CompactSize;
+
+    /// A serialized transaction with no bundles, patched to the given header
+    /// version.
+    fn empty_tx_bytes(version: u32) -> Vec<u8> {
+        let tx = TransactionData::<Authorized>::from_parts(
+            TxVersion::MASPv5,
+            BranchId::MASP,
+            0,
+            0u32.into(),
+            None,
+            None,
+        )
+        .freeze()
+        .unwrap();
+        let mut bytes = vec![];
+        tx.write(&mut bytes).unwrap();
+        bytes[0..4].copy_from_slice(&version.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn future_version_round_trips_extension_sections() {
+        let mut bytes = empty_tx_bytes(3);
+        let extension = b"opaque future bundle";
+        CompactSize::write(&mut bytes, extension.len()).unwrap();
+        bytes.extend_from_slice(extension);
+
+        let tx = Transaction::read(&bytes[..], BranchId::MASP).unwrap();
+        assert_eq!(tx.version(), TxVersion::MASPFuture(3));
+        assert_eq!(tx.extension_sections(), extension);
+
+        let mut reencoded = vec![];
+        tx.write(&mut reencoded).unwrap();
+        assert_eq!(reencoded, bytes);
+    }
+
+    #[test]
+    fn future_version_requires_extension_area() {
+        // Even an empty extension area must carry its length prefix.
+        let bytes = empty_tx_bytes(3);
+        assert!(Transaction::read(&bytes[..], BranchId::MASP).is_err());
+
+        let mut bytes = bytes;
+        CompactSize::write(&mut bytes, 0).unwrap();
+        let tx = Transaction::read(&bytes[..], BranchId::MASP).unwrap();
+        assert_eq!(tx.extension_sections(), &[] as &[u8]);
+    }
+
+    #[test]
+    fn unknown_version_group_is_rejected() {
+        let mut bytes = empty_tx_bytes(3);
+        bytes[4] ^= 1;
+        assert!(Transaction::read(&bytes[..], BranchId::MASP).is_err());
+    }
+
+    #[test]
+    fn older_versions_are_rejected() {
+        let bytes = empty_tx_bytes(1);
+        assert!(Transaction::read(&bytes[..], BranchId::MASP).is_err());
+    }
+
+    fn transparent_only_txdata(
+        vin: Vec<TxIn<transparent::Authorized>>,
+        vout: Vec<TxOut>,
+    ) -> TransactionData<Authorized> {
+        TransactionData::from_parts(
+            TxVersion::MASPv5,
+            BranchId::MASP,
+            0,
+            0u32.into(),
+            Some(transparent::Bundle {
+                vin,
+                vout,
+                authorization: transparent::Authorized,
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn value_balance_and_fee_paid() {
+        let nam = AssetType::new(b"NAM").unwrap();
+        let btc = AssetType::new(b"BTC").unwrap();
+        let address = TransparentAddress([0u8; 20]);
+        let data = transparent_only_txdata(
+            vec![
+                TxIn {
+                    asset_type: nam,
+                    value: 10_000,
+                    address,
+                    transparent_sig: (),
+                },
+                TxIn {
+                    asset_type: btc,
+                    value: 500,
+                    address,
+                    transparent_sig: (),
+                },
+            ],
+            vec![
+                TxOut {
+                    asset_type: nam,
+                    value: 9_000,
+                    address,
+                },
+                TxOut {
+                    asset_type: btc,
+                    value: 500,
+                    address,
+                },
+            ],
+        );
+
+        assert_eq!(
+            data.transparent_value_balance(),
+            ValueSum::from_pair(nam, 1_000i128)
+        );
+        assert!(data.sapling_value_balance().is_zero());
+        assert_eq!(data.value_balance(), ValueSum::from_pair(nam, 1_000i128));
+        // The balanced asset drops out of the fee breakdown entirely.
+        assert_eq!(data.fee_paid(), Ok(U64Sum::from_pair(nam, 1_000)));
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::test_runner::Config::with_cases(10))]
+        #[test]
+        fn serialized_size_matches_encoding(tx in super::testing::arb_tx(BranchId::MASP)) {
+            let mut bytes = vec![];
+            tx.write(&mut bytes).unwrap();
+            proptest::prop_assert_eq!(tx.serialized_size(), bytes.len());
+            proptest::prop_assert_eq!(tx.size_breakdown().total(), bytes.len());
+        }
+    }
+
+    #[test]
+    fn serialized_size_includes_extension_area() {
+        let mut bytes = empty_tx_bytes(3);
+        let extension = b"opaque future bundle";
+        CompactSize::write(&mut bytes, extension.len()).unwrap();
+        bytes.extend_from_slice(extension);
+
+        let tx = Transaction::read(&bytes[..], BranchId::MASP).unwrap();
+        assert_eq!(tx.serialized_size(), bytes.len());
+        // The breakdown on the transaction data excludes the extension area.
+        assert_eq!(
+            tx.size_breakdown().total(),
+            bytes.len() - extension.len() - 1
+        );
+    }
+
+    #[test]
+    fn fee_paid_rejects_unbalanced_transactions() {
+        let nam = AssetType::new(b"NAM").unwrap();
+        let address = TransparentAddress([0u8; 20]);
+        let data = transparent_only_txdata(
+            vec![],
+            vec![TxOut {
+                asset_type: nam,
+                value: 1,
+                address,
+            }],
+        );
+        assert_eq!(data.fee_paid(), Err(BalanceError::Underflow));
+    }
+}
+
 #[cfg(any(test, feature = "test-dependencies"))]
 pub mod testing {
     use proptest::prelude::*;
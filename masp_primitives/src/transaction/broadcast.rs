@@ -0,0 +1,237 @@
+//! Chain-agnostic transaction broadcasting.
+//!
+//! [`TransactionBroadcaster`] abstracts over how a finished [`Transaction`]
+//! reaches the network, so wallet helpers built on this crate can complete the
+//! full transaction lifecycle without tying themselves to any particular
+//! chain's RPC surface. A plain HTTP implementation is provided behind the
+//! `broadcast-http` feature; node implementations with richer RPC protocols
+//! can implement the trait directly.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+use crate::consensus::BlockHeight;
+use crate::transaction::{Transaction, TxId};
+
+/// The status of a submitted transaction, as reported by the backing node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// The node does not know about the transaction.
+    Unknown,
+    /// The transaction is in the mempool awaiting inclusion in a block.
+    InMempool,
+    /// The transaction was included in a block at the given height.
+    Confirmed(BlockHeight),
+    /// The node rejected the transaction, with its stated reason.
+    Rejected(String),
+}
+
+/// An error while submitting a transaction or querying its status.
+#[derive(Debug)]
+pub enum BroadcastError {
+    /// The transaction could not be serialized.
+    Serialization(io::Error),
+    /// The backend could not be reached, or returned a malformed response.
+    Transport(String),
+    /// The backend refused the submission, with its stated reason.
+    Rejected(String),
+}
+
+impl fmt::Display for BroadcastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BroadcastError::Serialization(e) => {
+                write!(f, "failed to serialize transaction: {}", e)
+            }
+            BroadcastError::Transport(e) => write!(f, "broadcast transport error: {}", e),
+            BroadcastError::Rejected(reason) => write!(f, "transaction rejected: {}", reason),
+        }
+    }
+}
+
+impl error::Error for BroadcastError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            BroadcastError::Serialization(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A sink through which finished transactions reach the network.
+pub trait TransactionBroadcaster {
+    /// Submits the transaction to the network, returning its txid on
+    /// acceptance.
+    fn submit(&self, tx: &Transaction) -> Result<TxId, BroadcastError>;
+
+    /// Queries the network for the status of a previously submitted
+    /// transaction.
+    fn status(&self, txid: &TxId) -> Result<TransactionStatus, BroadcastError>;
+}
+
+/// A [`TransactionBroadcaster`] speaking plain HTTP to a node or proxy.
+///
+/// Transactions are `POST`ed in their raw serialized form to
+/// `{base_url}/transactions`, and statuses are read from
+/// `GET {base_url}/transactions/{txid}/status`, whose body is one of
+/// `unknown`, `mempool`, `confirmed <height>`, or `rejected <reason>`.
+#[cfg(feature = "broadcast-http")]
+#[derive(Clone, Debug)]
+pub struct HttpBroadcaster {
+    base_url: String,
+    timeout: Option<u64>,
+}
+
+#[cfg(feature = "broadcast-http")]
+impl HttpBroadcaster {
+    /// Constructs a broadcaster for the endpoint at `base_url`, without a
+    /// trailing slash.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        HttpBroadcaster {
+            base_url: base_url.into(),
+            timeout: None,
+        }
+    }
+
+    /// Sets a timeout in seconds for each request.
+    pub fn with_timeout(mut self, seconds: u64) -> Self {
+        self.timeout = Some(seconds);
+        self
+    }
+
+    fn send(&self, request: minreq::Request) -> Result<minreq::Response, BroadcastError> {
+        let request = match self.timeout {
+            Some(seconds) => request.with_timeout(seconds),
+            None => request,
+        };
+        request
+            .send()
+            .map_err(|e| BroadcastError::Transport(e.to_string()))
+    }
+}
+
+#[cfg(feature = "broadcast-http")]
+impl TransactionBroadcaster for HttpBroadcaster {
+    fn submit(&self, tx: &Transaction) -> Result<TxId, BroadcastError> {
+        let mut bytes = vec![];
+        tx.write(&mut bytes)
+            .map_err(BroadcastError::Serialization)?;
+
+        let response = self.send(
+            minreq::post(format!("{}/transactions", self.base_url))
+                .with_header("Content-Type", "application/octet-stream")
+                .with_body(bytes),
+        )?;
+
+        match response.status_code {
+            200..=299 => Ok(tx.txid()),
+            _ => Err(BroadcastError::Rejected(
+                response.as_str().unwrap_or("").trim().to_owned(),
+            )),
+        }
+    }
+
+    fn status(&self, txid: &TxId) -> Result<TransactionStatus, BroadcastError> {
+        let response = self.send(minreq::get(format!(
+            "{}/transactions/{}/status",
+            self.base_url, txid
+        )))?;
+
+        if !(200..=299).contains(&response.status_code) {
+            return Err(BroadcastError::Transport(format!(
+                "status query returned HTTP {}",
+                response.status_code
+            )));
+        }
+
+        let body = response
+            .as_str()
+            .map_err(|e| BroadcastError::Transport(e.to_string()))?
+            .trim();
+
+        match body.split_once(' ') {
+            None if body == "unknown" => Ok(TransactionStatus::Unknown),
+            None if body == "mempool" => Ok(TransactionStatus::InMempool),
+            Some(("confirmed", height)) => height
+                .parse::<u32>()
+                .map(|h| TransactionStatus::Confirmed(BlockHeight::from(h)))
+                .map_err(|_| {
+                    BroadcastError::Transport(format!("malformed confirmation height: {}", height))
+                }),
+            Some(("rejected", reason)) => Ok(TransactionStatus::Rejected(reason.to_owned())),
+            _ => Err(BroadcastError::Transport(format!(
+                "malformed status response: {}",
+                body
+            ))),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "broadcast-http"))]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::{HttpBroadcaster, TransactionBroadcaster, TransactionStatus};
+    use crate::consensus::BlockHeight;
+    use crate::transaction::TxId;
+
+    /// Serves a single HTTP request with the given status line and body, and
+    /// returns the broadcaster pointed at it.
+    fn serve_one(status_line: &'static str, body: &'static str) -> HttpBroadcaster {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status_line,
+                body.len(),
+                body,
+            )
+            .unwrap();
+        });
+
+        HttpBroadcaster::new(format!("http://{}", addr)).with_timeout(5)
+    }
+
+    #[test]
+    fn status_parses_node_responses() {
+        let txid = TxId::from_bytes([1; 32]);
+
+        let cases: &[(&'static str, TransactionStatus)] = &[
+            ("unknown", TransactionStatus::Unknown),
+            ("mempool", TransactionStatus::InMempool),
+            (
+                "confirmed 42",
+                TransactionStatus::Confirmed(BlockHeight::from(42)),
+            ),
+            (
+                "rejected invalid binding signature",
+                TransactionStatus::Rejected("invalid binding signature".to_owned()),
+            ),
+        ];
+
+        for (body, expected) in cases {
+            let broadcaster = serve_one("200 OK", body);
+            assert_eq!(&broadcaster.status(&txid).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn status_rejects_malformed_responses() {
+        let txid = TxId::from_bytes([1; 32]);
+
+        let broadcaster = serve_one("200 OK", "floating");
+        assert!(broadcaster.status(&txid).is_err());
+
+        let broadcaster = serve_one("500 Internal Server Error", "");
+        assert!(broadcaster.status(&txid).is_err());
+    }
+}
@@ -6,7 +6,9 @@ use std::sync::mpsc::Sender;
 
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 
+use masp_note_encryption::{ENC_CIPHERTEXT_SIZE, OUT_CIPHERTEXT_SIZE};
 use rand::{CryptoRng, RngCore};
+use zcash_encoding::CompactSize;
 
 use crate::{
     asset_type::AssetType,
@@ -21,15 +23,18 @@ use crate::{
             amount::{BalanceError, I128Sum, U64Sum, ValueSum, MAX_MONEY},
             sapling::{
                 self,
-                builder::{BuildParams, SaplingBuilder, SaplingMetadata},
+                builder::{BuildParams, SaplingBuilder, SaplingMetadata, MIN_SHIELDED_OUTPUTS},
+                fees::{ConvertView, InputView as SaplingInputView},
             },
-            transparent::{self, builder::TransparentBuilder},
+            transparent::{self, builder::TransparentBuilder, fees::InputView},
+            GROTH_PROOF_SIZE,
         },
         fees::FeeRule,
         sighash::{signature_hash, SignableInput},
         txid::TxIdDigester,
         Transaction, TransactionData, TransparentAddress, TxVersion, Unauthorized,
     },
+    wallet::RefundTerms,
     zip32::{ExtendedKey, ExtendedSpendingKey},
     MaybeArbitrary,
 };
@@ -38,15 +43,49 @@ use crate::{
 use crate::transaction::components::transparent::TxOut;
 
 const DEFAULT_TX_EXPIRY_DELTA: u32 = 20;
+/// The shortfall of a single asset in an [`Error::InsufficientFunds`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FundsDeficit {
+    /// The asset that is short.
+    pub asset_type: AssetType,
+    /// The total value of this asset demanded by the outputs and fee.
+    pub required: i128,
+    /// The value of this asset supplied by the builder's inputs.
+    pub available: i128,
+}
+
+/// The circuit for which a proof could not be created in an
+/// [`Error::ProofFailure`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Circuit {
+    /// The MASP Spend circuit.
+    Spend,
+    /// The MASP Convert circuit.
+    Convert,
+}
+
+impl fmt::Display for Circuit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Circuit::Spend => write!(f, "Spend"),
+            Circuit::Convert => write!(f, "Convert"),
+        }
+    }
+}
+
 /// Errors that can occur during transaction construction.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error<FeeError> {
-    /// Insufficient funds were provided to the transaction builder; the given
-    /// additional amount is required in order to construct the transaction.
-    InsufficientFunds(I128Sum),
+    /// Insufficient funds were provided to the transaction builder; each
+    /// entry records one asset that is short and by how much.
+    InsufficientFunds(Vec<FundsDeficit>),
     /// The transaction has inputs in excess of outputs and fees; the user must
     /// add a change output.
     ChangeRequired(U64Sum),
+    /// The anchors of the builder's spends do not match.
+    InvalidAnchor,
+    /// A zero-knowledge proof could not be created for the given circuit.
+    ProofFailure(Circuit),
     /// An error occurred in computing the fees for a transaction.
     Fee(FeeError),
     /// An overflow or underflow occurred when computing value balances
@@ -60,16 +99,28 @@ pub enum Error<FeeError> {
 impl<FE: fmt::Display> fmt::Display for Error<FE> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Error::InsufficientFunds(amount) => write!(
-                f,
-                "Insufficient funds for transaction construction; need an additional {:?} zatoshis",
-                amount
-            ),
+            Error::InsufficientFunds(deficits) => {
+                write!(f, "Insufficient funds for transaction construction:")?;
+                for deficit in deficits {
+                    write!(
+                        f,
+                        " {} required but only {} available of asset {};",
+                        deficit.required, deficit.available, deficit.asset_type
+                    )?;
+                }
+                Ok(())
+            }
             Error::ChangeRequired(amount) => write!(
                 f,
                 "The transaction requires an additional change output of {:?} zatoshis",
                 amount
             ),
+            Error::InvalidAnchor => {
+                write!(f, "Anchor mismatch (anchors for all spends must be equal)")
+            }
+            Error::ProofFailure(circuit) => {
+                write!(f, "Failed to create MASP {} proof", circuit)
+            }
             Error::Balance(e) => write!(f, "Invalid amount {:?}", e),
             Error::Fee(e) => write!(f, "An error occurred in fee calculation: {}", e),
             Error::TransparentBuild(err) => err.fmt(f),
@@ -86,17 +137,95 @@ impl<FE> From<BalanceError> for Error<FE> {
     }
 }
 
+impl<FE> From<sapling::builder::Error> for Error<FE> {
+    fn from(e: sapling::builder::Error) -> Self {
+        match e {
+            sapling::builder::Error::AnchorMismatch => Error::InvalidAnchor,
+            sapling::builder::Error::SpendProof => Error::ProofFailure(Circuit::Spend),
+            sapling::builder::Error::ConvertProof => Error::ProofFailure(Circuit::Convert),
+            other => Error::SaplingBuild(other),
+        }
+    }
+}
+
+/// A structured account of the fee paid by a built transaction, returned by
+/// [`Builder::build_with_fee_report`].
+///
+/// The report lets wallets display exact costs and accounting systems book
+/// fees without re-deriving them from the fee rule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeReport {
+    /// The fee required by the fee rule, per asset.
+    pub fees: U64Sum,
+    /// Which inputs funded each asset's fee component.
+    pub funding: Vec<FeeFunding>,
+    /// The conversions applied while assembling the transaction: each allowed
+    /// conversion together with the multiplier it was applied with.
+    pub conversions: Vec<(AllowedConversion, u64)>,
+}
+
+/// The inputs attributed to one asset's fee component.
+///
+/// Inputs are attributed in the order they were added to the builder until
+/// the component is covered; this is an accounting convention, not a protocol
+/// property, since value is fungible within an asset type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeFunding {
+    /// The asset the fee component is denominated in.
+    pub asset_type: AssetType,
+    /// The fee component's value.
+    pub value: u64,
+    /// Indices into the builder's transparent inputs attributed to the fee.
+    pub transparent_inputs: Vec<usize>,
+    /// Indices into the builder's Sapling spends attributed to the fee.
+    pub sapling_spends: Vec<usize>,
+}
+
+/// A preview of the transaction a [`Builder`] would produce, computed by
+/// [`Builder::estimate`] without any proving or signing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionEstimate {
+    /// The number of spend descriptions, including padding dummies.
+    pub spends: usize,
+    /// The number of convert descriptions.
+    pub converts: usize,
+    /// The number of output descriptions, including padding dummies.
+    pub outputs: usize,
+    /// The number of transparent inputs.
+    pub transparent_inputs: usize,
+    /// The number of transparent outputs.
+    pub transparent_outputs: usize,
+    /// The fee the fee rule requires, per asset.
+    pub fee: U64Sum,
+    /// The transaction's per-asset value balance: inputs minus outputs,
+    /// which must equal the fee for the transaction to build.
+    pub balance: I128Sum,
+    /// The exact serialized size of the transaction, in bytes.
+    pub size: usize,
+}
+
+/// The phase of transaction building that a [`Progress`] report refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildPhase {
+    /// Creating the zero-knowledge proofs for the shielded descriptions.
+    Proving,
+    /// Creating the spend authorization and binding signatures.
+    Signing,
+}
+
 /// Reports on the progress made by the builder towards building a transaction.
 pub struct Progress {
     /// The number of steps completed.
     cur: u32,
     /// The expected total number of steps (as of this progress update), if known.
     end: Option<u32>,
+    /// The phase of building that the completed steps belong to.
+    phase: BuildPhase,
 }
 
 impl Progress {
-    pub fn new(cur: u32, end: Option<u32>) -> Self {
-        Self { cur, end }
+    pub fn new(cur: u32, end: Option<u32>, phase: BuildPhase) -> Self {
+        Self { cur, end, phase }
     }
 
     /// Returns the number of steps completed so far while building the transaction.
@@ -113,6 +242,16 @@ impl Progress {
     pub fn end(&self) -> Option<u32> {
         self.end
     }
+
+    /// Returns the phase of building that this progress update refers to.
+    ///
+    /// The step counters are per-phase: the proving phase counts proofs
+    /// created out of the total number of shielded descriptions, and the
+    /// signing phase counts signatures out of the number of spend
+    /// authorization signatures plus one for the binding signature.
+    pub fn phase(&self) -> BuildPhase {
+        self.phase
+    }
 }
 
 /// Generates a [`Transaction`] from its inputs and outputs.
@@ -121,6 +260,7 @@ pub struct Builder<P, Key = ExtendedSpendingKey, Notifier = Sender<Progress>> {
     params: P,
     target_height: BlockHeight,
     expiry_height: BlockHeight,
+    lock_time: u32,
     transparent_builder: TransparentBuilder,
     sapling_builder: SaplingBuilder<P, Key>,
     #[borsh(skip)]
@@ -138,6 +278,17 @@ impl<P, K, N> Builder<P, K, N> {
         self.target_height
     }
 
+    /// Returns the height after which the transaction under construction
+    /// will expire from the mempool.
+    pub fn expiry_height(&self) -> BlockHeight {
+        self.expiry_height
+    }
+
+    /// Returns the lock time of the transaction under construction.
+    pub fn lock_time(&self) -> u32 {
+        self.lock_time
+    }
+
     /// Returns the set of transparent inputs currently committed to be consumed
     /// by the transaction.
     pub fn transparent_inputs(&self) -> &[impl transparent::fees::InputView] {
@@ -200,12 +351,33 @@ impl<
             params: params.clone(),
             target_height,
             expiry_height: target_height + DEFAULT_TX_EXPIRY_DELTA,
+            lock_time: 0,
             transparent_builder: TransparentBuilder::empty(),
             sapling_builder: SaplingBuilder::new(params, target_height),
             progress_notifier: None,
         }
     }
 
+    /// Sets the height after which the transaction will expire from the
+    /// mempool if it has not been mined, overriding the default of the
+    /// target height plus 20 blocks. A height of zero disables expiry.
+    pub fn set_expiry_height(&mut self, expiry_height: BlockHeight) {
+        self.expiry_height = expiry_height;
+    }
+
+    /// Sets the transaction's lock time, before which it cannot be mined.
+    /// The default of zero imposes no constraint.
+    pub fn set_lock_time(&mut self, lock_time: u32) {
+        self.lock_time = lock_time;
+    }
+
+    /// Sets the minimum numbers of Sapling spend and output descriptions that
+    /// the built transaction will contain whenever it has any real spends,
+    /// padding with zero-valued dummy descriptions if necessary.
+    pub fn set_padding_rule(&mut self, padding_rule: sapling::builder::PaddingRule) {
+        self.sapling_builder.set_padding_rule(padding_rule);
+    }
+
     /// Adds a Sapling note to be spent in this transaction.
     ///
     /// Returns an error if the given Merkle path does not have the same anchor as the
@@ -236,6 +408,10 @@ impl<
     }
 
     /// Adds a Sapling address to send funds to.
+    ///
+    /// Passing `None` for `ovk` opts out of sender-side recoverability: the
+    /// outgoing ciphertext is filled with random bytes under a random key, so
+    /// not even the sender can decrypt the output afterwards.
     pub fn add_sapling_output(
         &mut self,
         ovk: Option<OutgoingViewingKey>,
@@ -251,6 +427,23 @@ impl<
             .add_output(ovk, to, asset_type, value, memo)
     }
 
+    /// Adds a Sapling address to send funds to under the refundable-payment
+    /// convention, encoding the given [`RefundTerms`] into the output's memo.
+    ///
+    /// Recipients that follow the convention are expected to return the funds
+    /// to the refund address once the expiry height passes without the payment
+    /// being acknowledged; see [`crate::wallet::plan_refund`].
+    pub fn add_refundable_sapling_output(
+        &mut self,
+        ovk: Option<OutgoingViewingKey>,
+        to: PaymentAddress,
+        asset_type: AssetType,
+        value: u64,
+        terms: &RefundTerms,
+    ) -> Result<(), sapling::builder::Error> {
+        self.add_sapling_output(ovk, to, asset_type, value, terms.encode())
+    }
+
     /// Adds a transparent coin to be spent in this transaction.
     #[cfg(feature = "transparent-inputs")]
     #[cfg_attr(docsrs, doc(cfg(feature = "transparent-inputs")))]
@@ -277,10 +470,10 @@ impl<
 
     /// Sets the notifier channel, where progress of building the transaction is sent.
     ///
-    /// An update is sent after every Spend or Output is computed, and the `u32` sent
-    /// represents the total steps completed so far. It will eventually send number of
-    /// spends + outputs. If there's an error building the transaction, the channel is
-    /// closed.
+    /// An update is sent after every Spend, Convert or Output proof is computed, and
+    /// again for each signature created during the signing phase. The counters are
+    /// per-phase; see [`Progress::phase`]. If there's an error building the
+    /// transaction, the channel is closed.
     pub fn with_progress_notifier(&mut self, progress_notifier: Sender<Progress>) {
         self.progress_notifier = Some(progress_notifier);
     }
@@ -318,6 +511,181 @@ impl<
         self.build_internal(prover, fee, rng, bparams)
     }
 
+    /// Builds a transaction like [`Builder::build`], additionally returning a
+    /// [`FeeReport`] describing the fee the fee rule required and how the
+    /// builder's inputs covered it.
+    pub fn build_with_fee_report<FR: FeeRule>(
+        self,
+        prover: &impl TxProver,
+        fee_rule: &FR,
+        rng: &mut (impl CryptoRng + RngCore),
+        bparams: &mut impl BuildParams,
+    ) -> Result<(Transaction, SaplingMetadata, FeeReport), Error<FR::Error>> {
+        let fee = fee_rule
+            .fee_required(
+                &self.params,
+                self.target_height,
+                self.transparent_builder.outputs(),
+                self.sapling_builder.inputs().len(),
+                self.sapling_builder.outputs().len(),
+            )
+            .map_err(Error::Fee)?;
+        let report = self.fee_report(&fee);
+        let (tx, tx_metadata) = self.build_internal(prover, fee, rng, bparams)?;
+        Ok((tx, tx_metadata, report))
+    }
+
+    /// Attributes the given fee to the builder's inputs and conversions.
+    fn fee_report(&self, fee: &U64Sum) -> FeeReport {
+        let funding = fee
+            .components()
+            .map(|(asset_type, fee_value)| {
+                let mut remaining = *fee_value;
+                let mut transparent_inputs = vec![];
+                let mut sapling_spends = vec![];
+
+                for (idx, input) in self.transparent_builder.inputs().iter().enumerate() {
+                    if remaining == 0 {
+                        break;
+                    }
+                    let coin = input.coin();
+                    if coin.asset_type == *asset_type {
+                        transparent_inputs.push(idx);
+                        remaining = remaining.saturating_sub(coin.value);
+                    }
+                }
+                for (idx, spend) in self.sapling_builder.inputs().iter().enumerate() {
+                    if remaining == 0 {
+                        break;
+                    }
+                    if spend.asset_type() == *asset_type {
+                        sapling_spends.push(idx);
+                        remaining = remaining.saturating_sub(spend.value());
+                    }
+                }
+
+                FeeFunding {
+                    asset_type: *asset_type,
+                    value: *fee_value,
+                    transparent_inputs,
+                    sapling_spends,
+                }
+            })
+            .collect();
+
+        FeeReport {
+            fees: fee.clone(),
+            funding,
+            conversions: self
+                .sapling_builder
+                .converts()
+                .iter()
+                .map(|c| (c.conversion().clone(), c.value()))
+                .collect(),
+        }
+    }
+
+    /// Previews the transaction this builder would produce, without creating
+    /// any proofs or signatures: the serialized size, the description counts
+    /// after padding, the fee the given fee rule requires, and the per-asset
+    /// value balance.
+    ///
+    /// The size is exact for the builder's current contents; adding further
+    /// spends or outputs invalidates it. The builder itself is unaffected, so
+    /// a wallet can show a confirmation screen and then call
+    /// [`Builder::build`] on the same builder.
+    pub fn estimate<FR: FeeRule>(
+        &self,
+        fee_rule: &FR,
+    ) -> Result<TransactionEstimate, Error<FR::Error>> {
+        let fee = fee_rule
+            .fee_required(
+                &self.params,
+                self.target_height,
+                self.transparent_builder.outputs(),
+                self.sapling_builder.inputs().len(),
+                self.sapling_builder.outputs().len(),
+            )
+            .map_err(Error::Fee)?;
+
+        let transparent_inputs = self.transparent_builder.inputs().len();
+        let transparent_outputs = self.transparent_builder.outputs().len();
+
+        // Mirror the padding the Sapling builder applies before proving.
+        let padding_rule = self.sapling_builder.padding_rule();
+        let spends = self
+            .sapling_builder
+            .inputs()
+            .len()
+            .max(padding_rule.min_spends as usize);
+        let converts = self.sapling_builder.converts().len();
+        let mut outputs = self.sapling_builder.outputs().len();
+        if spends > 0 {
+            outputs = outputs.max(MIN_SHIELDED_OUTPUTS.max(padding_rule.min_outputs as usize));
+        }
+
+        // The length of a CompactSize-prefixed count.
+        let compact_size_len = |n: usize| {
+            let mut buf = vec![];
+            CompactSize::write(&mut buf, n).expect("writing to a Vec cannot fail");
+            buf.len()
+        };
+
+        // v5 header: version, version group id, branch id, lock time, expiry
+        let mut size = 20;
+
+        // Transparent bundle: each input and output is a 32-byte asset type,
+        // an 8-byte value, and a 20-byte address.
+        size += compact_size_len(transparent_inputs) + 60 * transparent_inputs;
+        size += compact_size_len(transparent_outputs) + 60 * transparent_outputs;
+
+        if spends > 0 || converts > 0 || outputs > 0 {
+            // cv, nullifier, rk
+            size += compact_size_len(spends) + 96 * spends;
+            // cv
+            size += compact_size_len(converts) + 32 * converts;
+            // cv, cmu, ephemeral key, note and outgoing ciphertexts
+            size += compact_size_len(outputs)
+                + (96 + ENC_CIPHERTEXT_SIZE + OUT_CIPHERTEXT_SIZE) * outputs;
+
+            // The bundle's value balance is variable-length, so measure it.
+            let mut value_balance_bytes = vec![];
+            self.sapling_builder
+                .value_balance()
+                .write(&mut value_balance_bytes)
+                .expect("writing to a Vec cannot fail");
+            size += value_balance_bytes.len();
+
+            // Spend and convert anchors
+            if spends > 0 {
+                size += 32;
+            }
+            if converts > 0 {
+                size += 32;
+            }
+
+            // Proofs, spend authorization signatures, and the binding signature
+            size += (GROTH_PROOF_SIZE + 64) * spends;
+            size += GROTH_PROOF_SIZE * converts;
+            size += GROTH_PROOF_SIZE * outputs;
+            size += 64;
+        } else {
+            // Three zero CompactSizes for the absent Sapling bundle
+            size += 3;
+        }
+
+        Ok(TransactionEstimate {
+            spends,
+            converts,
+            outputs,
+            transparent_inputs,
+            transparent_outputs,
+            fee,
+            balance: self.value_balance(),
+            size,
+        })
+    }
+
     fn build_internal<FE>(
         self,
         prover: &impl TxProver,
@@ -338,7 +706,41 @@ impl<
         let balance_after_fees = self.value_balance() - I128Sum::from_sum(fee);
 
         if balance_after_fees != ValueSum::zero() {
-            return Err(Error::InsufficientFunds(-balance_after_fees));
+            // Work out, per asset, what the inputs actually supply, so the
+            // shortfall can be reported alongside the demand it fails to meet.
+            let mut available = I128Sum::zero();
+            for input in self.transparent_builder.inputs() {
+                let coin = input.coin();
+                available += I128Sum::from_pair(coin.asset_type, coin.value as i128);
+            }
+            for spend in self.sapling_builder.inputs() {
+                available += I128Sum::from_pair(spend.asset_type(), spend.value() as i128);
+            }
+            for convert in self.sapling_builder.converts() {
+                available +=
+                    I128Sum::from(convert.conversion().clone()) * (convert.value() as i128);
+            }
+
+            let shortfall = -balance_after_fees.clone();
+            let deficits: Vec<FundsDeficit> = shortfall
+                .components()
+                .filter(|(_, value)| **value > 0)
+                .map(|(asset_type, value)| {
+                    let available = available.get(asset_type);
+                    FundsDeficit {
+                        asset_type: *asset_type,
+                        required: available + value,
+                        available,
+                    }
+                })
+                .collect();
+            if deficits.is_empty() {
+                // No asset is short, so the imbalance is entirely excess input.
+                let excess =
+                    U64Sum::try_from_sum(balance_after_fees).map_err(|_| BalanceError::Overflow)?;
+                return Err(Error::ChangeRequired(excess));
+            }
+            return Err(Error::InsufficientFunds(deficits));
         };
 
         let transparent_bundle = self.transparent_builder.build();
@@ -354,12 +756,12 @@ impl<
                 self.target_height,
                 self.progress_notifier.as_ref(),
             )
-            .map_err(Error::SaplingBuild)?;
+            .map_err(Error::from)?;
 
         let unauthed_tx: TransactionData<Unauthorized<K>> = TransactionData {
             version,
             consensus_branch_id: BranchId::for_height(&self.params, self.target_height),
-            lock_time: 0,
+            lock_time: self.lock_time,
             expiry_height: self.expiry_height,
             transparent_bundle,
             sapling_bundle,
@@ -390,10 +792,11 @@ impl<
                     rng,
                     bparams,
                     shielded_sig_commitment.as_ref(),
+                    self.progress_notifier.as_ref(),
                 )
             })
             .transpose()
-            .map_err(Error::SaplingBuild)?
+            .map_err(Error::from)?
         {
             Some((bundle, meta)) => (Some(bundle), meta),
             None => (None, SaplingMetadata::empty()),
@@ -427,6 +830,7 @@ impl<P1, K1, N1> Builder<P1, K1, N1> {
             params: f.map_params(self.params),
             target_height: self.target_height,
             expiry_height: self.expiry_height,
+            lock_time: self.lock_time,
             transparent_builder: self.transparent_builder,
             progress_notifier: self.progress_notifier.map(|x| f.map_notifier(x)),
             sapling_builder: self.sapling_builder.map_builder(f),
@@ -483,14 +887,16 @@ mod tests {
         merkle_tree::{CommitmentTree, IncrementalWitness},
         sapling::Rseed,
         transaction::{
-            components::amount::{I128Sum, ValueSum, DEFAULT_FEE},
+            components::amount::{I128Sum, DEFAULT_FEE},
             sapling::builder as build_s,
             TransparentAddress,
         },
         zip32::ExtendedSpendingKey,
     };
 
-    use super::{Builder, Error};
+    use super::{BuildPhase, Builder, Error, FeeFunding, FundsDeficit};
+    use crate::sapling::prover::mock::MockTxProver;
+    use crate::transaction::fees::fixed;
 
     /*#[test]
     fn fails_on_overflow_output() {
@@ -559,11 +965,180 @@ mod tests {
             .add_transparent_output(&transparent_address, zec(), 49000)
             .unwrap();
 
-        // Expect a binding signature error, because our inputs aren't valid, but this shows
-        // that a binding signature was attempted
+        // The MockTxProver produces dummy proofs but a real binding signature,
+        // so a balanced transaction builds successfully
+        let (tx, _) = builder
+            .mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng))
+            .unwrap();
+        assert!(tx.sapling_bundle().is_some());
+    }
+
+    #[test]
+    fn progress_notifications_cover_proving_and_signing() {
+        let mut rng = OsRng;
+
+        let transparent_address = TransparentAddress(rng.gen::<[u8; 20]>());
+
+        let extsk = ExtendedSpendingKey::master(&[]);
+        let dfvk = extsk.to_diversifiable_full_viewing_key();
+        let to = dfvk.default_address().1;
+
+        let note1 = to
+            .create_note(
+                zec(),
+                50000,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap();
+        let cmu1 = note1.commitment();
+        let mut tree = CommitmentTree::empty();
+        tree.append(cmu1).unwrap();
+        let witness1 = IncrementalWitness::from_tree(&tree);
+
+        let tx_height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let mut builder = Builder::new(TEST_NETWORK, tx_height);
+
+        builder
+            .add_sapling_spend(extsk, *to.diversifier(), note1, witness1.path().unwrap())
+            .unwrap();
+
+        builder
+            .add_transparent_output(&transparent_address, zec(), 49000)
+            .unwrap();
+
+        let (notifier, receiver) = std::sync::mpsc::channel();
+        builder.with_progress_notifier(notifier);
+
+        builder
+            .mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng))
+            .unwrap();
+
+        let updates: Vec<_> = receiver.iter().collect();
+
+        // One spend plus the dummy outputs added to meet the output minimum.
+        let proving: Vec<_> = updates
+            .iter()
+            .filter(|p| p.phase() == BuildPhase::Proving)
+            .collect();
+        assert_eq!(proving.len(), 3);
+        for (i, p) in proving.iter().enumerate() {
+            assert_eq!(p.cur(), (i + 1) as u32);
+            assert_eq!(p.end(), Some(3));
+        }
+
+        // The binding signature plus one spend authorization signature.
+        let signing: Vec<_> = updates
+            .iter()
+            .filter(|p| p.phase() == BuildPhase::Signing)
+            .collect();
+        assert_eq!(signing.len(), 2);
+        for (i, p) in signing.iter().enumerate() {
+            assert_eq!(p.cur(), (i + 1) as u32);
+            assert_eq!(p.end(), Some(2));
+        }
+
+        assert_eq!(updates.len(), 5);
+    }
+
+    #[test]
+    fn fee_report_accounts_for_fee_funding_and_conversions() {
+        let mut rng = OsRng;
+
+        let transparent_address = TransparentAddress(rng.gen::<[u8; 20]>());
+
+        let extsk = ExtendedSpendingKey::master(&[]);
+        let dfvk = extsk.to_diversifiable_full_viewing_key();
+        let to = dfvk.default_address().1;
+
+        let nam0 = AssetType::new(b"NAM/0").unwrap();
+        let nam1 = AssetType::new(b"NAM/1").unwrap();
+
+        // A stale-epoch note to roll forward, and two ZEC notes whose value
+        // partly goes towards the fee.
+        let notes = [
+            to.create_note(nam0, 500, Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)))
+                .unwrap(),
+            to.create_note(
+                zec(),
+                400,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap(),
+            to.create_note(
+                zec(),
+                49600,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap(),
+        ];
+        let mut tree = CommitmentTree::empty();
+        let mut witnesses: Vec<IncrementalWitness<_>> = vec![];
+        for note in &notes {
+            tree.append(note.commitment()).unwrap();
+            for witness in witnesses.iter_mut() {
+                witness.append(note.commitment()).unwrap();
+            }
+            witnesses.push(IncrementalWitness::from_tree(&tree));
+        }
+
+        let conv: crate::convert::AllowedConversion =
+            (I128Sum::from_pair(nam0, -1) + I128Sum::from_pair(nam1, 1)).into();
+        let mut conv_tree = CommitmentTree::empty();
+        conv_tree.append(conv.commitment()).unwrap();
+        let conv_witness = IncrementalWitness::from_tree(&conv_tree);
+
+        let tx_height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let mut builder = Builder::new(TEST_NETWORK, tx_height);
+        builder
+            .add_transparent_input(crate::transaction::components::transparent::TxOut {
+                asset_type: zec(),
+                value: 300,
+                address: transparent_address,
+            })
+            .unwrap();
+        for (note, witness) in notes.into_iter().zip(&witnesses) {
+            builder
+                .add_sapling_spend(extsk, *to.diversifier(), note, witness.path().unwrap())
+                .unwrap();
+        }
+        builder
+            .add_sapling_convert(conv.clone(), 500, conv_witness.path().unwrap())
+            .unwrap();
+        builder
+            .add_sapling_output(None, to, nam1, 500, MemoBytes::empty())
+            .unwrap();
+        builder
+            .add_transparent_output(&transparent_address, zec(), 49300)
+            .unwrap();
+
+        let (tx, _, report) = builder
+            .build_with_fee_report(
+                &MockTxProver,
+                &fixed::FeeRule::standard(),
+                &mut OsRng,
+                &mut build_s::RngBuildParams::new(OsRng),
+            )
+            .unwrap();
+        assert!(tx.sapling_bundle().is_some());
+
+        assert_eq!(report.fees, DEFAULT_FEE.clone());
+        assert_eq!(report.conversions, vec![(conv, 500)]);
+
+        // The fee is attributed to the transparent coin first, then to the
+        // ZEC spends (in the order they were added) until it is covered; the
+        // stale-epoch spend plays no part in it.
         assert_eq!(
-            builder.mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng)),
-            Err(Error::SaplingBuild(build_s::Error::BindingSig))
+            report.funding,
+            vec![FeeFunding {
+                asset_type: zec(),
+                value: 1000,
+                transparent_inputs: vec![0],
+                sapling_spends: vec![1, 2],
+            }]
         );
     }
 
@@ -584,9 +1159,11 @@ mod tests {
             let builder = Builder::new(TEST_NETWORK, tx_height);
             assert_eq!(
                 builder.mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng)),
-                Err(Error::InsufficientFunds(I128Sum::from_sum(
-                    DEFAULT_FEE.clone()
-                )))
+                Err(Error::InsufficientFunds(vec![FundsDeficit {
+                    asset_type: zec(),
+                    required: 1000,
+                    available: 0,
+                }]))
             );
         }
 
@@ -603,9 +1180,11 @@ mod tests {
                 .unwrap();
             assert_eq!(
                 builder.mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng)),
-                Err(Error::InsufficientFunds(
-                    I128Sum::from_pair(zec(), 50000) + &I128Sum::from_sum(DEFAULT_FEE.clone())
-                ))
+                Err(Error::InsufficientFunds(vec![FundsDeficit {
+                    asset_type: zec(),
+                    required: 51000,
+                    available: 0,
+                }]))
             );
         }
 
@@ -618,9 +1197,11 @@ mod tests {
                 .unwrap();
             assert_eq!(
                 builder.mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng)),
-                Err(Error::InsufficientFunds(
-                    I128Sum::from_pair(zec(), 50000) + &I128Sum::from_sum(DEFAULT_FEE.clone())
-                ))
+                Err(Error::InsufficientFunds(vec![FundsDeficit {
+                    asset_type: zec(),
+                    required: 51000,
+                    available: 0,
+                }]))
             );
         }
 
@@ -651,7 +1232,11 @@ mod tests {
                 .unwrap();
             assert_eq!(
                 builder.mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng)),
-                Err(Error::InsufficientFunds(ValueSum::from_pair(zec(), 1)))
+                Err(Error::InsufficientFunds(vec![FundsDeficit {
+                    asset_type: zec(),
+                    required: 51000,
+                    available: 50999,
+                }]))
             );
         }
 
@@ -665,9 +1250,6 @@ mod tests {
 
         // Succeeds if there is sufficient input
         // 0.0003 z-ZEC out, 0.0002 t-ZEC out, 0.0001 t-ZEC fee, 0.0006 z-ZEC in
-        //
-        // (Still fails because we are using a MockTxProver which doesn't correctly
-        // compute bindingSig.)
         {
             let mut builder = Builder::new(TEST_NETWORK, tx_height);
             builder
@@ -682,10 +1264,118 @@ mod tests {
             builder
                 .add_transparent_output(&transparent_address, zec(), 20000)
                 .unwrap();
-            assert_eq!(
-                builder.mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng)),
-                Err(Error::SaplingBuild(build_s::Error::BindingSig))
-            )
+            assert!(builder
+                .mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng))
+                .is_ok());
         }
     }
+
+    #[test]
+    fn expiry_height_and_lock_time_are_settable() {
+        let tx_height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+
+        let mut rng = OsRng;
+        let extsk = ExtendedSpendingKey::master(&[]);
+        let dfvk = extsk.to_diversifiable_full_viewing_key();
+        let to = dfvk.default_address().1;
+
+        let note1 = to
+            .create_note(
+                zec(),
+                50000,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap();
+        let cmu1 = note1.commitment();
+        let mut tree = CommitmentTree::empty();
+        tree.append(cmu1).unwrap();
+        let witness1 = IncrementalWitness::from_tree(&tree);
+
+        let mut builder = Builder::new(TEST_NETWORK, tx_height);
+        // The default expiry is the target height plus the expiry delta
+        assert_eq!(builder.expiry_height(), tx_height + 20);
+        assert_eq!(builder.lock_time(), 0);
+
+        builder.set_expiry_height(tx_height + 100);
+        builder.set_lock_time(1_600_000_000);
+
+        builder
+            .add_sapling_spend(extsk, *to.diversifier(), note1, witness1.path().unwrap())
+            .unwrap();
+        builder
+            .add_transparent_output(&TransparentAddress(rng.gen::<[u8; 20]>()), zec(), 49000)
+            .unwrap();
+
+        let (tx, _) = builder
+            .mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng))
+            .unwrap();
+        assert_eq!(tx.expiry_height(), tx_height + 100);
+        assert_eq!(tx.lock_time(), 1_600_000_000);
+    }
+
+    #[test]
+    fn estimate_matches_built_transaction() {
+        let mut rng = OsRng;
+
+        let transparent_address = TransparentAddress(rng.gen::<[u8; 20]>());
+        let extsk = ExtendedSpendingKey::master(&[]);
+        let tx_height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let dfvk = extsk.to_diversifiable_full_viewing_key();
+        let ovk = Some(dfvk.fvk().ovk);
+        let to = dfvk.default_address().1;
+
+        let note1 = to
+            .create_note(
+                zec(),
+                50999,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap();
+        let note2 = to
+            .create_note(zec(), 1, Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)))
+            .unwrap();
+        let mut tree = CommitmentTree::empty();
+        tree.append(note1.commitment()).unwrap();
+        let mut witness1 = IncrementalWitness::from_tree(&tree);
+        tree.append(note2.commitment()).unwrap();
+        witness1.append(note2.commitment()).unwrap();
+        let witness2 = IncrementalWitness::from_tree(&tree);
+
+        let mut builder = Builder::new(TEST_NETWORK, tx_height);
+        builder
+            .add_sapling_spend(extsk, *to.diversifier(), note1, witness1.path().unwrap())
+            .unwrap();
+        builder
+            .add_sapling_spend(extsk, *to.diversifier(), note2, witness2.path().unwrap())
+            .unwrap();
+        builder
+            .add_sapling_output(ovk, to, zec(), 30000, MemoBytes::empty())
+            .unwrap();
+        builder
+            .add_transparent_output(&transparent_address, zec(), 20000)
+            .unwrap();
+
+        let estimate = builder.estimate(&fixed::FeeRule::standard()).unwrap();
+
+        // The single output is padded up to MIN_SHIELDED_OUTPUTS
+        assert_eq!(estimate.spends, 2);
+        assert_eq!(estimate.converts, 0);
+        assert_eq!(estimate.outputs, 2);
+        assert_eq!(estimate.transparent_inputs, 0);
+        assert_eq!(estimate.transparent_outputs, 1);
+        assert_eq!(estimate.fee, DEFAULT_FEE.clone());
+        assert_eq!(estimate.balance, I128Sum::from_sum(DEFAULT_FEE.clone()));
+
+        // The estimated size is exact
+        let (tx, _) = builder
+            .mock_build(&mut OsRng, &mut build_s::RngBuildParams::new(OsRng))
+            .unwrap();
+        let mut bytes = vec![];
+        tx.write(&mut bytes).unwrap();
+        assert_eq!(estimate.size, bytes.len());
+    }
 }
@@ -1,4 +1,5 @@
 use crate::asset_type::AssetType;
+use crate::errors::Error;
 use borsh::schema::add_definition;
 use borsh::schema::Fields;
 use borsh::schema::{Declaration, Definition};
@@ -81,7 +82,7 @@ where
     Value: BorshSerialize + BorshDeserialize + PartialEq + Eq + Copy + Default + PartialOrd,
 {
     /// Creates a non-negative ValueSum from a Value.
-    pub fn from_nonnegative(atype: Unit, amount: Value) -> Result<Self, ()> {
+    pub fn from_nonnegative(atype: Unit, amount: Value) -> Result<Self, Error> {
         if amount == Value::default() {
             Ok(Self::zero())
         } else if Value::default() <= amount {
@@ -89,7 +90,7 @@ where
             ret.insert(atype, amount);
             Ok(ValueSum(ret))
         } else {
-            Err(())
+            Err(Error::NegativeAmount)
         }
     }
 }
@@ -110,6 +111,17 @@ where
         }
     }
 
+    /// Creates a ValueSum from a list of (unit, value) pairs, summing the
+    /// values of any duplicated units.
+    pub fn from_pair_vec(pairs: Vec<(Unit, Value)>) -> Self
+    where
+        Value: CheckedAdd<Value, Output = Value>,
+    {
+        pairs.into_iter().fold(Self::zero(), |acc, (atype, amt)| {
+            acc + Self::from_pair(atype, amt)
+        })
+    }
+
     /// Filters out everything but the given AssetType from this ValueSum
     pub fn project(&self, index: Unit) -> Self {
         let val = self.0.get(&index).copied().unwrap_or_default();
@@ -158,6 +170,93 @@ where
         val.0.remove(&index);
         val
     }
+
+    /// Applies `f` to each component's value, dropping any component that
+    /// maps to zero.
+    pub fn map_values<Output>(self, mut f: impl FnMut(Value) -> Output) -> ValueSum<Unit, Output>
+    where
+        Output: BorshSerialize + BorshDeserialize + PartialEq + Eq + Default,
+    {
+        let mut comps = BTreeMap::new();
+        for (atype, amount) in self.0 {
+            comps.insert(atype, f(amount));
+        }
+        comps.retain(|_, v| *v != Output::default());
+        ValueSum(comps)
+    }
+
+    /// Keeps only the components whose unit satisfies the predicate.
+    pub fn retain(&mut self, mut f: impl FnMut(&Unit) -> bool) {
+        self.0.retain(|atype, _| f(atype));
+    }
+}
+
+impl<Unit, Value> ValueSum<Unit, Value>
+where
+    Unit: Hash + Ord + BorshSerialize + BorshDeserialize + Clone,
+    Value: BorshSerialize + BorshDeserialize + PartialEq + Eq + Copy + Default + PartialOrd,
+{
+    /// Splits this sum into its positive and negative parts, so that
+    /// `sum = positive + negative`. Values that cannot be compared to zero
+    /// are placed in the negative part.
+    pub fn split(self) -> (Self, Self) {
+        let (positive, negative) = self
+            .0
+            .into_iter()
+            .partition(|(_, amount)| *amount > Value::default());
+        (ValueSum(positive), ValueSum(negative))
+    }
+}
+
+impl<Unit, Value> ValueSum<Unit, Value>
+where
+    Unit: Hash + Ord + BorshSerialize + BorshDeserialize + Clone,
+    Value: BorshSerialize
+        + BorshDeserialize
+        + PartialEq
+        + Eq
+        + Copy
+        + Default
+        + CheckedAdd<Value, Output = Value>,
+{
+    /// Re-keys this sum by mapping each unit through `f`, summing the values
+    /// of units that map to the same new unit (e.g. collapsing epoch-tagged
+    /// asset types onto their underlying token). Returns `None` if `f` cannot
+    /// map some unit, or if summing colliding components overflows.
+    pub fn try_convert<NewUnit>(
+        self,
+        mut f: impl FnMut(&Unit) -> Option<NewUnit>,
+    ) -> Option<ValueSum<NewUnit, Value>>
+    where
+        NewUnit: Hash + Ord + BorshSerialize + BorshDeserialize + Clone,
+    {
+        let mut ret = ValueSum::zero();
+        for (atype, amount) in self.0 {
+            let new_unit = f(&atype)?;
+            ret = ret.checked_add(&ValueSum::from_pair(new_unit, amount))?;
+        }
+        Some(ret)
+    }
+}
+
+impl<Unit, Value> ValueSum<Unit, Value>
+where
+    Unit: Hash + Ord + BorshSerialize + BorshDeserialize + Clone,
+    Value: BorshSerialize + BorshDeserialize + PartialEq + Eq + Copy,
+{
+    /// Partitions this sum into one sub-sum per key produced by `f`, without
+    /// altering any component. The sub-sums add back to the original.
+    pub fn decompose<Key: Ord>(self, mut f: impl FnMut(&Unit) -> Key) -> BTreeMap<Key, Self> {
+        let mut ret: BTreeMap<Key, Self> = BTreeMap::new();
+        for (atype, amount) in self.0 {
+            let key = f(&atype);
+            ret.entry(key)
+                .or_insert_with(Self::zero)
+                .0
+                .insert(atype, amount);
+        }
+        ret
+    }
 }
 
 impl<Unit, Value> BorshSerialize for ValueSum<Unit, Value>
@@ -340,6 +439,67 @@ where
     }
 }
 
+impl<Unit, Value> std::fmt::Display for ValueSum<Unit, Value>
+where
+    Unit: Hash + Ord + BorshSerialize + BorshDeserialize + std::fmt::Display,
+    Value: BorshSerialize + BorshDeserialize + PartialEq + Eq + std::fmt::Display,
+{
+    /// Renders the sum as its components joined by ` + `, each as the signed
+    /// value followed by the unit (e.g. `-12 a1b2.. + 7 dead..`). A zero sum
+    /// renders as `0`. The output round-trips through [`std::str::FromStr`].
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "0");
+        }
+        for (i, (atype, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " + ")?;
+            }
+            write!(f, "{} {}", value, atype)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Unit, Value> std::str::FromStr for ValueSum<Unit, Value>
+where
+    Unit: Hash + Ord + BorshSerialize + BorshDeserialize + std::str::FromStr,
+    Value: BorshSerialize + BorshDeserialize + PartialEq + Eq + Copy + Default + std::str::FromStr,
+    <Unit as std::str::FromStr>::Err: std::fmt::Display,
+    <Value as std::str::FromStr>::Err: std::fmt::Display,
+{
+    type Err = std::io::Error;
+
+    /// Parses the textual form produced by the [`std::fmt::Display`]
+    /// implementation. Duplicate units are rejected so that the
+    /// representation stays canonical; zero-valued components are dropped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+        let s = s.trim();
+        if s == "0" {
+            return Ok(Self(BTreeMap::new()));
+        }
+        let mut comps = BTreeMap::new();
+        for component in s.split(" + ") {
+            let (value, atype) = component
+                .trim()
+                .split_once(' ')
+                .ok_or_else(|| invalid(format!("malformed component: {}", component)))?;
+            let value = value
+                .parse::<Value>()
+                .map_err(|e| invalid(format!("invalid value in {}: {}", component, e)))?;
+            let atype = atype
+                .parse::<Unit>()
+                .map_err(|e| invalid(format!("invalid unit in {}: {}", component, e)))?;
+            if comps.insert(atype, value).is_some() {
+                return Err(invalid(format!("duplicate unit in {}", component)));
+            }
+        }
+        comps.retain(|_, v| *v != Value::default());
+        Ok(Self(comps))
+    }
+}
+
 impl<Unit, Value> PartialOrd for ValueSum<Unit, Value>
 where
     Unit: Hash + Ord + BorshSerialize + BorshDeserialize + Clone,
@@ -718,6 +878,109 @@ impl std::fmt::Display for BalanceError {
     }
 }
 
+/// Display metadata for a single asset type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetMetadata {
+    /// The human-readable ticker symbol for the asset, e.g. `NAM`.
+    pub ticker: String,
+    /// The number of fractional decimal digits in the asset's atomic unit.
+    pub decimals: u8,
+}
+
+impl AssetMetadata {
+    pub fn new(ticker: impl Into<String>, decimals: u8) -> Self {
+        AssetMetadata {
+            ticker: ticker.into(),
+            decimals,
+        }
+    }
+}
+
+/// A mapping from asset types to their display metadata.
+///
+/// Registries are maintained out of band (e.g. from a chain's token registry);
+/// assets without an entry are rendered by their hex identifier in atomic
+/// units.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetadataRegistry(BTreeMap<AssetType, AssetMetadata>);
+
+impl MetadataRegistry {
+    /// Constructs an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers metadata for the given asset type, replacing any previous
+    /// entry.
+    pub fn insert(&mut self, atype: AssetType, metadata: AssetMetadata) {
+        self.0.insert(atype, metadata);
+    }
+
+    /// Returns the metadata for the given asset type, if registered.
+    pub fn get(&self, atype: &AssetType) -> Option<&AssetMetadata> {
+        self.0.get(atype)
+    }
+}
+
+/// A single asset's contribution to a [`ValueSum`], ready for display.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetBreakdown {
+    /// The asset type this entry describes.
+    pub asset_type: AssetType,
+    /// The registered ticker symbol, if any.
+    pub ticker: Option<String>,
+    /// The signed value in atomic units.
+    pub value: i128,
+    /// A display-ready rendering of the value, scaled by the registered
+    /// number of decimals and suffixed with the ticker where known.
+    pub formatted: String,
+}
+
+/// Renders `value` with a decimal point inserted `decimals` digits from the
+/// right.
+fn format_atomic(value: i128, decimals: u8) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let digits = value.unsigned_abs().to_string();
+    if decimals == 0 {
+        return format!("{}{}", sign, digits);
+    }
+    let decimals = decimals as usize;
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (int, frac) = padded.split_at(padded.len() - decimals);
+    format!("{}{}.{}", sign, int, frac)
+}
+
+impl ValueSum<AssetType, i128> {
+    /// Breaks this sum down into one display-ready entry per asset type,
+    /// ordered by asset identifier.
+    ///
+    /// Assets with an entry in `registry` are formatted with the registered
+    /// number of decimals and ticker; unregistered assets fall back to their
+    /// value in atomic units suffixed with the hex asset identifier.
+    pub fn to_breakdown(&self, registry: &MetadataRegistry) -> Vec<AssetBreakdown> {
+        self.components()
+            .map(|(atype, value)| match registry.get(atype) {
+                Some(metadata) => AssetBreakdown {
+                    asset_type: *atype,
+                    ticker: Some(metadata.ticker.clone()),
+                    value: *value,
+                    formatted: format!(
+                        "{} {}",
+                        format_atomic(*value, metadata.decimals),
+                        metadata.ticker
+                    ),
+                },
+                None => AssetBreakdown {
+                    asset_type: *atype,
+                    ticker: None,
+                    value: *value,
+                    formatted: format!("{} {}", value, atype),
+                },
+            })
+            .collect()
+    }
+}
+
 pub fn zec() -> AssetType {
     AssetType::new(b"ZEC").unwrap()
 }
@@ -745,6 +1008,41 @@ pub mod testing {
         }
     }
 
+    prop_compose! {
+        /// Produce an amount spanning up to `max_assets` distinct assets, with
+        /// each value bounded by `MAX_MONEY` in magnitude.
+        pub fn arb_bounded_multi_asset_i128_sum(max_assets: usize)(
+            components in proptest::collection::vec(
+                (arb_asset_type(), -(MAX_MONEY as i128)..=(MAX_MONEY as i128)),
+                0..=max_assets,
+            )
+        ) -> I128Sum {
+            components
+                .into_iter()
+                .fold(ValueSum::zero(), |acc, (asset_type, amt)| {
+                    acc + I128Sum::from_pair(asset_type, amt)
+                })
+        }
+    }
+
+    prop_compose! {
+        /// Produce a non-negative amount spanning up to `max_assets` distinct
+        /// assets. Each value is bounded such that the total cannot overflow
+        /// even if the sampled asset types collide.
+        pub fn arb_bounded_multi_asset_u64_sum(max_assets: usize)(
+            components in proptest::collection::vec(
+                (arb_asset_type(), 0u64..MAX_MONEY / (max_assets as u64 + 1)),
+                0..=max_assets,
+            )
+        ) -> U64Sum {
+            components
+                .into_iter()
+                .fold(ValueSum::zero(), |acc, (asset_type, amt)| {
+                    acc + U64Sum::from_pair(asset_type, amt)
+                })
+        }
+    }
+
     prop_compose! {
         pub fn arb_nonnegative_amount()(asset_type in arb_asset_type(), amt in 0u64..MAX_MONEY) -> U64Sum {
             ValueSum::from_pair(asset_type, amt)
@@ -760,7 +1058,140 @@ pub mod testing {
 
 #[cfg(test)]
 mod tests {
-    use super::{zec, I128Sum, I32Sum, I64Sum, ValueSum, MAX_MONEY};
+    use super::{
+        zec, AssetMetadata, I128Sum, I32Sum, I64Sum, MetadataRegistry, ValueSum, MAX_MONEY,
+    };
+    use crate::asset_type::AssetType;
+
+    #[test]
+    fn breakdown_formats_registered_and_unknown_assets() {
+        let btc = AssetType::new(b"BTC").unwrap();
+        let unknown = AssetType::new(b"mystery").unwrap();
+
+        let mut registry = MetadataRegistry::new();
+        registry.insert(zec(), AssetMetadata::new("ZEC", 8));
+        registry.insert(btc, AssetMetadata::new("BTC", 0));
+
+        let sum = I128Sum::from_pair(zec(), -123_456_789)
+            + I128Sum::from_pair(btc, 5)
+            + I128Sum::from_pair(unknown, 42);
+
+        let breakdown = sum.to_breakdown(&registry);
+        assert_eq!(breakdown.len(), 3);
+
+        let by_asset = |atype: &AssetType| {
+            breakdown
+                .iter()
+                .find(|entry| entry.asset_type == *atype)
+                .unwrap()
+        };
+
+        let zec_entry = by_asset(&zec());
+        assert_eq!(zec_entry.ticker.as_deref(), Some("ZEC"));
+        assert_eq!(zec_entry.value, -123_456_789);
+        assert_eq!(zec_entry.formatted, "-1.23456789 ZEC");
+
+        let btc_entry = by_asset(&btc);
+        assert_eq!(btc_entry.formatted, "5 BTC");
+
+        let unknown_entry = by_asset(&unknown);
+        assert_eq!(unknown_entry.ticker, None);
+        assert_eq!(unknown_entry.value, 42);
+        assert_eq!(unknown_entry.formatted, format!("42 {}", unknown));
+    }
+
+    #[test]
+    fn amount_display_round_trips_through_from_str() {
+        let btc = AssetType::new(b"BTC").unwrap();
+
+        let sum = I64Sum::from_pair_vec(vec![(zec(), -12), (btc, 7)]);
+        let encoded = sum.to_string();
+        let expected: Vec<_> = sum
+            .components()
+            .map(|(atype, value)| format!("{} {}", value, atype))
+            .collect();
+        assert_eq!(encoded, expected.join(" + "));
+        assert_eq!(encoded.parse::<I64Sum>().unwrap(), sum);
+
+        assert_eq!(I64Sum::zero().to_string(), "0");
+        assert_eq!("0".parse::<I64Sum>().unwrap(), I64Sum::zero());
+
+        assert!("12".parse::<I64Sum>().is_err());
+        assert!("1 notanasset".parse::<I64Sum>().is_err());
+        assert!(format!("1 {} + 2 {}", zec(), zec())
+            .parse::<I64Sum>()
+            .is_err());
+    }
+
+    #[test]
+    fn map_values_retain_and_split() {
+        let btc = AssetType::new(b"BTC").unwrap();
+
+        let sum = I64Sum::from_pair_vec(vec![(zec(), -12), (btc, 7)]);
+
+        let doubled = sum.clone().map_values(|v| v * 2);
+        assert_eq!(
+            doubled,
+            I64Sum::from_pair_vec(vec![(zec(), -24), (btc, 14)])
+        );
+        assert_eq!(sum.clone().map_values(|_| 0i64), I64Sum::zero());
+
+        let mut only_btc = sum.clone();
+        only_btc.retain(|atype| *atype == btc);
+        assert_eq!(only_btc, I64Sum::from_pair(btc, 7));
+
+        let (positive, negative) = sum.clone().split();
+        assert_eq!(positive, I64Sum::from_pair(btc, 7));
+        assert_eq!(negative, I64Sum::from_pair(zec(), -12));
+        assert_eq!(positive + negative, sum);
+    }
+
+    #[test]
+    fn try_convert_rekeys_and_detects_collisions() {
+        let epoch_0 = AssetType::new(b"TOK/0").unwrap();
+        let epoch_1 = AssetType::new(b"TOK/1").unwrap();
+        let token = AssetType::new(b"TOK").unwrap();
+
+        let sum = I64Sum::from_pair_vec(vec![(epoch_0, 5), (epoch_1, -2)]);
+
+        // Both epoch-tagged assets collapse onto the underlying token,
+        // preserving the total.
+        let converted = sum.clone().try_convert(|_| Some(token)).unwrap();
+        assert_eq!(converted, I64Sum::from_pair(token, 3));
+
+        // An unmappable unit fails the whole conversion.
+        assert_eq!(
+            sum.try_convert(|atype| (*atype == epoch_0).then_some(token)),
+            None
+        );
+
+        // A collision whose sum overflows is detected rather than wrapped.
+        let overflowing = I64Sum::from_pair_vec(vec![(epoch_0, i64::MAX), (epoch_1, 1)]);
+        assert_eq!(overflowing.try_convert(|_| Some(token)), None);
+    }
+
+    #[test]
+    fn decompose_partitions_by_key() {
+        let epoch_0 = AssetType::new(b"TOK/0").unwrap();
+        let epoch_1 = AssetType::new(b"TOK/1").unwrap();
+
+        let sum = I64Sum::from_pair_vec(vec![(epoch_0, 5), (epoch_1, -2)]);
+        let parts = sum.clone().decompose(|atype| *atype == epoch_0);
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[&true], I64Sum::from_pair(epoch_0, 5));
+        assert_eq!(parts[&false], I64Sum::from_pair(epoch_1, -2));
+        assert_eq!(parts.into_values().sum::<I64Sum>(), sum);
+    }
+
+    #[test]
+    fn from_pair_vec_sums_duplicate_assets() {
+        let sum = I64Sum::from_pair_vec(vec![(zec(), 5), (zec(), -5)]);
+        assert_eq!(sum, I64Sum::zero());
+
+        let sum = I64Sum::from_pair_vec(vec![(zec(), 5), (zec(), 2)]);
+        assert_eq!(sum, I64Sum::from_pair(zec(), 7));
+    }
 
     #[test]
     fn amount_in_range() {
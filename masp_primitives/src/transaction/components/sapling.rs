@@ -11,24 +11,29 @@ use std::io::{self, Read, Write};
 
 use masp_note_encryption::{
     EphemeralKeyBytes, ShieldedOutput, COMPACT_NOTE_SIZE, ENC_CIPHERTEXT_SIZE,
+    MEMOLESS_CIPHERTEXT_SIZE,
 };
 
 use borsh::schema::add_definition;
 use borsh::schema::Definition;
 use borsh::schema::Fields;
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use zcash_encoding::Vector;
 
 use crate::{
     consensus,
     sapling::{
         note_encryption::SaplingDomain,
         redjubjub::{self, PublicKey, Signature},
-        Nullifier,
+        ExtractedNoteCommitment, Nullifier,
     },
     MaybeArbitrary,
 };
 
-use super::{amount::I128Sum, GROTH_PROOF_SIZE};
+use super::{
+    amount::{I128Sum, MAX_MONEY},
+    GROTH_PROOF_SIZE,
+};
 
 pub type GrothProofBytes = [u8; GROTH_PROOF_SIZE];
 
@@ -152,13 +157,180 @@ impl<A: Authorization + PartialEq + BorshSerialize + BorshDeserialize> Bundle<A>
             authorization: f.map_authorization(self.authorization),
         }
     }
+
+    /// Performs cheap structural checks on the bundle so that mempools can
+    /// reject malformed bundles before doing any proof or signature
+    /// verification:
+    ///
+    /// - no two spends reveal the same nullifier,
+    /// - all spends share one anchor and all converts share one anchor,
+    /// - no value commitment or rerandomized verification key is of small
+    ///   order,
+    /// - every output ephemeral key is a canonical encoding of a point that
+    ///   is not of small order,
+    /// - every value balance component is within `{-MAX_MONEY..MAX_MONEY}`.
+    ///
+    /// Passing these checks does not make the bundle valid; proofs and
+    /// signatures must still be verified.
+    pub fn validate_shape(&self) -> Result<(), ShapeError> {
+        let mut nullifiers = std::collections::BTreeSet::new();
+        for spend in &self.shielded_spends {
+            if !nullifiers.insert(spend.nullifier.0) {
+                return Err(ShapeError::DuplicateNullifier(spend.nullifier));
+            }
+            if spend.anchor != self.shielded_spends[0].anchor {
+                return Err(ShapeError::SpendAnchorMismatch);
+            }
+            if spend.cv.is_small_order().into() {
+                return Err(ShapeError::SmallOrderCv);
+            }
+            if spend.rk.0.is_small_order().into() {
+                return Err(ShapeError::SmallOrderRk);
+            }
+        }
+        for convert in &self.shielded_converts {
+            if convert.anchor != self.shielded_converts[0].anchor {
+                return Err(ShapeError::ConvertAnchorMismatch);
+            }
+            if convert.cv.is_small_order().into() {
+                return Err(ShapeError::SmallOrderCv);
+            }
+        }
+        for output in &self.shielded_outputs {
+            if output.cv.is_small_order().into() {
+                return Err(ShapeError::SmallOrderCv);
+            }
+            let epk: Option<jubjub::ExtendedPoint> =
+                jubjub::ExtendedPoint::from_bytes(&output.ephemeral_key.0).into();
+            match epk {
+                Some(epk) if !bool::from(epk.is_small_order()) => {}
+                _ => return Err(ShapeError::InvalidEphemeralKey),
+            }
+        }
+        for (_, value) in self.value_balance.components() {
+            if value.unsigned_abs() > u128::from(MAX_MONEY) {
+                return Err(ShapeError::ValueBalanceOutOfRange);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Errors produced by the structural checks in [`Bundle::validate_shape`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShapeError {
+    /// Two spends within the bundle reveal the same nullifier.
+    DuplicateNullifier(Nullifier),
+    /// Not all spends are rooted at the same anchor.
+    SpendAnchorMismatch,
+    /// Not all converts are rooted at the same anchor.
+    ConvertAnchorMismatch,
+    /// A value commitment is of small order.
+    SmallOrderCv,
+    /// A spend's rerandomized verification key is of small order.
+    SmallOrderRk,
+    /// An output ephemeral key is not a canonical encoding of a point of the
+    /// right order.
+    InvalidEphemeralKey,
+    /// A value balance component exceeds `MAX_MONEY` in magnitude.
+    ValueBalanceOutOfRange,
+}
+
+impl std::fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShapeError::DuplicateNullifier(nf) => {
+                write!(f, "duplicate nullifier {}", hex::encode(nf.0))
+            }
+            ShapeError::SpendAnchorMismatch => write!(f, "spend anchors do not match"),
+            ShapeError::ConvertAnchorMismatch => write!(f, "convert anchors do not match"),
+            ShapeError::SmallOrderCv => write!(f, "value commitment is of small order"),
+            ShapeError::SmallOrderRk => {
+                write!(f, "rerandomized verification key is of small order")
+            }
+            ShapeError::InvalidEphemeralKey => write!(f, "invalid output ephemeral key"),
+            ShapeError::ValueBalanceOutOfRange => {
+                write!(f, "value balance component out of range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+/// The root of the Sapling note commitment tree that spend descriptions are
+/// proven against.
+///
+/// This is a distinct type from [`ConvertAnchor`] so that roots of the two
+/// trees cannot be mixed up.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Anchor(pub bls12_381::Scalar);
+
+impl Anchor {
+    /// Parses an anchor from its canonical 32-byte encoding, returning `None`
+    /// if the bytes are not a canonical field element encoding.
+    pub fn from_bytes(bytes: [u8; 32]) -> Option<Self> {
+        Option::from(bls12_381::Scalar::from_repr(bytes)).map(Anchor)
+    }
+
+    /// Returns the canonical 32-byte encoding of the anchor.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+}
+
+impl From<bls12_381::Scalar> for Anchor {
+    fn from(root: bls12_381::Scalar) -> Self {
+        Anchor(root)
+    }
+}
+
+impl From<crate::sapling::Node> for Anchor {
+    fn from(root: crate::sapling::Node) -> Self {
+        Anchor(root.into())
+    }
+}
+
+/// The root of the allowed-conversion tree that convert descriptions are
+/// proven against.
+///
+/// This is a distinct type from [`Anchor`] so that roots of the two trees
+/// cannot be mixed up.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConvertAnchor(pub bls12_381::Scalar);
+
+impl ConvertAnchor {
+    /// Parses an anchor from its canonical 32-byte encoding, returning `None`
+    /// if the bytes are not a canonical field element encoding.
+    pub fn from_bytes(bytes: [u8; 32]) -> Option<Self> {
+        Option::from(bls12_381::Scalar::from_repr(bytes)).map(ConvertAnchor)
+    }
+
+    /// Returns the canonical 32-byte encoding of the anchor.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+}
+
+impl From<bls12_381::Scalar> for ConvertAnchor {
+    fn from(root: bls12_381::Scalar) -> Self {
+        ConvertAnchor(root)
+    }
+}
+
+impl From<crate::sapling::Node> for ConvertAnchor {
+    fn from(root: crate::sapling::Node) -> Self {
+        ConvertAnchor(root.into())
+    }
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Clone, PartialEq, Eq)]
 pub struct SpendDescription<A: Authorization + PartialEq> {
     pub cv: jubjub::ExtendedPoint,
-    pub anchor: bls12_381::Scalar,
+    pub anchor: Anchor,
     pub nullifier: Nullifier,
     pub rk: PublicKey,
     pub zkproof: A::Proof,
@@ -177,8 +349,8 @@ impl<A: Authorization + PartialEq> std::fmt::Debug for SpendDescription<A> {
 
 /// Consensus rules (§4.4) & (§4.5):
 /// - Canonical encoding is enforced here.
-/// - "Not small order" is enforced in SaplingVerificationContext::(check_spend()/check_output())
-///   (located in zcash_proofs::sapling::verifier).
+/// - "Not small order" is enforced here, so that invalid points are rejected as
+///   soon as they are parsed rather than in SaplingVerificationContext.
 pub fn read_point<R: Read>(mut reader: R, field: &str) -> io::Result<jubjub::ExtendedPoint> {
     let mut bytes = [0u8; 32];
     reader.read_exact(&mut bytes)?;
@@ -190,7 +362,15 @@ pub fn read_point<R: Read>(mut reader: R, field: &str) -> io::Result<jubjub::Ext
             format!("invalid {}", field),
         ))
     } else {
-        Ok(point.unwrap())
+        let point = point.unwrap();
+        if point.is_small_order().into() {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is of small order", field),
+            ))
+        } else {
+            Ok(point)
+        }
     }
 }
 
@@ -207,6 +387,15 @@ pub fn read_base<R: Read>(mut reader: R, field: &str) -> io::Result<bls12_381::S
     })
 }
 
+/// Consensus rules (§4.5):
+/// - Canonical encoding of cmu is enforced here.
+pub fn read_cmu<R: Read>(mut reader: R) -> io::Result<ExtractedNoteCommitment> {
+    let mut f = [0u8; 32];
+    reader.read_exact(&mut f)?;
+    Option::from(ExtractedNoteCommitment::from_bytes(&f))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cmu not in field"))
+}
+
 /// Consensus rules (§4.4) & (§4.5):
 /// - Canonical encoding is enforced by the API of SaplingVerificationContext::check_spend()
 ///   and SaplingVerificationContext::check_output() due to the need to parse this into a
@@ -228,9 +417,18 @@ impl SpendDescription<Authorized> {
 
     /// Consensus rules (§4.4):
     /// - Canonical encoding is enforced here.
-    /// - "Not small order" is enforced in SaplingVerificationContext::check_spend()
+    /// - "Not small order" is enforced here, ahead of
+    ///   SaplingVerificationContext::check_spend()
     pub fn read_rk<R: Read>(mut reader: R) -> io::Result<PublicKey> {
-        PublicKey::read(&mut reader)
+        let rk = PublicKey::read(&mut reader)?;
+        if rk.0.is_small_order().into() {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "rk is of small order",
+            ))
+        } else {
+            Ok(rk)
+        }
     }
 
     /// Consensus rules (§4.4):
@@ -265,7 +463,7 @@ impl SpendDescriptionV5 {
 
     pub fn into_spend_description(
         self,
-        anchor: bls12_381::Scalar,
+        anchor: Anchor,
         zkproof: GrothProofBytes,
         spend_auth_sig: Signature,
     ) -> SpendDescription<Authorized> {
@@ -312,7 +510,7 @@ impl BorshSchema for SpendDescriptionV5 {
 #[derive(Clone, PartialEq, Eq)]
 pub struct OutputDescription<Proof: Clone> {
     pub cv: jubjub::ExtendedPoint,
-    pub cmu: bls12_381::Scalar,
+    pub cmu: ExtractedNoteCommitment,
     pub ephemeral_key: EphemeralKeyBytes,
     pub enc_ciphertext: [u8; 580 + 32],
     pub out_ciphertext: [u8; 80],
@@ -337,7 +535,7 @@ impl<P: consensus::Parameters, Proof: Clone> ShieldedOutput<SaplingDomain<P>, EN
     }
 
     fn cmstar_bytes(&self) -> [u8; 32] {
-        self.cmu.to_repr()
+        self.cmu.to_bytes()
     }
 
     fn enc_ciphertext(&self) -> &[u8; ENC_CIPHERTEXT_SIZE] {
@@ -361,7 +559,7 @@ where
 impl OutputDescription<GrothProofBytes> {
     pub fn write_v5_without_proof<W: Write>(&self, mut writer: W) -> io::Result<()> {
         writer.write_all(&self.cv.to_bytes())?;
-        writer.write_all(self.cmu.to_repr().as_ref())?;
+        writer.write_all(&self.cmu.to_bytes())?;
         writer.write_all(self.ephemeral_key.as_ref())?;
         writer.write_all(&self.enc_ciphertext)?;
         writer.write_all(&self.out_ciphertext)
@@ -371,7 +569,7 @@ impl OutputDescription<GrothProofBytes> {
 #[derive(Clone)]
 pub struct OutputDescriptionV5 {
     pub cv: jubjub::ExtendedPoint,
-    pub cmu: bls12_381::Scalar,
+    pub cmu: ExtractedNoteCommitment,
     pub ephemeral_key: EphemeralKeyBytes,
     pub enc_ciphertext: [u8; 580 + 32],
     pub out_ciphertext: [u8; 80],
@@ -382,13 +580,15 @@ memuse::impl_no_dynamic_usage!(OutputDescriptionV5);
 impl OutputDescriptionV5 {
     pub fn read<R: Read>(mut reader: &mut R) -> io::Result<Self> {
         let cv = read_point(&mut reader, "cv")?;
-        let cmu = read_base(&mut reader, "cmu")?;
+        let cmu = read_cmu(&mut reader)?;
 
         // Consensus rules (§4.5):
-        // - Canonical encoding is enforced in librustzcash_sapling_check_output by zcashd
-        // - "Not small order" is enforced in SaplingVerificationContext::check_output()
+        // - Canonical encoding and "not small order" are enforced here, ahead of
+        //   SaplingVerificationContext::check_output(). The raw bytes are kept
+        //   for note decryption.
         let mut ephemeral_key = EphemeralKeyBytes([0u8; 32]);
         reader.read_exact(&mut ephemeral_key.0)?;
+        read_point(&ephemeral_key.0[..], "ephemeral key")?;
 
         let mut enc_ciphertext = [0u8; 580 + 32];
         let mut out_ciphertext = [0u8; 80];
@@ -453,7 +653,7 @@ impl BorshSchema for OutputDescriptionV5 {
 #[derive(Clone)]
 pub struct CompactOutputDescription {
     pub ephemeral_key: EphemeralKeyBytes,
-    pub cmu: bls12_381::Scalar,
+    pub cmu: ExtractedNoteCommitment,
     pub enc_ciphertext: [u8; COMPACT_NOTE_SIZE],
 }
 
@@ -477,13 +677,47 @@ impl<P: consensus::Parameters> ShieldedOutput<SaplingDomain<P>, COMPACT_NOTE_SIZ
     }
 
     fn cmstar_bytes(&self) -> [u8; 32] {
-        self.cmu.to_repr()
+        self.cmu.to_bytes()
     }
 
     fn enc_ciphertext(&self) -> &[u8; COMPACT_NOTE_SIZE] {
         &self.enc_ciphertext
     }
 }
+
+/// An output whose ciphertext carries only the authenticated compact note
+/// fields, for chains that store memos off-band.
+///
+/// Unlike [`CompactOutputDescription`], this cannot be derived from a full
+/// [`OutputDescription`] by truncation: the AEAD tag covers exactly the
+/// compact note fields, so the ciphertext must be produced with
+/// [`NoteEncryption::encrypt_memoless_note_plaintext`].
+///
+/// [`NoteEncryption::encrypt_memoless_note_plaintext`]: masp_note_encryption::NoteEncryption::encrypt_memoless_note_plaintext
+#[derive(Clone)]
+pub struct MemolessOutputDescription {
+    pub ephemeral_key: EphemeralKeyBytes,
+    pub cmu: ExtractedNoteCommitment,
+    pub enc_ciphertext: [u8; MEMOLESS_CIPHERTEXT_SIZE],
+}
+
+memuse::impl_no_dynamic_usage!(MemolessOutputDescription);
+
+impl<P: consensus::Parameters> ShieldedOutput<SaplingDomain<P>, MEMOLESS_CIPHERTEXT_SIZE>
+    for MemolessOutputDescription
+{
+    fn ephemeral_key(&self) -> EphemeralKeyBytes {
+        self.ephemeral_key.clone()
+    }
+
+    fn cmstar_bytes(&self) -> [u8; 32] {
+        self.cmu.to_bytes()
+    }
+
+    fn enc_ciphertext(&self) -> &[u8; MEMOLESS_CIPHERTEXT_SIZE] {
+        &self.enc_ciphertext
+    }
+}
 impl<Proof: Clone + PartialOrd> PartialOrd for OutputDescription<Proof> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         (
@@ -522,7 +756,7 @@ impl<Proof: Clone + Hash> Hash for OutputDescription<Proof> {
 #[derive(Clone, PartialEq, Eq)]
 pub struct ConvertDescription<Proof: PartialEq> {
     pub cv: jubjub::ExtendedPoint,
-    pub anchor: bls12_381::Scalar,
+    pub anchor: ConvertAnchor,
     pub zkproof: Proof,
 }
 
@@ -562,11 +796,77 @@ impl<Proof: PartialEq> std::fmt::Debug for ConvertDescription<Proof> {
 }
 
 impl ConvertDescription<GrothProofBytes> {
+    /// Consensus rules (§4.4) & (§4.5):
+    /// - Canonical encoding of cv and anchor is enforced here.
+    /// - "Not small order" and proof validity are enforced in
+    ///   SaplingVerificationContext::check_convert().
+    pub fn read<R: Read>(mut reader: &mut R) -> io::Result<Self> {
+        let cv = read_point(&mut reader, "cv")?;
+        let anchor = read_base(&mut reader, "anchor").map(ConvertAnchor)?;
+        let zkproof = read_zkproof(&mut reader)?;
+
+        Ok(ConvertDescription {
+            cv,
+            anchor,
+            zkproof,
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.cv.to_bytes())?;
+        writer.write_all(&self.anchor.to_bytes())?;
+        writer.write_all(&self.zkproof)
+    }
+
     pub fn write_v5_without_witness_data<W: Write>(&self, mut writer: W) -> io::Result<()> {
         writer.write_all(&self.cv.to_bytes())
     }
 }
 
+/// A standalone collection of convert descriptions sharing a single anchor
+/// into the host chain's allowed-conversion tree.
+///
+/// The anchor a conversion is proven against is a claim about which
+/// [`AllowedConversion`]s the chain has authorized, so host chains must check
+/// it against their own tree roots; [`ConvertBundle::check_anchor`] performs
+/// the structural half of that check.
+///
+/// [`AllowedConversion`]: crate::convert::AllowedConversion
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvertBundle {
+    pub shielded_converts: Vec<ConvertDescription<GrothProofBytes>>,
+    pub anchor: ConvertAnchor,
+}
+
+impl ConvertBundle {
+    pub fn read<R: Read>(mut reader: &mut R) -> io::Result<Self> {
+        let shielded_converts = Vector::read(&mut reader, ConvertDescription::read)?;
+        let anchor = read_base(&mut reader, "anchor").map(ConvertAnchor)?;
+
+        Ok(ConvertBundle {
+            shielded_converts,
+            anchor,
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        Vector::write(&mut writer, &self.shielded_converts, |w, convert| {
+            convert.write(w)
+        })?;
+        writer.write_all(&self.anchor.to_bytes())
+    }
+
+    /// Checks that every convert description in the bundle is proven against
+    /// the bundle anchor, and that the bundle anchor is one of the roots the
+    /// host chain recognizes (as reported by `is_known_root`).
+    pub fn check_anchor(&self, is_known_root: impl FnOnce(&ConvertAnchor) -> bool) -> bool {
+        self.shielded_converts
+            .iter()
+            .all(|convert| convert.anchor == self.anchor)
+            && is_known_root(&self.anchor)
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 pub struct ConvertDescriptionV5 {
     pub cv: jubjub::ExtendedPoint,
@@ -584,7 +884,7 @@ impl ConvertDescriptionV5 {
     }
     pub fn into_convert_description(
         self,
-        anchor: bls12_381::Scalar,
+        anchor: ConvertAnchor,
         zkproof: GrothProofBytes,
     ) -> ConvertDescription<GrothProofBytes> {
         ConvertDescription {
@@ -617,6 +917,179 @@ impl BorshSchema for ConvertDescriptionV5 {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use ff::PrimeField;
+    use group::{Group, GroupEncoding};
+
+    use super::{
+        Anchor, Authorized, Bundle, ConvertAnchor, ConvertBundle, ConvertDescription,
+        ExtractedNoteCommitment, OutputDescription, ShapeError, SpendDescription, GROTH_PROOF_SIZE,
+    };
+    use crate::asset_type::AssetType;
+    use crate::sapling::{redjubjub, Nullifier};
+    use crate::transaction::components::amount::ValueSum;
+    use masp_note_encryption::EphemeralKeyBytes;
+
+    fn point() -> jubjub::ExtendedPoint {
+        jubjub::ExtendedPoint::generator()
+    }
+
+    fn spend(nullifier: u8) -> SpendDescription<Authorized> {
+        SpendDescription {
+            cv: point(),
+            anchor: Anchor(bls12_381::Scalar::one()),
+            nullifier: Nullifier([nullifier; 32]),
+            rk: redjubjub::PublicKey(point()),
+            zkproof: [0u8; GROTH_PROOF_SIZE],
+            spend_auth_sig: redjubjub::Signature::read(&[0u8; 64][..]).unwrap(),
+        }
+    }
+
+    fn output() -> OutputDescription<[u8; GROTH_PROOF_SIZE]> {
+        OutputDescription {
+            cv: point(),
+            cmu: Option::from(ExtractedNoteCommitment::from_bytes(
+                &bls12_381::Scalar::one().to_repr(),
+            ))
+            .unwrap(),
+            ephemeral_key: EphemeralKeyBytes(point().to_bytes()),
+            enc_ciphertext: [0u8; 580 + 32],
+            out_ciphertext: [0u8; 80],
+            zkproof: [0u8; GROTH_PROOF_SIZE],
+        }
+    }
+
+    fn bundle() -> Bundle<Authorized> {
+        Bundle {
+            shielded_spends: vec![spend(1), spend(2)],
+            shielded_converts: vec![ConvertDescription {
+                cv: point(),
+                anchor: ConvertAnchor(bls12_381::Scalar::one()),
+                zkproof: [0u8; GROTH_PROOF_SIZE],
+            }],
+            shielded_outputs: vec![output()],
+            value_balance: ValueSum::from_pair(AssetType::new(b"NAM").unwrap(), 1_000i128),
+            authorization: Authorized {
+                binding_sig: redjubjub::Signature::read(&[0u8; 64][..]).unwrap(),
+            },
+        }
+    }
+
+    #[test]
+    fn well_formed_bundle_passes() {
+        assert_eq!(bundle().validate_shape(), Ok(()));
+    }
+
+    #[test]
+    fn duplicate_nullifiers_are_rejected() {
+        let mut bundle = bundle();
+        bundle.shielded_spends[1].nullifier = bundle.shielded_spends[0].nullifier;
+        assert_eq!(
+            bundle.validate_shape(),
+            Err(ShapeError::DuplicateNullifier(Nullifier([1; 32])))
+        );
+    }
+
+    #[test]
+    fn mismatched_anchors_are_rejected() {
+        let mut bundle = bundle();
+        bundle.shielded_spends[1].anchor = Anchor(bls12_381::Scalar::zero());
+        assert_eq!(
+            bundle.validate_shape(),
+            Err(ShapeError::SpendAnchorMismatch)
+        );
+    }
+
+    #[test]
+    fn small_order_points_are_rejected() {
+        let mut with_bad_cv = bundle();
+        with_bad_cv.shielded_spends[0].cv = jubjub::ExtendedPoint::identity();
+        assert_eq!(with_bad_cv.validate_shape(), Err(ShapeError::SmallOrderCv));
+
+        let mut with_bad_rk = bundle();
+        with_bad_rk.shielded_spends[0].rk = redjubjub::PublicKey(jubjub::ExtendedPoint::identity());
+        assert_eq!(with_bad_rk.validate_shape(), Err(ShapeError::SmallOrderRk));
+
+        let mut with_bad_epk = bundle();
+        with_bad_epk.shielded_outputs[0].ephemeral_key =
+            EphemeralKeyBytes(jubjub::ExtendedPoint::identity().to_bytes());
+        assert_eq!(
+            with_bad_epk.validate_shape(),
+            Err(ShapeError::InvalidEphemeralKey)
+        );
+    }
+
+    #[test]
+    fn small_order_points_are_rejected_at_parse_time() {
+        let identity = jubjub::ExtendedPoint::identity().to_bytes();
+        // The order-2 point (0, -1).
+        let order_two = (-bls12_381::Scalar::one()).to_repr();
+        let non_canonical = [0xff; 32];
+
+        for bad in [identity, order_two, non_canonical] {
+            assert!(super::read_point(&bad[..], "cv").is_err());
+            assert!(SpendDescription::read_rk(&bad[..]).is_err());
+        }
+        assert!(super::read_point(&point().to_bytes()[..], "cv").is_ok());
+
+        // An OutputDescriptionV5 with a small-order ephemeral key is rejected.
+        let output = output();
+        let mut bytes = vec![];
+        bytes.extend_from_slice(&output.cv.to_bytes());
+        bytes.extend_from_slice(&output.cmu.to_bytes());
+        bytes.extend_from_slice(output.ephemeral_key.as_ref());
+        bytes.extend_from_slice(&output.enc_ciphertext);
+        bytes.extend_from_slice(&output.out_ciphertext);
+        assert!(super::OutputDescriptionV5::read(&mut &bytes[..]).is_ok());
+
+        bytes[64..96].copy_from_slice(&identity);
+        assert!(super::OutputDescriptionV5::read(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn convert_bundle_round_trips_and_checks_anchor() {
+        let convert = |anchor| ConvertDescription {
+            cv: point(),
+            anchor,
+            zkproof: [0u8; GROTH_PROOF_SIZE],
+        };
+        let bundle = ConvertBundle {
+            shielded_converts: vec![
+                convert(ConvertAnchor(bls12_381::Scalar::one())),
+                convert(ConvertAnchor(bls12_381::Scalar::one())),
+            ],
+            anchor: ConvertAnchor(bls12_381::Scalar::one()),
+        };
+
+        let mut bytes = vec![];
+        bundle.write(&mut bytes).unwrap();
+        let decoded = ConvertBundle::read(&mut &bytes[..]).unwrap();
+        assert_eq!(decoded, bundle);
+
+        // A non-canonical cv encoding is rejected at parse time.
+        bytes[1..33].copy_from_slice(&[0xff; 32]);
+        assert!(ConvertBundle::read(&mut &bytes[..]).is_err());
+
+        assert!(bundle.check_anchor(|root| *root == ConvertAnchor(bls12_381::Scalar::one())));
+        assert!(!bundle.check_anchor(|root| *root == ConvertAnchor(bls12_381::Scalar::zero())));
+
+        let mut mismatched = bundle;
+        mismatched.shielded_converts[1].anchor = ConvertAnchor(bls12_381::Scalar::zero());
+        assert!(!mismatched.check_anchor(|_| true));
+    }
+
+    #[test]
+    fn out_of_range_value_balance_is_rejected() {
+        let mut bundle = bundle();
+        bundle.value_balance = ValueSum::from_pair(AssetType::new(b"NAM").unwrap(), i128::MAX);
+        assert_eq!(
+            bundle.validate_shape(),
+            Err(ShapeError::ValueBalanceOutOfRange)
+        );
+    }
+}
+
 #[cfg(any(test, feature = "test-dependencies"))]
 pub mod testing {
     use ff::Field;
@@ -629,7 +1102,7 @@ pub mod testing {
         constants::{SPENDING_KEY_GENERATOR, VALUE_COMMITMENT_RANDOMNESS_GENERATOR},
         sapling::{
             redjubjub::{PrivateKey, PublicKey},
-            Nullifier,
+            ExtractedNoteCommitment, Nullifier,
         },
         transaction::{
             components::{amount::testing::arb_i128_sum, GROTH_PROOF_SIZE},
@@ -638,8 +1111,8 @@ pub mod testing {
     };
 
     use super::{
-        Authorized, Bundle, ConvertDescription, GrothProofBytes, OutputDescription,
-        SpendDescription,
+        Anchor, Authorized, Bundle, ConvertAnchor, ConvertDescription, GrothProofBytes,
+        OutputDescription, SpendDescription,
     };
 
     prop_compose! {
@@ -657,7 +1130,7 @@ pub mod testing {
             cv in arb_extended_point(),
             anchor in vec(any::<u8>(), 64)
                 .prop_map(|v| <[u8;64]>::try_from(v.as_slice()).unwrap())
-                .prop_map(|v| bls12_381::Scalar::from_bytes_wide(&v)),
+                .prop_map(|v| Anchor(bls12_381::Scalar::from_bytes_wide(&v))),
             nullifier in prop::array::uniform32(any::<u8>())
                 .prop_map(|v| Nullifier::from_slice(&v).unwrap()),
             zkproof in vec(any::<u8>(), GROTH_PROOF_SIZE)
@@ -686,7 +1159,7 @@ pub mod testing {
             cv in arb_extended_point(),
             cmu in vec(any::<u8>(), 64)
                 .prop_map(|v| <[u8;64]>::try_from(v.as_slice()).unwrap())
-                .prop_map(|v| bls12_381::Scalar::from_bytes_wide(&v)),
+                .prop_map(|v| ExtractedNoteCommitment(bls12_381::Scalar::from_bytes_wide(&v))),
             enc_ciphertext in vec(any::<u8>(), 580+32)
                 .prop_map(|v| <[u8;580+32]>::try_from(v.as_slice()).unwrap()),
             epk in arb_extended_point(),
@@ -747,7 +1220,7 @@ pub mod testing {
             cv in arb_extended_point(),
             anchor in vec(any::<u8>(), 64)
                 .prop_map(|v| <[u8;64]>::try_from(v.as_slice()).unwrap())
-                .prop_map(|v| bls12_381::Scalar::from_bytes_wide(&v)),
+                .prop_map(|v| ConvertAnchor(bls12_381::Scalar::from_bytes_wide(&v))),
             zkproof in vec(any::<u8>(), GROTH_PROOF_SIZE)
                 .prop_map(|v| <[u8;GROTH_PROOF_SIZE]>::try_from(v.as_slice()).unwrap()),
         ) -> ConvertDescription<GrothProofBytes> {
@@ -1,5 +1,6 @@
 //! Types and functions for building MASP shielded transaction components.
 
+use blake2b_simd::Hash as Blake2bHash;
 use core::fmt;
 use std::sync::mpsc::Sender;
 
@@ -12,25 +13,27 @@ use crate::MaybeArbitrary;
 use crate::{
     asset_type::AssetType,
     consensus::{self, BlockHeight},
+    constants::SPENDING_KEY_GENERATOR,
     convert::AllowedConversion,
-    keys::OutgoingViewingKey,
+    keys::{prf_expand_vec, OutgoingViewingKey},
     memo::MemoBytes,
-    merkle_tree::MerklePath,
+    merkle_tree::{CommitmentTree, MerklePath},
     sapling::{
         note_encryption::sapling_note_encryption,
         prover::TxProver,
-        redjubjub::{PrivateKey, Signature},
+        redjubjub::{PrivateKey, PublicKey, Signature},
         spend_sig_internal,
-        util::generate_random_rseed_internal,
+        util::{generate_random_rseed, generate_random_rseed_internal},
         Diversifier, Node, Note, PaymentAddress, ProofGenerationKey, Rseed,
+        SAPLING_COMMITMENT_TREE_DEPTH,
     },
     transaction::{
-        builder::Progress,
+        builder::{BuildPhase, Progress},
         components::{
             amount::{I128Sum, ValueSum, MAX_MONEY},
             sapling::{
-                fees, Authorization, Authorized, Bundle, ConvertDescription, GrothProofBytes,
-                OutputDescription, SpendDescription,
+                fees, Anchor, Authorization, Authorized, Bundle, ConvertAnchor, ConvertDescription,
+                GrothProofBytes, OutputDescription, SpendDescription,
             },
         },
     },
@@ -60,6 +63,18 @@ pub trait BuildParams {
     fn output_rcm(&mut self, i: usize) -> jubjub::Fr;
     /// Get the random seed for the ith output description
     fn output_rseed(&mut self, i: usize) -> [u8; 32];
+
+    /// Derive the re-randomized verification key that the ith spend
+    /// description commits to, from the spend's verification key `ak`.
+    ///
+    /// This performs the same `ak.randomize(spend_alpha(i))` computation as
+    /// the prover, so an external signer holding the same build parameters
+    /// (e.g. a [`StoredBuildParams`] shared by the builder) can reproduce the
+    /// exact `rk` appearing in the proof and authorize the spend with
+    /// [`PrivateKey::randomize`] under the same alpha.
+    fn spend_rk(&mut self, i: usize, ak: PublicKey) -> PublicKey {
+        ak.randomize(self.spend_alpha(i), SPENDING_KEY_GENERATOR)
+    }
 }
 
 // Allow build parameters to be boxed
@@ -382,9 +397,106 @@ impl<R: CryptoRng + RngCore> BuildParams for RngBuildParams<R> {
     }
 }
 
+/// Build parameters derived deterministically from a seed.
+///
+/// Every request is answered by a BLAKE2b PRF of the seed, a domain tag, and
+/// the description index, so the same seed yields the same parameters
+/// regardless of the order in which the builder asks for them. This makes
+/// transaction builds reproducible for testing; a predictable seed forfeits
+/// the privacy the randomness normally provides, so do not use this in
+/// production.
+#[derive(Clone, Debug)]
+pub struct SeededBuildParams {
+    seed: [u8; 32],
+}
+
+impl SeededBuildParams {
+    /// Constructs build parameters derived from the given seed.
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self { seed }
+    }
+
+    fn expand(&self, tag: u8, i: usize) -> Blake2bHash {
+        prf_expand_vec(&self.seed, &[&[tag], &(i as u64).to_le_bytes()])
+    }
+
+    fn derive_fr(&self, tag: u8, i: usize) -> jubjub::Fr {
+        jubjub::Fr::from_bytes_wide(self.expand(tag, i).as_array())
+    }
+}
+
+impl BuildParams for SeededBuildParams {
+    fn spend_rcv(&mut self, i: usize) -> jubjub::Fr {
+        self.derive_fr(0x00, i)
+    }
+
+    fn spend_alpha(&mut self, i: usize) -> jubjub::Fr {
+        self.derive_fr(0x01, i)
+    }
+
+    fn convert_rcv(&mut self, i: usize) -> jubjub::Fr {
+        self.derive_fr(0x02, i)
+    }
+
+    fn output_rcv(&mut self, i: usize) -> jubjub::Fr {
+        self.derive_fr(0x03, i)
+    }
+
+    fn output_rcm(&mut self, i: usize) -> jubjub::Fr {
+        self.derive_fr(0x04, i)
+    }
+
+    fn output_rseed(&mut self, i: usize) -> [u8; 32] {
+        let mut rseed = [0u8; 32];
+        rseed.copy_from_slice(&self.expand(0x05, i).as_bytes()[..32]);
+        rseed
+    }
+}
+
 /// If there are any shielded inputs, always have at least two shielded outputs, padding
 /// with dummy outputs if necessary. See <https://github.com/zcash/zcash/issues/3615>.
-const MIN_SHIELDED_OUTPUTS: usize = 2;
+pub const MIN_SHIELDED_OUTPUTS: usize = 2;
+
+/// A policy specifying the minimum numbers of spend and output descriptions
+/// that a shielded bundle must contain.
+///
+/// The builder tops up the spend and output description counts to these
+/// minimums with zero-valued dummy descriptions before shuffling, so that
+/// transactions built with the same rule are indistinguishable by their
+/// description counts. Dummy spends are valid under the MASP circuit even
+/// without a real anchor because the anchor is only enforced for notes of
+/// non-zero value, and their value commitments are uniformly distributed
+/// since the commitment to a zero value is just the randomness term.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct PaddingRule {
+    /// The minimum number of spend descriptions
+    pub min_spends: u32,
+    /// The minimum number of output descriptions. Values below
+    /// [`MIN_SHIELDED_OUTPUTS`] are treated as that floor.
+    pub min_outputs: u32,
+}
+
+impl PaddingRule {
+    /// Construct a padding rule with the given minimum description counts
+    pub fn new(min_spends: u32, min_outputs: u32) -> Self {
+        Self {
+            min_spends,
+            min_outputs,
+        }
+    }
+}
+
+impl Default for PaddingRule {
+    /// The historical padding behavior: no dummy spends, and at least
+    /// [`MIN_SHIELDED_OUTPUTS`] outputs whenever there are spends.
+    fn default() -> Self {
+        Self {
+            min_spends: 0,
+            min_outputs: MIN_SHIELDED_OUTPUTS as u32,
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
@@ -466,6 +578,54 @@ impl<Key: BorshDeserialize> BorshDeserialize for SpendDescriptionInfo<Key> {
     }
 }
 
+impl<Key: ExtendedKey> SpendDescriptionInfo<Key> {
+    /// Generates a dummy spend of a zero-valued note addressed to a throwaway
+    /// key, with a random Merkle path. The circuit only enforces the anchor
+    /// for notes of non-zero value, so the random path suffices.
+    ///
+    /// This is the same sampling the builder uses when padding a bundle, so
+    /// dummies generated here are indistinguishable from the builder's own.
+    pub fn dummy<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let rseed = Rseed::AfterZip212({
+            let mut buffer = [0u8; 32];
+            rng.fill_bytes(&mut buffer);
+            buffer
+        });
+        Self::dummy_internal(rng, rseed)
+    }
+
+    fn dummy_internal<R: RngCore>(rng: &mut R, rseed: Rseed) -> Self {
+        let extsk = Key::from_dummy_seed(&{
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            seed
+        });
+        let (_, dummy_to) = extsk.to_viewing_key().default_address();
+        let dummy_note = Note::from_parts(
+            AssetType::new(b"dummy").unwrap(),
+            0,
+            dummy_to.g_d().expect("dummy address must be valid"),
+            *dummy_to.pk_d(),
+            rseed,
+        );
+        let position = u64::from(rng.next_u32());
+        let auth_path = (0..SAPLING_COMMITMENT_TREE_DEPTH)
+            .map(|depth| {
+                (
+                    Node::from_scalar(bls12_381::Scalar::random(&mut *rng)),
+                    (position >> depth) & 1 == 1,
+                )
+            })
+            .collect();
+        SpendDescriptionInfo {
+            extsk,
+            diversifier: *dummy_to.diversifier(),
+            note: dummy_note,
+            merkle_path: MerklePath::from_path(auth_path, position),
+        }
+    }
+}
+
 impl<K> fees::InputView<(), K> for SpendDescriptionInfo<K> {
     fn note_id(&self) -> &() {
         // The builder does not make use of note identifiers, so we can just return the unit value.
@@ -510,13 +670,7 @@ impl SaplingOutputInfo {
             return Err(Error::InvalidAmount);
         }
 
-        let note = Note {
-            g_d,
-            pk_d: *to.pk_d(),
-            value,
-            rseed: (),
-            asset_type,
-        };
+        let note = Note::from_parts(asset_type, value, g_d, *to.pk_d(), ());
 
         Ok(SaplingOutputInfo {
             ovk,
@@ -526,7 +680,7 @@ impl SaplingOutputInfo {
         })
     }
 
-    fn build<P: consensus::Parameters, Pr: TxProver, R: RngCore>(
+    fn build<P: consensus::Parameters, Pr: TxProver, R: CryptoRng + RngCore>(
         self,
         prover: &Pr,
         ctx: &mut Pr::SaplingProvingContext,
@@ -534,17 +688,18 @@ impl SaplingOutputInfo {
         rcv: jubjub::Fr,
         rseed: Rseed,
     ) -> OutputDescription<GrothProofBytes> {
-        let note = Note {
+        let note = Note::from_parts(
+            self.note.asset_type,
+            self.note.value,
+            self.note.g_d,
+            self.note.pk_d,
             rseed,
-            value: self.note.value,
-            g_d: self.note.g_d,
-            pk_d: self.note.pk_d,
-            asset_type: self.note.asset_type,
-        };
+        );
         let encryptor = sapling_note_encryption::<P>(self.ovk, note, self.to, self.memo);
 
         let (zkproof, cv) = prover.output_proof(
             ctx,
+            rng,
             *encryptor.esk(),
             self.to,
             note.rcm(),
@@ -553,7 +708,7 @@ impl SaplingOutputInfo {
             rcv,
         );
 
-        let cmu = note.cmu();
+        let cmu = note.cmstar();
 
         let enc_ciphertext = encryptor.encrypt_note_plaintext();
         let out_ciphertext = encryptor.encrypt_outgoing_plaintext(&cv, &cmu, rng);
@@ -639,13 +794,14 @@ impl SaplingMetadata {
 #[derive(Clone, Debug)]
 pub struct SaplingBuilder<P, Key = ExtendedSpendingKey> {
     params: P,
-    spend_anchor: Option<bls12_381::Scalar>,
+    spend_anchor: Option<Anchor>,
     target_height: BlockHeight,
     value_balance: I128Sum,
-    convert_anchor: Option<bls12_381::Scalar>,
+    convert_anchor: Option<ConvertAnchor>,
     spends: Vec<SpendDescriptionInfo<Key>>,
     converts: Vec<ConvertDescriptionInfo>,
     outputs: Vec<SaplingOutputInfo>,
+    padding_rule: PaddingRule,
 }
 
 impl<P: BorshSchema, Key: BorshSchema> BorshSchema for SaplingBuilder<P, Key> {
@@ -666,6 +822,7 @@ impl<P: BorshSchema, Key: BorshSchema> BorshSchema for SaplingBuilder<P, Key> {
                     Vec::<ConvertDescriptionInfo>::declaration(),
                 ),
                 ("outputs".into(), Vec::<SaplingOutputInfo>::declaration()),
+                ("padding_rule".into(), PaddingRule::declaration()),
             ]),
         };
         add_definition(Self::declaration(), definition, definitions);
@@ -676,6 +833,7 @@ impl<P: BorshSchema, Key: BorshSchema> BorshSchema for SaplingBuilder<P, Key> {
         Vec::<SpendDescriptionInfo<Key>>::add_definitions_recursively(definitions);
         Vec::<ConvertDescriptionInfo>::add_definitions_recursively(definitions);
         Vec::<SaplingOutputInfo>::add_definitions_recursively(definitions);
+        PaddingRule::add_definitions_recursively(definitions);
     }
 
     fn declaration() -> Declaration {
@@ -698,28 +856,30 @@ impl<P: BorshSerialize, Key: BorshSerialize> BorshSerialize for SaplingBuilder<P
             .serialize(writer)?;
         self.spends.serialize(writer)?;
         self.converts.serialize(writer)?;
-        self.outputs.serialize(writer)
+        self.outputs.serialize(writer)?;
+        self.padding_rule.serialize(writer)
     }
 }
 
 impl<P: BorshDeserialize, Key: BorshDeserialize> BorshDeserialize for SaplingBuilder<P, Key> {
     fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
         let params = P::deserialize_reader(reader)?;
-        let spend_anchor: Option<Option<_>> = Option::<[u8; 32]>::deserialize_reader(reader)?
-            .map(|x| bls12_381::Scalar::from_bytes(&x).into());
+        let spend_anchor: Option<Option<_>> =
+            Option::<[u8; 32]>::deserialize_reader(reader)?.map(Anchor::from_bytes);
         let spend_anchor = spend_anchor
             .map(|x| x.ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData)))
             .transpose()?;
         let target_height = BlockHeight::deserialize_reader(reader)?;
         let value_balance = I128Sum::deserialize_reader(reader)?;
-        let convert_anchor: Option<Option<_>> = Option::<[u8; 32]>::deserialize_reader(reader)?
-            .map(|x| bls12_381::Scalar::from_bytes(&x).into());
+        let convert_anchor: Option<Option<_>> =
+            Option::<[u8; 32]>::deserialize_reader(reader)?.map(ConvertAnchor::from_bytes);
         let convert_anchor = convert_anchor
             .map(|x| x.ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidData)))
             .transpose()?;
         let spends = Vec::<SpendDescriptionInfo<Key>>::deserialize_reader(reader)?;
         let converts = Vec::<ConvertDescriptionInfo>::deserialize_reader(reader)?;
         let outputs = Vec::<SaplingOutputInfo>::deserialize_reader(reader)?;
+        let padding_rule = PaddingRule::deserialize_reader(reader)?;
         Ok(SaplingBuilder {
             params,
             spend_anchor,
@@ -729,6 +889,7 @@ impl<P: BorshDeserialize, Key: BorshDeserialize> BorshDeserialize for SaplingBui
             spends,
             converts,
             outputs,
+            padding_rule,
         })
     }
 }
@@ -764,9 +925,21 @@ impl<P, K> SaplingBuilder<P, K> {
             spends: vec![],
             converts: vec![],
             outputs: vec![],
+            padding_rule: PaddingRule::default(),
         }
     }
 
+    /// Returns the padding rule that will be applied when building the bundle.
+    pub fn padding_rule(&self) -> PaddingRule {
+        self.padding_rule
+    }
+
+    /// Sets the minimum numbers of spend and output descriptions that the
+    /// built bundle will contain whenever it has any real spends.
+    pub fn set_padding_rule(&mut self, padding_rule: PaddingRule) {
+        self.padding_rule = padding_rule;
+    }
+
     /// Returns the list of Sapling inputs that will be consumed by the transaction being
     /// constructed.
     pub fn inputs(&self) -> &[impl fees::InputView<(), K>] {
@@ -806,7 +979,7 @@ impl<
         // Consistency check: all anchors must equal the first one
         let node = note.commitment();
         if let Some(anchor) = self.spend_anchor {
-            let path_root: bls12_381::Scalar = merkle_path.root(node).into();
+            let path_root: Anchor = merkle_path.root(node).into();
             if path_root != anchor {
                 return Err(Error::AnchorMismatch);
             }
@@ -840,7 +1013,7 @@ impl<
 
         let node = allowed.commitment();
         if let Some(anchor) = self.convert_anchor {
-            let path_root: bls12_381::Scalar = merkle_path.root(node).into();
+            let path_root: ConvertAnchor = merkle_path.root(node).into();
             if path_root != anchor {
                 return Err(Error::AnchorMismatch);
             }
@@ -891,7 +1064,7 @@ impl<
         // Record initial positions of spends and outputs
         let value_balance = self.value_balance();
         let params = self.params;
-        let mut indexed_spends: Vec<_> = self.spends.into_iter().enumerate().collect();
+        let mut indexed_spends: Vec<_> = self.spends.into_iter().enumerate().map(Some).collect();
         let mut indexed_converts: Vec<_> = self.converts.into_iter().enumerate().collect();
         let mut indexed_outputs: Vec<_> = self
             .outputs
@@ -909,9 +1082,16 @@ impl<
             .resize(indexed_converts.len(), 0);
         tx_metadata.output_indices.resize(indexed_outputs.len(), 0);
 
-        // Pad Sapling outputs
+        // Pad Sapling spends and outputs. Dummy spends are zero-valued, and
+        // the circuit leaves the anchor unconstrained for zero-value notes,
+        // so spends are padded even when no real spend (and hence no real
+        // anchor) is present.
+        while indexed_spends.len() < self.padding_rule.min_spends as usize {
+            indexed_spends.push(None);
+        }
         if !indexed_spends.is_empty() {
-            while indexed_outputs.len() < MIN_SHIELDED_OUTPUTS {
+            let min_outputs = MIN_SHIELDED_OUTPUTS.max(self.padding_rule.min_outputs as usize);
+            while indexed_outputs.len() < min_outputs {
                 indexed_outputs.push(None);
             }
         }
@@ -922,20 +1102,36 @@ impl<
         indexed_outputs.shuffle(rng);
 
         // Keep track of the total number of steps computed
-        let total_progress = indexed_spends.len() as u32 + indexed_outputs.len() as u32;
+        let total_progress = indexed_spends.len() as u32
+            + indexed_converts.len() as u32
+            + indexed_outputs.len() as u32;
         let mut progress = 0u32;
 
         // Create Sapling SpendDescriptions
         let shielded_spends: Vec<SpendDescription<Unauthorized<K>>> = if !indexed_spends.is_empty()
         {
+            // A real spend fixes the anchor when it is added. If every spend
+            // is a dummy, any anchor is valid under the circuit (the root is
+            // only enforced for notes of non-zero value); expose the empty
+            // tree root, which every chain recognizes.
             let anchor = self
                 .spend_anchor
-                .expect("MASP Spend anchor must be set if MASP spends are present.");
+                .unwrap_or_else(|| CommitmentTree::<Node>::empty().root().into());
 
             indexed_spends
                 .into_iter()
                 .enumerate()
-                .map(|(i, (pos, spend))| {
+                .map(|(i, spend)| {
+                    let (pos, spend) = match spend {
+                        Some((pos, spend)) => (Some(pos), spend),
+                        None => {
+                            // This is a dummy spend of a zero-valued note
+                            // addressed to a throwaway key.
+                            let rseed = generate_random_rseed(&params, target_height, rng);
+                            (None, SpendDescriptionInfo::dummy_internal(rng, rseed))
+                        }
+                    };
+
                     let proof_generation_key = spend
                         .extsk
                         .to_proof_generation_key()
@@ -949,27 +1145,34 @@ impl<
                     let (zkproof, cv, rk) = prover
                         .spend_proof(
                             ctx,
+                            &mut *rng,
                             proof_generation_key,
                             spend.diversifier,
                             spend.note.rseed,
                             bparams.spend_alpha(i),
                             spend.note.asset_type,
                             spend.note.value,
-                            anchor,
+                            anchor.0,
                             spend.merkle_path.clone(),
                             bparams.spend_rcv(i),
                         )
                         .map_err(|_| Error::SpendProof)?;
 
                     // Record the post-randomized spend location
-                    tx_metadata.spend_indices[pos] = i;
+                    if let Some(pos) = pos {
+                        tx_metadata.spend_indices[pos] = i;
+                    }
 
                     // Update progress and send a notification on the channel
                     progress += 1;
                     if let Some(sender) = progress_notifier {
                         // If the send fails, we should ignore the error, not crash.
                         sender
-                            .send(Progress::new(progress, Some(total_progress)))
+                            .send(Progress::new(
+                                progress,
+                                Some(total_progress),
+                                BuildPhase::Proving,
+                            ))
                             .unwrap_or(());
                     }
 
@@ -1001,9 +1204,10 @@ impl<
                         let (zkproof, cv) = prover
                             .convert_proof(
                                 ctx,
+                                &mut *rng,
                                 convert.allowed.clone(),
                                 convert.value,
-                                anchor,
+                                anchor.0,
                                 convert.merkle_path,
                                 bparams.convert_rcv(i),
                             )
@@ -1017,7 +1221,11 @@ impl<
                         if let Some(sender) = progress_notifier {
                             // If the send fails, we should ignore the error, not crash.
                             sender
-                                .send(Progress::new(progress, Some(total_progress)))
+                                .send(Progress::new(
+                                    progress,
+                                    Some(total_progress),
+                                    BuildPhase::Proving,
+                                ))
                                 .unwrap_or(());
                         }
 
@@ -1080,13 +1288,13 @@ impl<
 
                         (
                             payment_address,
-                            Note {
+                            Note::from_parts(
+                                AssetType::new(b"dummy").unwrap(),
+                                0,
                                 g_d,
                                 pk_d,
                                 rseed,
-                                value: 0,
-                                asset_type: AssetType::new(b"dummy").unwrap(),
-                            },
+                            ),
                         )
                     };
 
@@ -1095,6 +1303,7 @@ impl<
 
                     let (zkproof, cv) = prover.output_proof(
                         ctx,
+                        &mut *rng,
                         esk,
                         dummy_to,
                         dummy_note.rcm(),
@@ -1103,7 +1312,7 @@ impl<
                         bparams.output_rcv(i),
                     );
 
-                    let cmu = dummy_note.cmu();
+                    let cmu = dummy_note.cmstar();
 
                     let mut enc_ciphertext = [0u8; 580 + 32];
                     let mut out_ciphertext = [0u8; 80];
@@ -1125,7 +1334,11 @@ impl<
                 if let Some(sender) = progress_notifier {
                     // If the send fails, we should ignore the error, not crash.
                     sender
-                        .send(Progress::new(progress, Some(total_progress)))
+                        .send(Progress::new(
+                            progress,
+                            Some(total_progress),
+                            BuildPhase::Proving,
+                        ))
                         .unwrap_or(());
                 }
 
@@ -1170,17 +1383,36 @@ impl<K: ExtendedKey + Debug + Clone + PartialEq + for<'a> MaybeArbitrary<'a>>
 impl<K: ExtendedKey + Debug + Clone + PartialEq + for<'a> MaybeArbitrary<'a>>
     Bundle<Unauthorized<K>>
 {
-    pub fn apply_signatures<Pr: TxProver, R: RngCore, S: BuildParams>(
+    pub fn apply_signatures<Pr: TxProver, R: CryptoRng + RngCore, S: BuildParams>(
         self,
         prover: &Pr,
         ctx: &mut Pr::SaplingProvingContext,
         rng: &mut R,
         bparams: &mut S,
         sighash_bytes: &[u8; 32],
+        progress_notifier: Option<&Sender<Progress>>,
     ) -> Result<(Bundle<Authorized>, SaplingMetadata), Error> {
+        // Keep track of the total number of signatures computed
+        let total_progress = self.shielded_spends.len() as u32 + 1;
+        let mut progress = 0u32;
+        let notify = |progress: u32| {
+            if let Some(sender) = progress_notifier {
+                // If the send fails, we should ignore the error, not crash.
+                sender
+                    .send(Progress::new(
+                        progress,
+                        Some(total_progress),
+                        BuildPhase::Signing,
+                    ))
+                    .unwrap_or(());
+            }
+        };
+
         let binding_sig = prover
-            .binding_sig(ctx, &self.value_balance, sighash_bytes)
+            .binding_sig(ctx, &mut *rng, &self.value_balance, sighash_bytes)
             .map_err(|_| Error::BindingSig)?;
+        progress += 1;
+        notify(progress);
 
         Ok((
             Bundle {
@@ -1189,12 +1421,18 @@ impl<K: ExtendedKey + Debug + Clone + PartialEq + for<'a> MaybeArbitrary<'a>>
                     .iter()
                     .enumerate()
                     .map(|(i, spend)| {
-                        spend.apply_signature(spend_sig_internal(
+                        let signed = spend.apply_signature(spend_sig_internal(
                             PrivateKey(spend.spend_auth_sig.extsk.to_spending_key().expect("Spend authorization key must be known for each MASP spend.").expsk.ask),
                             bparams.spend_alpha(i),
                             sighash_bytes,
                             rng,
-                        ))
+                        ));
+
+                        // Update progress and send a notification on the channel
+                        progress += 1;
+                        notify(progress);
+
+                        signed
                     })
                     .collect(),
                 shielded_converts: self.shielded_converts,
@@ -1264,6 +1502,7 @@ impl<P1, K1> SaplingBuilder<P1, K1> {
             convert_anchor: self.convert_anchor,
             converts: self.converts,
             outputs: self.outputs,
+            padding_rule: self.padding_rule,
             spends: self
                 .spends
                 .into_iter()
@@ -1291,7 +1530,7 @@ pub mod testing {
         },
         merkle_tree::{testing::arb_commitment_tree, IncrementalWitness},
         sapling::{
-            prover::mock::MockTxProver,
+            prover::{mock::MockTxProver, TxProver},
             testing::{arb_node, arb_note, arb_positive_note_value},
             Diversifier,
         },
@@ -1336,11 +1575,12 @@ pub mod testing {
             }
 
             let prover = MockTxProver;
+            let mut ctx = prover.new_sapling_proving_context();
             let mut bparams = RngBuildParams::new(StdRng::from_seed(bparams_seed));
 
             let bundle = builder.build(
                 &prover,
-                &mut (),
+                &mut ctx,
                 &mut rng,
                 &mut bparams,
                 target_height.unwrap(),
@@ -1349,13 +1589,194 @@ pub mod testing {
 
             let (bundle, _) = bundle.apply_signatures(
                 &prover,
-                &mut (),
+                &mut ctx,
                 &mut rng,
                 &mut bparams,
                 &fake_sighash_bytes,
+                None,
             ).unwrap();
 
             bundle
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ff::Field;
+    use rand_core::OsRng;
+
+    use super::{
+        BuildParams, PaddingRule, RngBuildParams, SaplingBuilder, SeededBuildParams,
+        SpendDescriptionInfo,
+    };
+    use crate::{
+        asset_type::AssetType,
+        consensus::{NetworkUpgrade, Parameters, TEST_NETWORK},
+        merkle_tree::{CommitmentTree, IncrementalWitness},
+        sapling::{
+            prover::{mock::MockTxProver, TxProver},
+            Rseed,
+        },
+        transaction::components::amount::ValueSum,
+        zip32::{ExtendedKey, ExtendedSpendingKey},
+    };
+
+    #[test]
+    fn external_signer_reproduces_spend_rk() {
+        use crate::constants::SPENDING_KEY_GENERATOR;
+        use crate::sapling::redjubjub::{PrivateKey, PublicKey};
+
+        let extsk = ExtendedSpendingKey::master(&[0x44; 32]);
+        let ak = PublicKey(extsk.expsk.proof_generation_key().ak.into());
+
+        // The builder samples alpha lazily; a signer replaying the stored
+        // parameters lands on the same rk the prover committed to.
+        let mut bparams = RngBuildParams::new(OsRng);
+        let rk = bparams.spend_rk(0, ak);
+        let mut stored = bparams.to_stored().unwrap();
+        assert_eq!(stored.spend_rk(0, ak), rk);
+        assert_eq!(
+            rk,
+            ak.randomize(stored.spend_alpha(0), SPENDING_KEY_GENERATOR)
+        );
+
+        // A signature under the correspondingly re-randomized private key
+        // verifies under rk, while the unrandomized key does not.
+        let rsk = PrivateKey(extsk.expsk.ask).randomize(stored.spend_alpha(0));
+        let msg = b"spend authorization sighash";
+        let sig = rsk.sign(msg, &mut OsRng, SPENDING_KEY_GENERATOR);
+        assert!(rk.verify(msg, &sig, SPENDING_KEY_GENERATOR));
+        assert!(!ak.verify(msg, &sig, SPENDING_KEY_GENERATOR));
+    }
+
+    #[test]
+    fn dummy_spends_are_zero_valued_and_spendable() {
+        let mut rng = OsRng;
+
+        let dummy = SpendDescriptionInfo::<ExtendedSpendingKey>::dummy(&mut rng);
+        assert_eq!(dummy.note.value, 0);
+        // The throwaway key can produce the proof generation key the prover
+        // will need for this spend.
+        assert!(dummy.extsk.to_proof_generation_key().is_some());
+
+        let other = SpendDescriptionInfo::<ExtendedSpendingKey>::dummy(&mut rng);
+        assert_ne!(dummy.merkle_path, other.merkle_path);
+    }
+
+    #[test]
+    fn padding_rule_pads_spends_and_outputs_with_dummies() {
+        let mut rng = OsRng;
+
+        let extsk = ExtendedSpendingKey::master(&[]);
+        let to = extsk.default_address().1;
+        let asset_type = AssetType::new(b"padding").unwrap();
+        let note = to
+            .create_note(
+                asset_type,
+                100,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap();
+        let mut tree = CommitmentTree::empty();
+        tree.append(note.commitment()).unwrap();
+        let witness = IncrementalWitness::from_tree(&tree);
+        let merkle_path = witness.path().unwrap();
+        let expected_nf = note.nf(
+            &extsk.expsk.proof_generation_key().to_viewing_key().nk,
+            merkle_path.position,
+        );
+
+        let tx_height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let mut builder = SaplingBuilder::new(TEST_NETWORK, tx_height);
+        builder.set_padding_rule(PaddingRule::new(3, 4));
+        builder
+            .add_spend(extsk, *to.diversifier(), note, merkle_path)
+            .unwrap();
+
+        let prover = MockTxProver;
+        let mut ctx = prover.new_sapling_proving_context();
+        let mut bparams = RngBuildParams::new(OsRng);
+        let bundle = builder
+            .build(&prover, &mut ctx, &mut rng, &mut bparams, tx_height, None)
+            .unwrap()
+            .unwrap();
+
+        // The bundle is topped up to the requested description counts
+        assert_eq!(bundle.shielded_spends.len(), 3);
+        assert_eq!(bundle.shielded_outputs.len(), 4);
+
+        // The metadata still points at the lone real spend, and the
+        // zero-valued dummies leave the value balance untouched
+        let meta = &bundle.authorization.tx_metadata;
+        let idx = meta.spend_index(0).unwrap();
+        assert_eq!(bundle.shielded_spends[idx].nullifier, expected_nf);
+        assert_eq!(
+            bundle.value_balance,
+            ValueSum::from_pair(asset_type, 100i128)
+        );
+
+        // Dummy spends carry their own throwaway keys, so signing succeeds
+        let (signed, _) = bundle
+            .apply_signatures(&prover, &mut ctx, &mut rng, &mut bparams, &[0u8; 32], None)
+            .unwrap();
+        assert_eq!(signed.shielded_spends.len(), 3);
+    }
+
+    #[test]
+    fn dummy_only_bundle_needs_no_anchor() {
+        let mut rng = OsRng;
+
+        let tx_height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let mut builder = SaplingBuilder::<_, ExtendedSpendingKey>::new(TEST_NETWORK, tx_height);
+        builder.set_padding_rule(PaddingRule::new(2, 2));
+
+        let prover = MockTxProver;
+        let mut ctx = prover.new_sapling_proving_context();
+        let mut bparams = RngBuildParams::new(OsRng);
+        let bundle = builder
+            .build(&prover, &mut ctx, &mut rng, &mut bparams, tx_height, None)
+            .unwrap()
+            .unwrap();
+
+        // No real spend ever fixed an anchor, so the dummies expose the
+        // empty tree root, which the circuit does not enforce for
+        // zero-valued notes.
+        assert_eq!(bundle.shielded_spends.len(), 2);
+        assert_eq!(bundle.shielded_outputs.len(), 2);
+        let empty_root = CommitmentTree::<crate::sapling::Node>::empty()
+            .root()
+            .into();
+        for spend in &bundle.shielded_spends {
+            assert_eq!(spend.anchor, empty_root);
+        }
+        assert_eq!(bundle.value_balance, ValueSum::zero());
+
+        let (signed, _) = bundle
+            .apply_signatures(&prover, &mut ctx, &mut rng, &mut bparams, &[0u8; 32], None)
+            .unwrap();
+        assert_eq!(signed.shielded_spends.len(), 2);
+    }
+
+    #[test]
+    fn seeded_build_params_are_deterministic_and_domain_separated() {
+        let mut a = SeededBuildParams::new([7u8; 32]);
+        let mut b = SeededBuildParams::new([7u8; 32]);
+        let mut other = SeededBuildParams::new([8u8; 32]);
+
+        // The same seed yields the same parameters, in any query order.
+        assert_eq!(a.output_rcv(1), b.output_rcv(1));
+        assert_eq!(a.spend_rcv(0), b.spend_rcv(0));
+        assert_eq!(a.output_rseed(3), b.output_rseed(3));
+
+        // Different seeds, tags, and indices all diverge.
+        assert_ne!(a.spend_rcv(0), other.spend_rcv(0));
+        assert_ne!(a.spend_rcv(0), a.spend_alpha(0));
+        assert_ne!(a.spend_rcv(0), a.output_rcv(0));
+        assert_ne!(a.spend_rcv(0), a.spend_rcv(1));
+    }
+}
@@ -22,6 +22,34 @@ pub trait Authorization: fmt::Debug {
     type TransparentSig: fmt::Debug + Clone + PartialEq + for<'a> MaybeArbitrary<'a>;
 }
 
+/// The address type carried by transparent inputs and outputs.
+///
+/// MASP does not interpret transparent addresses; it only moves value between
+/// them and the shielded pool. The host chain provides serialization and
+/// validation through this trait, so address types other than the default
+/// 20-byte [`TransparentAddress`] — Namada addresses, EVM addresses — can
+/// appear directly in shielding and unshielding transactions.
+pub trait HostAddress: Clone + fmt::Debug + PartialEq {
+    /// Reads an address from its canonical encoding, rejecting encodings the
+    /// host chain considers invalid.
+    fn read<R: Read>(reader: &mut R) -> io::Result<Self>;
+
+    /// Writes the address's canonical encoding.
+    fn write<W: Write>(&self, writer: W) -> io::Result<()>;
+}
+
+impl HostAddress for TransparentAddress {
+    fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut tmp = [0u8; 20];
+        reader.read_exact(&mut tmp)?;
+        Ok(TransparentAddress(tmp))
+    }
+
+    fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct Authorized;
@@ -57,14 +85,14 @@ impl MapAuth<Authorized, Authorized> for () {
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Clone, PartialEq)]
-pub struct Bundle<A: Authorization> {
-    pub vin: Vec<TxIn<A>>,
-    pub vout: Vec<TxOut>,
+pub struct Bundle<A: Authorization, TA = TransparentAddress> {
+    pub vin: Vec<TxIn<A, TA>>,
+    pub vout: Vec<TxOut<TA>>,
     pub authorization: A,
 }
 
-impl<A: Authorization> Bundle<A> {
-    pub fn map_authorization<B: Authorization, F: MapAuth<A, B>>(self, f: F) -> Bundle<B> {
+impl<A: Authorization, TA> Bundle<A, TA> {
+    pub fn map_authorization<B: Authorization, F: MapAuth<A, B>>(self, f: F) -> Bundle<B, TA> {
         Bundle {
             vin: self
                 .vin
@@ -110,14 +138,14 @@ impl<A: Authorization> Bundle<A> {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-pub struct TxIn<A: Authorization> {
+pub struct TxIn<A: Authorization, TA = TransparentAddress> {
     pub asset_type: AssetType,
     pub value: u64,
-    pub address: TransparentAddress,
+    pub address: TA,
     pub transparent_sig: A::TransparentSig,
 }
 
-impl TxIn<Authorized> {
+impl<TA: HostAddress> TxIn<Authorized, TA> {
     pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
         let asset_type = AssetType::read(reader)?;
         let value = {
@@ -131,11 +159,7 @@ impl TxIn<Authorized> {
                 "value out of range",
             ));
         }
-        let address = {
-            let mut tmp = [0u8; 20];
-            reader.read_exact(&mut tmp)?;
-            TransparentAddress(tmp)
-        };
+        let address = TA::read(reader)?;
 
         Ok(TxIn {
             asset_type,
@@ -148,7 +172,7 @@ impl TxIn<Authorized> {
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         writer.write_all(self.asset_type.get_identifier())?;
         writer.write_all(&self.value.to_le_bytes())?;
-        writer.write_all(&self.address.0)
+        self.address.write(&mut writer)
     }
 }
 
@@ -188,13 +212,13 @@ impl BorshSchema for TxIn<Authorized> {
 
 #[derive(Clone, Debug, Hash, PartialOrd, PartialEq, Ord, Eq)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-pub struct TxOut {
+pub struct TxOut<TA = TransparentAddress> {
     pub asset_type: AssetType,
     pub value: u64,
-    pub address: TransparentAddress,
+    pub address: TA,
 }
 
-impl TxOut {
+impl<TA: HostAddress> TxOut<TA> {
     pub fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
         let asset_type = AssetType::read(reader)?;
         let value = {
@@ -209,11 +233,7 @@ impl TxOut {
             ));
         }
 
-        let address = {
-            let mut tmp = [0u8; 20];
-            reader.read_exact(&mut tmp)?;
-            TransparentAddress(tmp)
-        };
+        let address = TA::read(reader)?;
 
         Ok(TxOut {
             asset_type,
@@ -225,12 +245,12 @@ impl TxOut {
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         writer.write_all(self.asset_type.get_identifier())?;
         writer.write_all(&self.value.to_le_bytes())?;
-        writer.write_all(&self.address.0)
+        self.address.write(&mut writer)
     }
 
     /// Returns the address to which the TxOut was sent, if this is a valid P2SH or P2PKH output.
-    pub fn recipient_address(&self) -> TransparentAddress {
-        self.address
+    pub fn recipient_address(&self) -> TA {
+        self.address.clone()
     }
 }
 
@@ -347,4 +367,53 @@ mod test_serialization {
         let deserialized = TxOut::read::<&[u8]>(&mut buf.as_ref()).expect("Test failed");
         assert_eq!(deserialized, txout);
     }
+
+    /// A host-chain address: a length-prefixed byte string that must be
+    /// non-empty.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct MockHostAddress(Vec<u8>);
+
+    impl HostAddress for MockHostAddress {
+        fn read<R: Read>(reader: &mut R) -> io::Result<Self> {
+            let mut len = [0u8; 1];
+            reader.read_exact(&mut len)?;
+            if len[0] == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "empty host address",
+                ));
+            }
+            let mut bytes = vec![0u8; len[0] as usize];
+            reader.read_exact(&mut bytes)?;
+            Ok(MockHostAddress(bytes))
+        }
+
+        fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+            writer.write_all(&[self.0.len() as u8])?;
+            writer.write_all(&self.0)
+        }
+    }
+
+    /// A round trip through a host-provided address type is the identity, and
+    /// the host's validation rejects bad encodings.
+    #[test]
+    fn test_roundtrip_host_address_txout() {
+        let asset_type = AssetType::new_with_nonce(&[1, 2, 3, 4], 1).expect("Test failed");
+        let txout = TxOut {
+            asset_type,
+            value: MAX_MONEY - 1,
+            address: MockHostAddress(b"namada1qxy".to_vec()),
+        };
+
+        let mut buf = vec![];
+        txout.write(&mut buf).expect("Test failed");
+        let deserialized =
+            TxOut::<MockHostAddress>::read::<&[u8]>(&mut buf.as_ref()).expect("Test failed");
+        assert_eq!(deserialized, txout);
+
+        // A zero-length address is rejected by the host's validation.
+        let addr_offset = buf.len() - 1 - txout.address.0.len();
+        buf[addr_offset] = 0;
+        assert!(TxOut::<MockHostAddress>::read::<&[u8]>(&mut buf.as_ref()).is_err());
+    }
 }
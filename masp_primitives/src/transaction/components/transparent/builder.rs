@@ -108,6 +108,10 @@ impl TransparentBuilder {
     /// Adds a coin (the output of a previous transaction) to be spent to the transaction.
     #[cfg(feature = "transparent-inputs")]
     pub fn add_input(&mut self, coin: TxOut) -> Result<(), Error> {
+        if coin.value > MAX_MONEY {
+            return Err(Error::InvalidAmount);
+        }
+
         self.inputs.push(TransparentInputInfo { coin });
 
         Ok(())
@@ -70,6 +70,10 @@ pub fn signature_hash<
     txid_parts: &TxDigests<Blake2bHash>,
 ) -> SignatureHash {
     SignatureHash(match tx.version {
-        TxVersion::MASPv5 => v5_signature_hash(tx, signable_input, txid_parts),
+        // A future minor version shares the MASPv5 body, so its recognized
+        // fields are hashed the same way.
+        TxVersion::MASPv5 | TxVersion::MASPFuture(_) => {
+            v5_signature_hash(tx, signable_input, txid_parts)
+        }
     })
 }
@@ -0,0 +1,304 @@
+//! Streaming parser for MASP v5 transactions.
+//!
+//! [`Transaction::read`] materializes every bundle before returning, so
+//! parsing a multi-megabyte transaction costs memory proportional to the
+//! whole encoding. [`TransactionReader`] instead walks the wire format in
+//! order and hands each description to a [`TransactionVisitor`] as soon as it
+//! is decoded, keeping memory bounded by the largest single item. Proofs and
+//! signatures are delivered from stack buffers, so a visitor that only needs
+//! nullifiers or note ciphertexts pays nothing for the proof data it skips.
+//!
+//! All consensus-relevant decoding checks of [`Transaction::read`] (canonical
+//! point encodings, valid value balances, version bounds) are applied; only
+//! the in-memory assembly is elided.
+//!
+//! [`Transaction::read`]: super::Transaction::read
+
+use std::io::{self, Read};
+
+use zcash_encoding::CompactSize;
+
+use super::{
+    components::sapling::{
+        self, Anchor, ConvertAnchor, ConvertDescriptionV5, OutputDescriptionV5, SpendDescription,
+        SpendDescriptionV5,
+    },
+    components::{
+        amount::I128Sum,
+        transparent::{self, TxIn, TxOut},
+    },
+    GrothProofBytes, Transaction, TxVersion,
+};
+use crate::consensus::{BlockHeight, BranchId};
+use crate::sapling::redjubjub;
+
+/// Callbacks invoked by [`TransactionReader`] as a transaction is decoded.
+///
+/// Events are emitted in wire order: the header, the transparent inputs and
+/// outputs, the spend, convert and output description bodies, the value
+/// balance and anchors, then the proofs and signatures. Every method has a
+/// no-op default, so a visitor implements only the events it cares about.
+pub trait TransactionVisitor {
+    /// Called once with the parsed header fields.
+    fn header(
+        &mut self,
+        _version: TxVersion,
+        _consensus_branch_id: BranchId,
+        _lock_time: u32,
+        _expiry_height: BlockHeight,
+    ) {
+    }
+
+    /// Called for each transparent input.
+    fn transparent_input(&mut self, _index: usize, _txin: &TxIn<transparent::Authorized>) {}
+
+    /// Called for each transparent output.
+    fn transparent_output(&mut self, _index: usize, _txout: &TxOut) {}
+
+    /// Called for each spend description body.
+    fn spend_description(&mut self, _index: usize, _spend: &SpendDescriptionV5) {}
+
+    /// Called for each convert description body.
+    fn convert_description(&mut self, _index: usize, _convert: &ConvertDescriptionV5) {}
+
+    /// Called for each output description body.
+    fn output_description(&mut self, _index: usize, _output: &OutputDescriptionV5) {}
+
+    /// Called with the value balance, if the transaction has a Sapling bundle.
+    fn value_balance(&mut self, _value_balance: &I128Sum) {}
+
+    /// Called with the spend anchor, if the transaction has spends.
+    fn spend_anchor(&mut self, _anchor: &Anchor) {}
+
+    /// Called with the convert anchor, if the transaction has converts.
+    fn convert_anchor(&mut self, _anchor: &ConvertAnchor) {}
+
+    /// Called for each spend proof.
+    fn spend_proof(&mut self, _index: usize, _zkproof: &GrothProofBytes) {}
+
+    /// Called for each spend authorization signature.
+    fn spend_auth_sig(&mut self, _index: usize, _sig: &redjubjub::Signature) {}
+
+    /// Called for each convert proof.
+    fn convert_proof(&mut self, _index: usize, _zkproof: &GrothProofBytes) {}
+
+    /// Called for each output proof.
+    fn output_proof(&mut self, _index: usize, _zkproof: &GrothProofBytes) {}
+
+    /// Called with the binding signature, if the transaction has a Sapling
+    /// bundle.
+    fn binding_sig(&mut self, _sig: &redjubjub::Signature) {}
+
+    /// Called with successive chunks of the opaque extension area of a
+    /// [`TxVersion::MASPFuture`] transaction.
+    fn extension_bytes(&mut self, _chunk: &[u8]) {}
+}
+
+/// A single-pass, bounded-memory reader for the v5 transaction format.
+pub struct TransactionReader;
+
+impl TransactionReader {
+    /// Parses a transaction from `reader`, forwarding each decoded item to
+    /// `visitor`.
+    ///
+    /// Consumes exactly the bytes of one transaction and fails with the same
+    /// errors as [`Transaction::read`] on malformed input; events emitted
+    /// before the error was encountered have already been delivered.
+    pub fn read<R: Read, V: TransactionVisitor>(mut reader: R, visitor: &mut V) -> io::Result<()> {
+        let version = TxVersion::read(&mut reader)?;
+        let (consensus_branch_id, lock_time, expiry_height) =
+            Transaction::read_v5_header_fragment(&mut reader)?;
+        visitor.header(version, consensus_branch_id, lock_time, expiry_height);
+
+        let n_vin: usize = CompactSize::read_t(&mut reader)?;
+        for i in 0..n_vin {
+            let txin = TxIn::read(&mut reader)?;
+            visitor.transparent_input(i, &txin);
+        }
+        let n_vout: usize = CompactSize::read_t(&mut reader)?;
+        for i in 0..n_vout {
+            let txout = TxOut::read(&mut reader)?;
+            visitor.transparent_output(i, &txout);
+        }
+
+        let n_spends: usize = CompactSize::read_t(&mut reader)?;
+        for i in 0..n_spends {
+            let sd = SpendDescriptionV5::read(&mut reader)?;
+            visitor.spend_description(i, &sd);
+        }
+        let n_converts: usize = CompactSize::read_t(&mut reader)?;
+        for i in 0..n_converts {
+            let cd = ConvertDescriptionV5::read(&mut reader)?;
+            visitor.convert_description(i, &cd);
+        }
+        let n_outputs: usize = CompactSize::read_t(&mut reader)?;
+        for i in 0..n_outputs {
+            let od = OutputDescriptionV5::read(&mut reader)?;
+            visitor.output_description(i, &od);
+        }
+
+        let has_sapling = n_spends > 0 || n_converts > 0 || n_outputs > 0;
+        if has_sapling {
+            let value_balance = Transaction::read_i128_sum(&mut reader)?;
+            visitor.value_balance(&value_balance);
+        }
+        if n_spends > 0 {
+            let anchor = Anchor(sapling::read_base(&mut reader, "spend anchor")?);
+            visitor.spend_anchor(&anchor);
+        }
+        if n_converts > 0 {
+            let anchor = ConvertAnchor(sapling::read_base(&mut reader, "convert anchor")?);
+            visitor.convert_anchor(&anchor);
+        }
+
+        for i in 0..n_spends {
+            let zkproof = sapling::read_zkproof(&mut reader)?;
+            visitor.spend_proof(i, &zkproof);
+        }
+        for i in 0..n_spends {
+            let sig = SpendDescription::read_spend_auth_sig(&mut reader)?;
+            visitor.spend_auth_sig(i, &sig);
+        }
+        for i in 0..n_converts {
+            let zkproof = sapling::read_zkproof(&mut reader)?;
+            visitor.convert_proof(i, &zkproof);
+        }
+        for i in 0..n_outputs {
+            let zkproof = sapling::read_zkproof(&mut reader)?;
+            visitor.output_proof(i, &zkproof);
+        }
+        if has_sapling {
+            let sig = redjubjub::Signature::read(&mut reader)?;
+            visitor.binding_sig(&sig);
+        }
+
+        if let TxVersion::MASPFuture(_) = version {
+            let mut remaining: usize = CompactSize::read_t(&mut reader)?;
+            let mut buf = [0u8; 4096];
+            while remaining > 0 {
+                let n = remaining.min(buf.len());
+                reader.read_exact(&mut buf[..n])?;
+                visitor.extension_bytes(&buf[..n]);
+                remaining -= n;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::{TransactionReader, TransactionVisitor};
+    use crate::consensus::BranchId;
+    use crate::transaction::{
+        components::{amount::I128Sum, sapling::OutputDescriptionV5, transparent::TxOut},
+        testing::arb_tx,
+        GrothProofBytes, Transaction,
+    };
+
+    /// Collects the event stream into comparable pieces.
+    #[derive(Default)]
+    struct Recorder {
+        vout_values: Vec<u64>,
+        nullifiers: Vec<[u8; 32]>,
+        cmus: Vec<[u8; 32]>,
+        value_balance: Option<I128Sum>,
+        n_spend_proofs: usize,
+        n_output_proofs: usize,
+        has_binding_sig: bool,
+    }
+
+    impl TransactionVisitor for Recorder {
+        fn transparent_output(&mut self, _index: usize, txout: &TxOut) {
+            self.vout_values.push(txout.value);
+        }
+
+        fn spend_description(
+            &mut self,
+            _index: usize,
+            spend: &crate::transaction::components::sapling::SpendDescriptionV5,
+        ) {
+            self.nullifiers.push(spend.nullifier.0);
+        }
+
+        fn output_description(&mut self, _index: usize, output: &OutputDescriptionV5) {
+            self.cmus.push(output.cmu.to_bytes());
+        }
+
+        fn value_balance(&mut self, value_balance: &I128Sum) {
+            self.value_balance = Some(value_balance.clone());
+        }
+
+        fn spend_proof(&mut self, _index: usize, _zkproof: &GrothProofBytes) {
+            self.n_spend_proofs += 1;
+        }
+
+        fn output_proof(&mut self, _index: usize, _zkproof: &GrothProofBytes) {
+            self.n_output_proofs += 1;
+        }
+
+        fn binding_sig(&mut self, _sig: &crate::sapling::redjubjub::Signature) {
+            self.has_binding_sig = true;
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(10))]
+        #[test]
+        fn streaming_matches_buffered_parse(tx in arb_tx(BranchId::MASP)) {
+            let mut bytes = vec![];
+            tx.write(&mut bytes).unwrap();
+
+            let mut recorder = Recorder::default();
+            TransactionReader::read(&bytes[..], &mut recorder).unwrap();
+
+            let parsed = Transaction::read(&bytes[..], BranchId::MASP).unwrap();
+            let vout_values: Vec<u64> = parsed
+                .transparent_bundle()
+                .iter()
+                .flat_map(|b| b.vout.iter().map(|o| o.value))
+                .collect();
+            prop_assert_eq!(recorder.vout_values, vout_values);
+
+            if let Some(bundle) = parsed.sapling_bundle() {
+                let nullifiers: Vec<[u8; 32]> =
+                    bundle.shielded_spends.iter().map(|s| s.nullifier.0).collect();
+                let cmus: Vec<[u8; 32]> =
+                    bundle.shielded_outputs.iter().map(|o| o.cmu.to_bytes()).collect();
+                prop_assert_eq!(recorder.nullifiers, nullifiers);
+                prop_assert_eq!(recorder.cmus, cmus);
+                prop_assert_eq!(recorder.value_balance.as_ref(), Some(&bundle.value_balance));
+                prop_assert_eq!(recorder.n_spend_proofs, bundle.shielded_spends.len());
+                prop_assert_eq!(recorder.n_output_proofs, bundle.shielded_outputs.len());
+                prop_assert!(recorder.has_binding_sig);
+            } else {
+                prop_assert!(recorder.nullifiers.is_empty());
+                prop_assert!(recorder.value_balance.is_none());
+                prop_assert!(!recorder.has_binding_sig);
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_input_is_rejected() {
+        let tx = crate::transaction::TransactionData::<crate::transaction::Authorized>::from_parts(
+            crate::transaction::TxVersion::MASPv5,
+            BranchId::MASP,
+            0,
+            0u32.into(),
+            None,
+            None,
+        )
+        .freeze()
+        .unwrap();
+        let mut bytes = vec![];
+        tx.write(&mut bytes).unwrap();
+        bytes.pop();
+
+        let mut recorder = Recorder::default();
+        assert!(TransactionReader::read(&bytes[..], &mut recorder).is_err());
+    }
+}
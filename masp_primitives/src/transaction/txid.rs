@@ -5,7 +5,6 @@ use std::io::Write;
 use blake2b_simd::{Hash as Blake2bHash, Params, State};
 use borsh::{BorshDeserialize, BorshSerialize};
 use byteorder::{LittleEndian, WriteBytesExt};
-use ff::PrimeField;
 use group::GroupEncoding;
 
 use crate::consensus::{BlockHeight, BranchId};
@@ -99,7 +98,7 @@ pub(crate) fn hash_sapling_spends<A: sapling::Authorization + PartialEq>(
             ch.write_all(s_spend.nullifier.as_ref()).unwrap();
 
             nh.write_all(&s_spend.cv.to_bytes()).unwrap();
-            nh.write_all(&s_spend.anchor.to_repr()).unwrap();
+            nh.write_all(&s_spend.anchor.to_bytes()).unwrap();
             s_spend.rk.write(&mut nh).unwrap();
         }
 
@@ -123,7 +122,7 @@ pub(crate) fn hash_sapling_converts<Proof: Clone + PartialEq>(
     if !shielded_converts.is_empty() {
         for s_convert in shielded_converts {
             h.write_all(&s_convert.cv.to_bytes()).unwrap();
-            h.write_all(&s_convert.anchor.to_repr()).unwrap();
+            h.write_all(&s_convert.anchor.to_bytes()).unwrap();
         }
     }
     h.finalize()
@@ -146,7 +145,7 @@ pub(crate) fn hash_sapling_outputs<Proof: Clone>(
         let mut mh = hasher(ZCASH_SAPLING_OUTPUTS_MEMOS_HASH_PERSONALIZATION);
         let mut nh = hasher(ZCASH_SAPLING_OUTPUTS_NONCOMPACT_HASH_PERSONALIZATION);
         for s_out in shielded_outputs {
-            ch.write_all(s_out.cmu.to_repr().as_ref()).unwrap();
+            ch.write_all(&s_out.cmu.to_bytes()).unwrap();
             ch.write_all(s_out.ephemeral_key.as_ref()).unwrap();
             ch.write_all(&s_out.enc_ciphertext[..masp_note_encryption::COMPACT_NOTE_SIZE])
                 .unwrap();
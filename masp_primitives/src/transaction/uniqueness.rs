@@ -0,0 +1,136 @@
+//! Block-level uniqueness checks for note commitments and nullifiers.
+//!
+//! Consensus rules require that no nullifier is revealed twice — within a
+//! block or against the historical nullifier set — and that no two outputs in
+//! a block share a note commitment. These helpers let node implementations
+//! run those checks over whole blocks without re-implementing the iteration
+//! over Sapling bundles.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::sapling::{ExtractedNoteCommitment, Nullifier};
+use crate::transaction::{Transaction, TxId};
+
+/// Errors produced by the uniqueness checks, identifying the offending
+/// transaction and description index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UniquenessError {
+    /// The output at the given index repeats a note commitment already seen
+    /// in this block.
+    DuplicateCommitment {
+        txid: TxId,
+        index: usize,
+        cmu: ExtractedNoteCommitment,
+    },
+    /// The spend at the given index reveals a nullifier already seen in this
+    /// block or in the provided historical nullifier set.
+    DuplicateNullifier {
+        txid: TxId,
+        index: usize,
+        nullifier: Nullifier,
+    },
+}
+
+impl fmt::Display for UniquenessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UniquenessError::DuplicateCommitment { txid, index, cmu } => write!(
+                f,
+                "Output {} of transaction {} repeats note commitment {:?}",
+                index, txid, cmu
+            ),
+            UniquenessError::DuplicateNullifier {
+                txid,
+                index,
+                nullifier,
+            } => write!(
+                f,
+                "Spend {} of transaction {} repeats nullifier {:?}",
+                index, txid, nullifier
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UniquenessError {}
+
+/// An accumulator tracking the note commitments and nullifiers seen so far in
+/// a block.
+#[derive(Clone, Debug, Default)]
+pub struct UniquenessChecker {
+    commitments: HashSet<[u8; 32]>,
+    nullifiers: HashSet<Nullifier>,
+}
+
+impl UniquenessChecker {
+    /// Constructs a checker with no commitments or nullifiers seen yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the Sapling bundle of `tx` against everything seen so far and
+    /// against `historical_nullifiers`, recording its commitments and
+    /// nullifiers on success.
+    ///
+    /// On failure the checker is left unchanged, so a block validator can
+    /// drop the offending transaction and continue with the rest.
+    pub fn check_transaction(
+        &mut self,
+        tx: &Transaction,
+        historical_nullifiers: &HashSet<Nullifier>,
+    ) -> Result<(), UniquenessError> {
+        let bundle = match tx.sapling_bundle() {
+            Some(bundle) => bundle,
+            None => return Ok(()),
+        };
+
+        // Validate the whole transaction before mutating the accumulator, so
+        // that a failed check leaves `self` untouched. The local sets also
+        // catch duplicates within the transaction itself.
+        let mut new_nullifiers = HashSet::new();
+        for (index, spend) in bundle.shielded_spends.iter().enumerate() {
+            if self.nullifiers.contains(&spend.nullifier)
+                || historical_nullifiers.contains(&spend.nullifier)
+                || !new_nullifiers.insert(spend.nullifier)
+            {
+                return Err(UniquenessError::DuplicateNullifier {
+                    txid: tx.txid(),
+                    index,
+                    nullifier: spend.nullifier,
+                });
+            }
+        }
+        let mut new_commitments = HashSet::new();
+        for (index, output) in bundle.shielded_outputs.iter().enumerate() {
+            if self.commitments.contains(&output.cmu.to_bytes())
+                || !new_commitments.insert(output.cmu.to_bytes())
+            {
+                return Err(UniquenessError::DuplicateCommitment {
+                    txid: tx.txid(),
+                    index,
+                    cmu: output.cmu,
+                });
+            }
+        }
+
+        self.nullifiers.extend(new_nullifiers);
+        self.commitments.extend(new_commitments);
+        Ok(())
+    }
+}
+
+/// Checks that no note commitment or nullifier is repeated across the given
+/// transactions, and that no nullifier appears in `historical_nullifiers`.
+///
+/// Returns the first violation encountered, in transaction order.
+pub fn check_block<'a>(
+    txs: impl IntoIterator<Item = &'a Transaction>,
+    historical_nullifiers: &HashSet<Nullifier>,
+) -> Result<(), UniquenessError> {
+    let mut checker = UniquenessChecker::new();
+    for tx in txs {
+        checker.check_transaction(tx, historical_nullifiers)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,1065 @@
+//! Wallet-level helpers for planning MASP transactions.
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+
+use masp_note_encryption::{ShieldedOutput, ENC_CIPHERTEXT_SIZE};
+
+use crate::{
+    asset_type::AssetType,
+    consensus::{self, BlockHeight},
+    convert::AllowedConversion,
+    memo::{Memo, MemoBytes},
+    merkle_tree::MerklePath,
+    sapling::{
+        note_encryption::{
+            try_sapling_note_decryption, try_sapling_output_recovery, PreparedIncomingViewingKey,
+            SaplingDomain,
+        },
+        Diversifier, Node, Note, PaymentAddress,
+    },
+    transaction::{
+        builder::Builder,
+        components::{sapling::builder as sapling_builder, I128Sum},
+        Transaction,
+    },
+    zip32::{DiversifiableFullViewingKey, ExtendedSpendingKey, Scope},
+};
+
+pub mod export;
+#[cfg(feature = "sqlite")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
+pub mod sqlite;
+pub mod store;
+
+/// A single planned convert, rolling the full held value of one stale asset
+/// forward via an [`AllowedConversion`].
+///
+/// Feed each planned convert to the transaction builder with
+/// [`add_sapling_convert`], together with a Merkle path for the conversion in
+/// the current allowed-conversions tree, alongside spends of the listed notes.
+///
+/// [`add_sapling_convert`]: crate::transaction::builder::Builder::add_sapling_convert
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedConvert {
+    /// The allowed conversion to apply.
+    pub conversion: AllowedConversion,
+    /// The multiplier with which to apply the conversion: the total value held
+    /// in notes of the stale asset.
+    pub value: u64,
+    /// Indices into the input note slice of the notes holding the stale asset.
+    pub notes: Vec<usize>,
+}
+
+/// Inspects the given held notes and plans the minimal set of converts needed
+/// to roll value of stale-epoch assets forward, collecting any rewards the
+/// conversions carry.
+///
+/// `conversions` maps each stale asset type to the allowed conversion that
+/// consumes it; an asset is considered stale exactly when such an entry
+/// exists. Notes of assets without an entry are left untouched. All notes of
+/// the same stale asset are collected into a single convert, so the returned
+/// plan contains at most one entry per stale asset and can typically be
+/// carried by a single convert-bearing transaction.
+pub fn plan_epoch_rollover(
+    notes: &[Note],
+    conversions: &BTreeMap<AssetType, AllowedConversion>,
+) -> Vec<PlannedConvert> {
+    let mut planned: BTreeMap<AssetType, PlannedConvert> = BTreeMap::new();
+    for (idx, note) in notes.iter().enumerate() {
+        if let Some(conversion) = conversions.get(&note.asset_type) {
+            let entry = planned
+                .entry(note.asset_type)
+                .or_insert_with(|| PlannedConvert {
+                    conversion: conversion.clone(),
+                    value: 0,
+                    notes: vec![],
+                });
+            entry.value += note.value;
+            entry.notes.push(idx);
+        }
+    }
+    planned.into_values().collect()
+}
+
+/// Magic bytes identifying a refundable-payment memo.
+const REFUNDABLE_MEMO_MAGIC: &[u8; 8] = b"MASPrfnd";
+
+/// The current version of the refundable-payment memo encoding.
+const REFUNDABLE_MEMO_VERSION: u8 = 1;
+
+/// The terms of a refundable (escrow-lite) payment, carried in the memo of
+/// the payment's output.
+///
+/// Under this convention the recipient's wallet is expected to either
+/// acknowledge the payment out of band before the expiry height, or to return
+/// the funds to the refund address once it has passed; see [`plan_refund`].
+/// Scanners detect refundable payments by calling [`RefundTerms::decode`] on
+/// the memo of each received note.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefundTerms {
+    /// The height at and after which the payment should be refunded.
+    pub expiry_height: BlockHeight,
+    /// The address the refund should be paid to.
+    pub refund_address: PaymentAddress,
+}
+
+impl RefundTerms {
+    /// Encodes these terms into a memo to attach to the payment's output.
+    pub fn encode(&self) -> MemoBytes {
+        let mut bytes = [0u8; 8 + 1 + 4 + 43];
+        bytes[0..8].copy_from_slice(REFUNDABLE_MEMO_MAGIC);
+        bytes[8] = REFUNDABLE_MEMO_VERSION;
+        bytes[9..13].copy_from_slice(&u32::from(self.expiry_height).to_le_bytes());
+        bytes[13..56].copy_from_slice(&self.refund_address.to_bytes());
+        MemoBytes::from_bytes(&bytes).expect("refundable memo fits in a memo field")
+    }
+
+    /// Decodes refund terms from the memo of a received note.
+    ///
+    /// Returns `None` if the memo does not carry a well-formed
+    /// refundable-payment encoding.
+    pub fn decode(memo: &MemoBytes) -> Option<Self> {
+        let bytes = memo.as_array();
+        if &bytes[0..8] != REFUNDABLE_MEMO_MAGIC || bytes[8] != REFUNDABLE_MEMO_VERSION {
+            return None;
+        }
+        let expiry_height = u32::from_le_bytes(bytes[9..13].try_into().unwrap()).into();
+        let refund_address = PaymentAddress::from_bytes(bytes[13..56].try_into().unwrap())?;
+        // The remainder of the memo must be empty padding.
+        if bytes[56..].iter().any(|b| *b != 0) {
+            return None;
+        }
+        Some(RefundTerms {
+            expiry_height,
+            refund_address,
+        })
+    }
+
+    /// Returns whether the payment is due for a refund at the given height.
+    pub fn is_expired(&self, height: BlockHeight) -> bool {
+        height >= self.expiry_height
+    }
+}
+
+/// Errors that can occur while planning a refund transaction.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RefundError {
+    /// The builder's target height is below the payment's expiry height.
+    NotExpired,
+    /// The note's value does not cover the fee.
+    InsufficientValue,
+    /// An error occurred adding the spend or output to the builder.
+    SaplingBuild(sapling_builder::Error),
+}
+
+impl fmt::Display for RefundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefundError::NotExpired => write!(f, "the payment's expiry height has not passed"),
+            RefundError::InsufficientValue => {
+                write!(f, "the note's value does not cover the fee")
+            }
+            RefundError::SaplingBuild(e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for RefundError {}
+
+/// Adds the spend and output for refunding an expired, unacknowledged payment
+/// to the given builder.
+///
+/// The received note is spent in full, paying `fee` (in the note's asset type)
+/// towards the transaction fee and returning the remainder to the refund
+/// address from the payment's [`RefundTerms`]. Fails if the builder's target
+/// height is below the expiry height, so wallets cannot accidentally refund a
+/// payment early.
+pub fn plan_refund<P: consensus::Parameters>(
+    builder: &mut Builder<P>,
+    extsk: ExtendedSpendingKey,
+    diversifier: Diversifier,
+    note: Note,
+    merkle_path: MerklePath<Node>,
+    terms: &RefundTerms,
+    fee: u64,
+) -> Result<(), RefundError> {
+    if !terms.is_expired(builder.target_height()) {
+        return Err(RefundError::NotExpired);
+    }
+    let refund_value = note
+        .value
+        .checked_sub(fee)
+        .ok_or(RefundError::InsufficientValue)?;
+
+    let asset_type = note.asset_type;
+    builder
+        .add_sapling_spend(extsk, diversifier, note, merkle_path)
+        .map_err(RefundError::SaplingBuild)?;
+    builder
+        .add_sapling_output(
+            None,
+            terms.refund_address,
+            asset_type,
+            refund_value,
+            MemoBytes::empty(),
+        )
+        .map_err(RefundError::SaplingBuild)?;
+
+    Ok(())
+}
+
+/// A remote source of fresh Merkle paths for note commitment positions.
+///
+/// Implementations typically proxy a full node or indexer that maintains the
+/// note commitment tree; the wallet only remembers each note's position and
+/// requests paths at build time via [`fetch_spend_paths`], which validates
+/// everything the service returns against a trusted anchor.
+pub trait MerklePathService {
+    /// The implementation's transport or lookup error.
+    type Error;
+
+    /// Returns one Merkle path per requested position, in request order.
+    fn merkle_paths(&self, positions: &[u64]) -> Result<Vec<MerklePath<Node>>, Self::Error>;
+}
+
+/// A note held by a wallet that tracks tree positions instead of witnesses.
+#[derive(Clone, Debug)]
+pub struct PositionedNote {
+    /// The diversifier of the address the note was sent to.
+    pub diversifier: Diversifier,
+    /// The note itself.
+    pub note: Note,
+    /// The note commitment's position in the commitment tree.
+    pub position: u64,
+}
+
+/// An error while fetching Merkle paths from a [`MerklePathService`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WitnessFetchError<E> {
+    /// The service itself failed.
+    Service(E),
+    /// The service returned a different number of paths than requested.
+    WrongPathCount { expected: usize, actual: usize },
+    /// The path for the given position carries a different position.
+    WrongPosition { requested: u64, returned: u64 },
+    /// The path for the given position does not lead to the trusted anchor.
+    AnchorMismatch { position: u64 },
+}
+
+impl<E: fmt::Display> fmt::Display for WitnessFetchError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WitnessFetchError::Service(e) => write!(f, "merkle path service error: {}", e),
+            WitnessFetchError::WrongPathCount { expected, actual } => write!(
+                f,
+                "merkle path service returned {} paths for {} positions",
+                actual, expected
+            ),
+            WitnessFetchError::WrongPosition {
+                requested,
+                returned,
+            } => write!(
+                f,
+                "merkle path for position {} carries position {}",
+                requested, returned
+            ),
+            WitnessFetchError::AnchorMismatch { position } => write!(
+                f,
+                "merkle path for position {} does not lead to the trusted anchor",
+                position
+            ),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> error::Error for WitnessFetchError<E> {}
+
+/// Fetches and validates Merkle paths for the given leaves from a
+/// [`MerklePathService`].
+///
+/// Each returned path is checked to carry the requested position and to hash
+/// the corresponding leaf to `anchor`, so a misbehaving service cannot trick
+/// the wallet into proving against a different tree state.
+pub fn fetch_merkle_paths<S: MerklePathService>(
+    service: &S,
+    leaves: &[(Node, u64)],
+    anchor: Node,
+) -> Result<Vec<MerklePath<Node>>, WitnessFetchError<S::Error>> {
+    let positions: Vec<u64> = leaves.iter().map(|(_, position)| *position).collect();
+    let paths = service
+        .merkle_paths(&positions)
+        .map_err(WitnessFetchError::Service)?;
+
+    if paths.len() != leaves.len() {
+        return Err(WitnessFetchError::WrongPathCount {
+            expected: leaves.len(),
+            actual: paths.len(),
+        });
+    }
+
+    for ((leaf, position), path) in leaves.iter().zip(&paths) {
+        if path.position != *position {
+            return Err(WitnessFetchError::WrongPosition {
+                requested: *position,
+                returned: path.position,
+            });
+        }
+        if path.root(*leaf) != anchor {
+            return Err(WitnessFetchError::AnchorMismatch {
+                position: *position,
+            });
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Fetches validated Merkle paths for the given notes, ready to pass to
+/// [`add_sapling_spend`] alongside each note.
+///
+/// [`add_sapling_spend`]: crate::transaction::builder::Builder::add_sapling_spend
+pub fn fetch_spend_paths<S: MerklePathService>(
+    service: &S,
+    notes: &[PositionedNote],
+    anchor: Node,
+) -> Result<Vec<MerklePath<Node>>, WitnessFetchError<S::Error>> {
+    let leaves: Vec<(Node, u64)> = notes
+        .iter()
+        .map(|positioned| (positioned.note.commitment(), positioned.position))
+        .collect();
+    fetch_merkle_paths(service, &leaves, anchor)
+}
+
+/// The default cap on indexed memo text, covering any ZIP 302 text memo.
+pub const DEFAULT_INDEXED_MEMO_LIMIT: usize = 511;
+
+/// A sink for decrypted memo text, fed by [`index_memos`] during scanning.
+///
+/// Wallets implement this over their search index of choice; the scanner
+/// calls it once per decrypted text memo, so memo search does not require
+/// re-decrypting history on every query. Non-text memos (empty, arbitrary
+/// bytes, or unparseable future formats) are never emitted.
+pub trait MemoIndexer {
+    /// Records the memo text of the output at `index` in the scanned slice.
+    ///
+    /// `scope` labels the viewing key the output decrypted under, letting the
+    /// index distinguish received notes from wallet-internal change. `text`
+    /// has already been truncated to the indexing limit.
+    fn index_memo(&mut self, scope: Scope, index: usize, text: &str);
+}
+
+/// Trial-decrypts the given outputs and feeds any text memos found to the
+/// indexer.
+///
+/// Each output is tried against the given `(scope, ivk)` pairs in order and
+/// indexed under the scope of the first key that decrypts it. Memo text
+/// longer than `limit` bytes is truncated at a character boundary before it
+/// is emitted; pass [`DEFAULT_INDEXED_MEMO_LIMIT`] to index memos in full.
+/// Returns the number of memos emitted.
+pub fn index_memos<P, Output, Ix>(
+    params: &P,
+    height: BlockHeight,
+    keys: &[(Scope, PreparedIncomingViewingKey)],
+    outputs: &[Output],
+    limit: usize,
+    indexer: &mut Ix,
+) -> usize
+where
+    P: consensus::Parameters,
+    Output: ShieldedOutput<SaplingDomain<P>, ENC_CIPHERTEXT_SIZE>,
+    Ix: MemoIndexer,
+{
+    let mut indexed = 0;
+    for (index, output) in outputs.iter().enumerate() {
+        for (scope, ivk) in keys {
+            if let Some((_, _, memo)) = try_sapling_note_decryption(params, height, ivk, output) {
+                if let Ok(Memo::Text(text)) = Memo::try_from(memo) {
+                    indexer.index_memo(*scope, index, truncate_to_limit(&text, limit));
+                    indexed += 1;
+                }
+                break;
+            }
+        }
+    }
+    indexed
+}
+
+/// How a decrypted output relates to the wallet that decrypted it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferType {
+    /// The output pays one of the wallet's external addresses.
+    Incoming,
+    /// The output is change the wallet sent back to itself.
+    WalletInternal,
+    /// The output was created by the wallet but pays somebody else; it was
+    /// recovered with the wallet's outgoing viewing key.
+    Outgoing,
+}
+
+/// One output of a transaction that the wallet's keys could decrypt.
+#[derive(Clone, Debug)]
+pub struct DecryptedNote {
+    /// The index of the output within the transaction's shielded outputs.
+    pub index: usize,
+    /// The output's relationship to the wallet.
+    pub transfer_type: TransferType,
+    /// The decrypted note.
+    pub note: Note,
+    /// The address the note pays.
+    pub address: PaymentAddress,
+    /// The memo attached to the output.
+    pub memo: MemoBytes,
+}
+
+/// The wallet-visible contents of one transaction, for history views and
+/// accounting exports.
+#[derive(Clone, Debug)]
+pub struct DecryptedTransaction {
+    /// Every output the wallet's keys could decrypt, in output order.
+    pub notes: Vec<DecryptedNote>,
+    /// The net per-asset flow between the wallet and the rest of the world:
+    /// incoming values minus outgoing values. Wallet-internal change is
+    /// neutral and fees are not attributed, so a payment of 60 shows as −60
+    /// regardless of how the wallet's notes funded it.
+    pub net_flows: I128Sum,
+}
+
+/// Decrypts every shielded output of `tx` with the wallet's viewing key and
+/// computes the wallet's net per-asset delta for the transaction.
+///
+/// Outputs are tried against the external and internal incoming viewing keys
+/// first, classifying received funds and change; anything else is tried
+/// against both outgoing viewing keys, recovering payments the wallet made.
+/// `height` determines the note plaintext rules in force (ZIP 212) and
+/// should be the height the transaction was (or will be) mined at.
+pub fn decrypt_transaction<P: consensus::Parameters>(
+    params: &P,
+    height: BlockHeight,
+    tx: &Transaction,
+    dfvk: &DiversifiableFullViewingKey,
+) -> DecryptedTransaction {
+    let ivks = [
+        (
+            TransferType::Incoming,
+            PreparedIncomingViewingKey::new(&dfvk.to_ivk(Scope::External)),
+        ),
+        (
+            TransferType::WalletInternal,
+            PreparedIncomingViewingKey::new(&dfvk.to_ivk(Scope::Internal)),
+        ),
+    ];
+    let ovks = [dfvk.to_ovk(Scope::External), dfvk.to_ovk(Scope::Internal)];
+
+    let mut notes = vec![];
+    let mut net_flows = I128Sum::zero();
+    let outputs = tx
+        .sapling_bundle()
+        .map(|bundle| &bundle.shielded_outputs[..])
+        .unwrap_or(&[]);
+    for (index, output) in outputs.iter().enumerate() {
+        let decrypted = ivks
+            .iter()
+            .find_map(|(transfer_type, ivk)| {
+                try_sapling_note_decryption(params, height, ivk, output)
+                    .map(|decrypted| (*transfer_type, decrypted))
+            })
+            .or_else(|| {
+                ovks.iter().find_map(|ovk| {
+                    try_sapling_output_recovery(params, height, ovk, output)
+                        .map(|recovered| (TransferType::Outgoing, recovered))
+                })
+            });
+        if let Some((transfer_type, (note, address, memo))) = decrypted {
+            match transfer_type {
+                TransferType::Incoming => {
+                    net_flows += &I128Sum::from_pair(note.asset_type, note.value.into());
+                }
+                TransferType::Outgoing => {
+                    net_flows -= &I128Sum::from_pair(note.asset_type, note.value.into());
+                }
+                TransferType::WalletInternal => {}
+            }
+            notes.push(DecryptedNote {
+                index,
+                transfer_type,
+                note,
+                address,
+                memo,
+            });
+        }
+    }
+
+    DecryptedTransaction { notes, net_flows }
+}
+
+/// Truncates `text` to at most `limit` bytes without splitting a character.
+fn truncate_to_limit(text: &str, limit: usize) -> &str {
+    if text.len() <= limit {
+        return text;
+    }
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decrypt_transaction, fetch_spend_paths, index_memos, plan_epoch_rollover, plan_refund,
+        MemoIndexer, MerklePathService, PositionedNote, RefundError, RefundTerms, TransferType,
+        WitnessFetchError, DEFAULT_INDEXED_MEMO_LIMIT,
+    };
+    use crate::asset_type::AssetType;
+    use crate::consensus::{NetworkUpgrade, Parameters, TestNetwork, TEST_NETWORK};
+    use crate::convert::AllowedConversion;
+    use crate::keys::OutgoingViewingKey;
+    use crate::memo::MemoBytes;
+    use crate::merkle_tree::{CommitmentTree, IncrementalWitness, MerklePath};
+    use crate::sapling::note_encryption::{sapling_note_encryption, PreparedIncomingViewingKey};
+    use crate::sapling::util::generate_random_rseed;
+    use crate::sapling::{Node, PaymentAddress, Rseed, SaplingIvk};
+    use crate::transaction::builder::Builder;
+    use crate::transaction::components::sapling::builder::RngBuildParams;
+    use crate::transaction::components::sapling::{GrothProofBytes, OutputDescription};
+    use crate::transaction::components::{I128Sum, GROTH_PROOF_SIZE};
+    use crate::zip32::{ExtendedSpendingKey, Scope};
+    use ff::Field;
+    use group::GroupEncoding;
+    use rand_core::OsRng;
+    use std::collections::BTreeMap;
+
+    /// Generate an asset type for the given epoch of a NAM-like token
+    fn nam(epoch: u64) -> AssetType {
+        AssetType::new(format!("NAM/{}", epoch).as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn plans_single_convert_per_stale_asset() {
+        let mut rng = OsRng;
+        let extsk = ExtendedSpendingKey::master(&[]);
+        let to = extsk.default_address().1;
+
+        let notes = [
+            to.create_note(
+                nam(0),
+                10,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap(),
+            to.create_note(
+                nam(1),
+                20,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap(),
+            to.create_note(
+                nam(0),
+                30,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap(),
+        ];
+
+        // Only epoch 0 is stale; its conversion rolls value to epoch 1.
+        let conv: AllowedConversion =
+            (I128Sum::from_pair(nam(0), -1) + I128Sum::from_pair(nam(1), 1)).into();
+        let mut conversions = BTreeMap::new();
+        conversions.insert(nam(0), conv.clone());
+
+        let plan = plan_epoch_rollover(&notes, &conversions);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].conversion, conv);
+        assert_eq!(plan[0].value, 40);
+        assert_eq!(plan[0].notes, vec![0, 2]);
+    }
+
+    #[test]
+    fn refund_terms_memo_roundtrip() {
+        let refund_address = ExtendedSpendingKey::master(&[1u8; 32]).default_address().1;
+        let terms = RefundTerms {
+            expiry_height: 1_234.into(),
+            refund_address,
+        };
+
+        let memo = terms.encode();
+        assert_eq!(RefundTerms::decode(&memo), Some(terms.clone()));
+
+        // Ordinary memos are not mistaken for refund terms.
+        assert_eq!(RefundTerms::decode(&MemoBytes::empty()), None);
+        assert_eq!(
+            RefundTerms::decode(&MemoBytes::from_bytes(b"a text memo").unwrap()),
+            None
+        );
+
+        assert!(!terms.is_expired(1_233.into()));
+        assert!(terms.is_expired(1_234.into()));
+    }
+
+    #[test]
+    fn refund_spends_note_back_to_refund_address() {
+        let mut rng = OsRng;
+
+        // The payer's refund address, and the recipient's keys.
+        let refund_address = ExtendedSpendingKey::master(&[1u8; 32]).default_address().1;
+        let extsk = ExtendedSpendingKey::master(&[2u8; 32]);
+        let to = extsk.default_address().1;
+
+        let expiry_height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let terms = RefundTerms {
+            expiry_height,
+            refund_address,
+        };
+
+        // The payment's note, as the recipient's scanner would recover it.
+        let zec = AssetType::new(b"ZEC").unwrap();
+        let note = to
+            .create_note(
+                zec,
+                50000,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap();
+        let mut tree = CommitmentTree::empty();
+        tree.append(note.commitment()).unwrap();
+        let witness = IncrementalWitness::from_tree(&tree);
+
+        // Refunds cannot be planned before the expiry height.
+        let mut builder = Builder::new(TEST_NETWORK, expiry_height - 1);
+        assert_eq!(
+            plan_refund(
+                &mut builder,
+                extsk,
+                *to.diversifier(),
+                note,
+                witness.path().unwrap(),
+                &terms,
+                1000,
+            ),
+            Err(RefundError::NotExpired)
+        );
+
+        // At the expiry height the refund builds into a valid transaction.
+        let mut builder = Builder::new(TEST_NETWORK, expiry_height);
+        plan_refund(
+            &mut builder,
+            extsk,
+            *to.diversifier(),
+            note,
+            witness.path().unwrap(),
+            &terms,
+            1000,
+        )
+        .unwrap();
+        let (tx, _) = builder
+            .mock_build(&mut OsRng, &mut RngBuildParams::new(OsRng))
+            .unwrap();
+        assert!(tx.sapling_bundle().is_some());
+    }
+
+    /// A path service backed by a local set of witnesses, one per leaf.
+    struct TreeService {
+        witnesses: Vec<IncrementalWitness<Node>>,
+    }
+
+    impl MerklePathService for TreeService {
+        type Error = String;
+
+        fn merkle_paths(&self, positions: &[u64]) -> Result<Vec<MerklePath<Node>>, String> {
+            positions
+                .iter()
+                .map(|position| {
+                    self.witnesses
+                        .get(*position as usize)
+                        .and_then(|w| w.path())
+                        .ok_or_else(|| format!("no witness for position {}", position))
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn fetches_and_validates_remote_merkle_paths() {
+        let mut rng = OsRng;
+        let extsk = ExtendedSpendingKey::master(&[3u8; 32]);
+        let to = extsk.default_address().1;
+        let zec = AssetType::new(b"ZEC").unwrap();
+
+        // Append three notes, maintaining a witness for each as the remote
+        // service would.
+        let mut tree = CommitmentTree::empty();
+        let mut witnesses: Vec<IncrementalWitness<Node>> = vec![];
+        let mut notes = vec![];
+        for position in 0..3u64 {
+            let note = to
+                .create_note(zec, 100, Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)))
+                .unwrap();
+            tree.append(note.commitment()).unwrap();
+            for witness in witnesses.iter_mut() {
+                witness.append(note.commitment()).unwrap();
+            }
+            witnesses.push(IncrementalWitness::from_tree(&tree));
+            notes.push(PositionedNote {
+                diversifier: *to.diversifier(),
+                note,
+                position,
+            });
+        }
+        let anchor = tree.root();
+        let service = TreeService { witnesses };
+
+        // The wallet, holding only positions, obtains usable paths.
+        let paths = fetch_spend_paths(&service, &notes, anchor).unwrap();
+        assert_eq!(paths.len(), 3);
+        for (positioned, path) in notes.iter().zip(&paths) {
+            assert_eq!(path.position, positioned.position);
+            assert_eq!(path.root(positioned.note.commitment()), anchor);
+        }
+
+        // A service failure surfaces as such.
+        let mut bad_positions = notes.clone();
+        bad_positions[0].position = 17;
+        assert_eq!(
+            fetch_spend_paths(&service, &bad_positions, anchor),
+            Err(WitnessFetchError::Service(
+                "no witness for position 17".into()
+            ))
+        );
+
+        // Paths against a different tree state are rejected.
+        assert_eq!(
+            fetch_spend_paths(
+                &service,
+                &notes,
+                Node::from_scalar(bls12_381::Scalar::one())
+            ),
+            Err(WitnessFetchError::AnchorMismatch { position: 0 })
+        );
+
+        // A service that relabels the positions of otherwise valid paths is
+        // caught before the anchor check.
+        struct LyingService(TreeService);
+        impl MerklePathService for LyingService {
+            type Error = String;
+
+            fn merkle_paths(&self, positions: &[u64]) -> Result<Vec<MerklePath<Node>>, String> {
+                let mut paths = self.0.merkle_paths(positions)?;
+                for path in paths.iter_mut() {
+                    path.position += 1;
+                }
+                Ok(paths)
+            }
+        }
+        assert_eq!(
+            fetch_spend_paths(&LyingService(service), &notes, anchor),
+            Err(WitnessFetchError::WrongPosition {
+                requested: 0,
+                returned: 1
+            })
+        );
+    }
+
+    #[test]
+    fn fetched_paths_satisfy_the_builder() {
+        let mut rng = OsRng;
+        let extsk = ExtendedSpendingKey::master(&[4u8; 32]);
+        let to = extsk.default_address().1;
+        let zec = AssetType::new(b"ZEC").unwrap();
+
+        let note = to
+            .create_note(
+                zec,
+                50000,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap();
+        let mut tree = CommitmentTree::empty();
+        tree.append(note.commitment()).unwrap();
+        let service = TreeService {
+            witnesses: vec![IncrementalWitness::from_tree(&tree)],
+        };
+
+        let positioned = PositionedNote {
+            diversifier: *to.diversifier(),
+            note,
+            position: 0,
+        };
+        let paths =
+            fetch_spend_paths(&service, std::slice::from_ref(&positioned), tree.root()).unwrap();
+
+        let height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let mut builder = Builder::new(TEST_NETWORK, height);
+        builder
+            .add_sapling_spend(
+                extsk,
+                positioned.diversifier,
+                positioned.note,
+                paths.into_iter().next().unwrap(),
+            )
+            .unwrap();
+        builder
+            .add_sapling_output(None, to, zec, 49000, MemoBytes::empty())
+            .unwrap();
+        let (tx, _) = builder
+            .mock_build(&mut OsRng, &mut RngBuildParams::new(OsRng))
+            .unwrap();
+        assert!(tx.sapling_bundle().is_some());
+    }
+
+    /// Encrypts a note with the given memo to the given address, as an output
+    /// on chain would carry it.
+    fn encrypted_output(
+        to: PaymentAddress,
+        memo: MemoBytes,
+        rng: &mut OsRng,
+    ) -> OutputDescription<GrothProofBytes> {
+        let height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let zec = AssetType::new(b"ZEC").unwrap();
+        let value_commitment = zec.value_commitment(100, jubjub::Fr::random(&mut *rng));
+        let cv = value_commitment.commitment().into();
+
+        let rseed = generate_random_rseed(&TEST_NETWORK, height, rng);
+        let note = to.create_note(zec, 100, rseed).unwrap();
+        let cmu = note.cmstar();
+
+        let ovk = OutgoingViewingKey([0; 32]);
+        let ne = sapling_note_encryption::<TestNetwork>(Some(ovk), note, to, memo);
+        let epk = *ne.epk();
+
+        OutputDescription {
+            cv,
+            cmu,
+            ephemeral_key: epk.to_bytes().into(),
+            enc_ciphertext: ne.encrypt_note_plaintext(),
+            out_ciphertext: ne.encrypt_outgoing_plaintext(&cv, &cmu, rng),
+            zkproof: [0u8; GROTH_PROOF_SIZE],
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingIndexer(Vec<(Scope, usize, String)>);
+
+    impl MemoIndexer for RecordingIndexer {
+        fn index_memo(&mut self, scope: Scope, index: usize, text: &str) {
+            self.0.push((scope, index, text.to_owned()));
+        }
+    }
+
+    #[test]
+    fn indexes_text_memos_under_their_key_scope() {
+        let mut rng = OsRng;
+        let height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let dfvk = ExtendedSpendingKey::master(&[5u8; 32]).to_diversifiable_full_viewing_key();
+        let keys = [
+            (
+                Scope::External,
+                PreparedIncomingViewingKey::new(&dfvk.to_ivk(Scope::External)),
+            ),
+            (
+                Scope::Internal,
+                PreparedIncomingViewingKey::new(&dfvk.to_ivk(Scope::Internal)),
+            ),
+        ];
+
+        let external = dfvk.default_address().1;
+        let (_, internal) = dfvk.change_address();
+        let stranger = {
+            let ivk = SaplingIvk(jubjub::Fr::random(&mut rng));
+            ivk.to_payment_address(*external.diversifier()).unwrap()
+        };
+
+        let outputs = [
+            encrypted_output(
+                external,
+                MemoBytes::from_bytes(b"coffee with alice").unwrap(),
+                &mut rng,
+            ),
+            // Change note carrying a wallet-internal annotation.
+            encrypted_output(
+                internal,
+                MemoBytes::from_bytes(b"change: invoice 7").unwrap(),
+                &mut rng,
+            ),
+            // Empty memos and other wallets' outputs are not indexed.
+            encrypted_output(external, MemoBytes::empty(), &mut rng),
+            encrypted_output(
+                stranger,
+                MemoBytes::from_bytes(b"not ours").unwrap(),
+                &mut rng,
+            ),
+        ];
+
+        let mut indexer = RecordingIndexer::default();
+        let indexed = index_memos(
+            &TEST_NETWORK,
+            height,
+            &keys,
+            &outputs,
+            DEFAULT_INDEXED_MEMO_LIMIT,
+            &mut indexer,
+        );
+
+        assert_eq!(indexed, 2);
+        assert_eq!(
+            indexer.0,
+            vec![
+                (Scope::External, 0, "coffee with alice".to_owned()),
+                (Scope::Internal, 1, "change: invoice 7".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn oversized_memo_text_is_truncated_at_a_char_boundary() {
+        let mut rng = OsRng;
+        let height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let dfvk = ExtendedSpendingKey::master(&[6u8; 32]).to_diversifiable_full_viewing_key();
+        let keys = [(
+            Scope::External,
+            PreparedIncomingViewingKey::new(&dfvk.to_ivk(Scope::External)),
+        )];
+
+        // "héllo" is six bytes; a two-byte limit falls inside the 'é', so the
+        // cut backs up to the previous character boundary.
+        let outputs = [encrypted_output(
+            dfvk.default_address().1,
+            MemoBytes::from_bytes("héllo".as_bytes()).unwrap(),
+            &mut rng,
+        )];
+
+        let mut indexer = RecordingIndexer::default();
+        assert_eq!(
+            index_memos(&TEST_NETWORK, height, &keys, &outputs, 2, &mut indexer),
+            1
+        );
+        assert_eq!(indexer.0, vec![(Scope::External, 0, "h".to_owned())]);
+    }
+
+    #[test]
+    fn fresh_assets_are_left_untouched() {
+        let mut rng = OsRng;
+        let extsk = ExtendedSpendingKey::master(&[]);
+        let to = extsk.default_address().1;
+
+        let notes = [to
+            .create_note(
+                nam(1),
+                10,
+                Rseed::BeforeZip212(jubjub::Fr::random(&mut rng)),
+            )
+            .unwrap()];
+
+        assert_eq!(plan_epoch_rollover(&notes, &BTreeMap::new()), vec![]);
+    }
+
+    #[test]
+    fn decrypted_transaction_reports_net_flows() {
+        let mut rng = OsRng;
+        let height = TEST_NETWORK
+            .activation_height(NetworkUpgrade::MASP)
+            .unwrap();
+        let zec = AssetType::new(b"ZEC").unwrap();
+
+        // The sender's keys and the recipient's address.
+        let extsk = ExtendedSpendingKey::master(&[7u8; 32]);
+        let sender = extsk.to_diversifiable_full_viewing_key();
+        let sender_addr = extsk.default_address().1;
+        let change_addr = extsk.derive_internal().default_address().1;
+        let recipient = ExtendedSpendingKey::master(&[8u8; 32]).to_diversifiable_full_viewing_key();
+        let recipient_addr = recipient.default_address().1;
+
+        // The sender spends a 50000 note: 20000 to the recipient, 29000
+        // change to the internal address, 1000 fee.
+        let note = to_note(&sender_addr, &zec, 50000, &mut rng);
+        let mut tree = CommitmentTree::empty();
+        tree.append(note.commitment()).unwrap();
+        let witness = IncrementalWitness::from_tree(&tree);
+
+        let (external_ovk, internal_ovk) = (extsk.external_ovk(), extsk.internal_ovk());
+        let mut builder = Builder::new(TEST_NETWORK, height);
+        builder
+            .add_sapling_spend(
+                extsk,
+                *sender_addr.diversifier(),
+                note,
+                witness.path().unwrap(),
+            )
+            .unwrap();
+        builder
+            .add_sapling_output(
+                Some(external_ovk),
+                recipient_addr,
+                zec,
+                20000,
+                MemoBytes::from_bytes(b"invoice 42").unwrap(),
+            )
+            .unwrap();
+        builder
+            .add_sapling_output(
+                Some(internal_ovk),
+                change_addr,
+                zec,
+                29000,
+                MemoBytes::empty(),
+            )
+            .unwrap();
+        let (tx, _) = builder
+            .mock_build(&mut OsRng, &mut RngBuildParams::new(OsRng))
+            .unwrap();
+
+        // The sender sees the payment and its change, netting to -20000.
+        let decrypted = decrypt_transaction(&TEST_NETWORK, height, &tx, &sender);
+        assert_eq!(decrypted.notes.len(), 2);
+        assert_eq!(decrypted.net_flows, I128Sum::from_pair(zec, -20000));
+        let outgoing = decrypted
+            .notes
+            .iter()
+            .find(|n| n.transfer_type == TransferType::Outgoing)
+            .unwrap();
+        assert_eq!(outgoing.note.value, 20000);
+        assert_eq!(outgoing.address, recipient_addr);
+        assert_eq!(outgoing.memo, MemoBytes::from_bytes(b"invoice 42").unwrap());
+        let change = decrypted
+            .notes
+            .iter()
+            .find(|n| n.transfer_type == TransferType::WalletInternal)
+            .unwrap();
+        assert_eq!(change.note.value, 29000);
+
+        // The recipient sees 20000 incoming and nothing else.
+        let decrypted = decrypt_transaction(&TEST_NETWORK, height, &tx, &recipient);
+        assert_eq!(decrypted.notes.len(), 1);
+        assert_eq!(decrypted.notes[0].transfer_type, TransferType::Incoming);
+        assert_eq!(decrypted.net_flows, I128Sum::from_pair(zec, 20000));
+
+        // An unrelated wallet sees nothing.
+        let stranger = ExtendedSpendingKey::master(&[9u8; 32]).to_diversifiable_full_viewing_key();
+        let decrypted = decrypt_transaction(&TEST_NETWORK, height, &tx, &stranger);
+        assert!(decrypted.notes.is_empty());
+        assert_eq!(decrypted.net_flows, I128Sum::zero());
+    }
+
+    /// Creates a note to the given address with a random pre-ZIP-212 rseed.
+    fn to_note(
+        addr: &PaymentAddress,
+        asset: &AssetType,
+        value: u64,
+        rng: &mut OsRng,
+    ) -> crate::sapling::Note {
+        addr.create_note(*asset, value, Rseed::BeforeZip212(jubjub::Fr::random(rng)))
+            .unwrap()
+    }
+}
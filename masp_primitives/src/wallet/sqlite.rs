@@ -0,0 +1,492 @@
+//! A SQLite-backed persistence layer for small wallets.
+//!
+//! [`WalletDb`] implements the [`WalletRead`] and [`WalletWrite`] storage
+//! traits on SQLite, so a small wallet gets durable storage for received
+//! notes, incremental witnesses, watched nullifiers, transaction history and
+//! balance queries without designing a schema. It persists the same shapes
+//! the wallet helpers consume — [`PositionedNote`] for spend planning,
+//! watched nullifiers for [`scan_block`] — so scanning, storage and
+//! transaction building wire together directly.
+//!
+//! Requires the `sqlite` feature.
+//!
+//! [`scan_block`]: crate::sapling::scanning::scan_block
+
+use std::error;
+use std::fmt;
+use std::path::Path;
+
+use borsh::BorshDeserialize;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::store::{WalletRead, WalletTx, WalletWrite};
+use super::PositionedNote;
+use crate::asset_type::AssetType;
+use crate::consensus::BlockHeight;
+use crate::merkle_tree::IncrementalWitness;
+use crate::sapling::{Diversifier, Node, Note, Nullifier};
+use crate::transaction::components::I128Sum;
+use crate::transaction::TxId;
+use crate::zip32::AccountId;
+
+/// Errors produced by the SQLite wallet store.
+#[derive(Debug)]
+pub enum WalletDbError {
+    /// The underlying database operation failed.
+    Db(rusqlite::Error),
+    /// A stored blob failed to decode, which means the database was written
+    /// by an incompatible version or has been corrupted.
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for WalletDbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletDbError::Db(e) => write!(f, "wallet database error: {}", e),
+            WalletDbError::Corrupt(what) => {
+                write!(f, "wallet database holds an undecodable {}", what)
+            }
+        }
+    }
+}
+
+impl error::Error for WalletDbError {}
+
+impl From<rusqlite::Error> for WalletDbError {
+    fn from(e: rusqlite::Error) -> Self {
+        WalletDbError::Db(e)
+    }
+}
+
+/// A SQLite-backed wallet store.
+pub struct WalletDb {
+    conn: Connection,
+}
+
+impl WalletDb {
+    /// Opens (and if necessary creates) a wallet database at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, WalletDbError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Opens an in-memory wallet database, for tests and ephemeral wallets.
+    pub fn open_in_memory() -> Result<Self, WalletDbError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, WalletDbError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS accounts (
+                account     INTEGER PRIMARY KEY
+            );
+            CREATE TABLE IF NOT EXISTS received_notes (
+                position    INTEGER PRIMARY KEY,
+                account     INTEGER NOT NULL,
+                diversifier BLOB NOT NULL,
+                note        BLOB NOT NULL,
+                asset_type  BLOB NOT NULL,
+                value       INTEGER NOT NULL,
+                nullifier   BLOB UNIQUE,
+                height      INTEGER NOT NULL,
+                spent       INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE IF NOT EXISTS witnesses (
+                position    INTEGER PRIMARY KEY,
+                witness     BLOB NOT NULL,
+                updated_at  INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS transactions (
+                txid        BLOB PRIMARY KEY,
+                height      INTEGER NOT NULL
+            );",
+        )?;
+        Ok(WalletDb { conn })
+    }
+}
+
+impl WalletRead for WalletDb {
+    type Error = WalletDbError;
+
+    fn accounts(&self) -> Result<Vec<AccountId>, Self::Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT account FROM accounts ORDER BY account")?;
+        let rows = stmt.query_map([], |row| row.get::<_, u32>(0))?;
+        rows.map(|id| Ok(AccountId(id?))).collect()
+    }
+
+    fn unspent_notes(&self, account: AccountId) -> Result<Vec<PositionedNote>, Self::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT position, diversifier, note FROM received_notes
+                WHERE account = ?1 AND spent = 0 ORDER BY position",
+        )?;
+        let rows = stmt.query_map(params![account.0], |row| {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, Vec<u8>>(1)?,
+                row.get::<_, Vec<u8>>(2)?,
+            ))
+        })?;
+        rows.map(|row| {
+            let (position, diversifier, note_bytes) = row?;
+            let diversifier = Diversifier(
+                diversifier
+                    .try_into()
+                    .map_err(|_| WalletDbError::Corrupt("diversifier"))?,
+            );
+            let note = Note::deserialize(&mut note_bytes.as_slice())
+                .map_err(|_| WalletDbError::Corrupt("note"))?;
+            Ok(PositionedNote {
+                diversifier,
+                note,
+                position,
+            })
+        })
+        .collect()
+    }
+
+    fn witness(&self, position: u64) -> Result<Option<IncrementalWitness<Node>>, Self::Error> {
+        let bytes: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT witness FROM witnesses WHERE position = ?1",
+                params![position],
+                |row| row.get(0),
+            )
+            .optional()?;
+        bytes
+            .map(|bytes| {
+                IncrementalWitness::read(&bytes[..]).map_err(|_| WalletDbError::Corrupt("witness"))
+            })
+            .transpose()
+    }
+
+    fn watched_nullifiers(&self) -> Result<Vec<Nullifier>, Self::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT nullifier FROM received_notes
+                WHERE spent = 0 AND nullifier IS NOT NULL
+                ORDER BY position",
+        )?;
+        let rows = stmt.query_map([], |row| row.get::<_, Vec<u8>>(0))?;
+        rows.map(|bytes| {
+            Nullifier::from_slice(&bytes?).map_err(|_| WalletDbError::Corrupt("nullifier"))
+        })
+        .collect()
+    }
+
+    /// Computes the balance per asset type in the database, without decoding
+    /// any note.
+    fn balance(&self, account: AccountId) -> Result<I128Sum, Self::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT asset_type, SUM(value) FROM received_notes
+                WHERE account = ?1 AND spent = 0 GROUP BY asset_type",
+        )?;
+        let rows = stmt.query_map(params![account.0], |row| {
+            Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        let mut balance = I128Sum::zero();
+        for row in rows {
+            let (identifier, value) = row?;
+            let identifier: [u8; 32] = identifier
+                .try_into()
+                .map_err(|_| WalletDbError::Corrupt("asset type"))?;
+            let asset_type = AssetType::from_identifier(&identifier)
+                .ok_or(WalletDbError::Corrupt("asset type"))?;
+            balance += &I128Sum::from_pair(asset_type, value.into());
+        }
+        Ok(balance)
+    }
+
+    fn spendable_notes(
+        &self,
+        asset: &AssetType,
+        min_confirmations: u32,
+        target_height: BlockHeight,
+    ) -> Result<Vec<PositionedNote>, Self::Error> {
+        let anchor = match u32::from(target_height).checked_sub(min_confirmations) {
+            Some(h) => h,
+            None => return Ok(vec![]),
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT n.position, n.diversifier, n.note
+                FROM received_notes n JOIN witnesses w ON w.position = n.position
+                WHERE n.spent = 0 AND n.asset_type = ?1
+                    AND n.height <= ?2 AND w.updated_at = ?2
+                ORDER BY n.position",
+        )?;
+        let rows = stmt.query_map(params![&asset.get_identifier()[..], anchor], |row| {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, Vec<u8>>(1)?,
+                row.get::<_, Vec<u8>>(2)?,
+            ))
+        })?;
+        rows.map(|row| {
+            let (position, diversifier, note_bytes) = row?;
+            let diversifier = Diversifier(
+                diversifier
+                    .try_into()
+                    .map_err(|_| WalletDbError::Corrupt("diversifier"))?,
+            );
+            let note = Note::deserialize(&mut note_bytes.as_slice())
+                .map_err(|_| WalletDbError::Corrupt("note"))?;
+            Ok(PositionedNote {
+                diversifier,
+                note,
+                position,
+            })
+        })
+        .collect()
+    }
+
+    fn tx_history(&self) -> Result<Vec<WalletTx>, Self::Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT txid, height FROM transactions ORDER BY height")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, u32>(1)?))
+        })?;
+        rows.map(|row| {
+            let (txid, height) = row?;
+            let txid: [u8; 32] = txid
+                .try_into()
+                .map_err(|_| WalletDbError::Corrupt("txid"))?;
+            Ok(WalletTx {
+                txid: TxId::from_bytes(txid),
+                height: BlockHeight::from(height),
+            })
+        })
+        .collect()
+    }
+}
+
+impl WalletWrite for WalletDb {
+    fn add_account(&mut self, account: AccountId) -> Result<(), Self::Error> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO accounts (account) VALUES (?1)",
+            params![account.0],
+        )?;
+        Ok(())
+    }
+
+    fn put_received_note(
+        &mut self,
+        account: AccountId,
+        note: &PositionedNote,
+        nullifier: Option<&Nullifier>,
+        height: BlockHeight,
+    ) -> Result<(), Self::Error> {
+        let note_bytes = borsh::to_vec(&note.note).map_err(|_| WalletDbError::Corrupt("note"))?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO received_notes
+                (position, account, diversifier, note, asset_type, value, nullifier, height)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                note.position,
+                account.0,
+                &note.diversifier.0[..],
+                note_bytes,
+                &note.note.asset_type.get_identifier()[..],
+                note.note.value,
+                nullifier.map(|nf| &nf.0[..]),
+                u32::from(height),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn put_witness(
+        &mut self,
+        position: u64,
+        witness: &IncrementalWitness<Node>,
+        updated_at: BlockHeight,
+    ) -> Result<(), Self::Error> {
+        let mut bytes = vec![];
+        witness
+            .write(&mut bytes)
+            .map_err(|_| WalletDbError::Corrupt("witness"))?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO witnesses (position, witness, updated_at)
+                VALUES (?1, ?2, ?3)",
+            params![position, bytes, u32::from(updated_at)],
+        )?;
+        Ok(())
+    }
+
+    fn mark_spent(&mut self, nullifier: &Nullifier) -> Result<bool, Self::Error> {
+        let affected = self.conn.execute(
+            "UPDATE received_notes SET spent = 1 WHERE nullifier = ?1",
+            params![&nullifier.0[..]],
+        )?;
+        Ok(affected > 0)
+    }
+
+    fn put_tx(&mut self, tx: WalletTx) -> Result<(), Self::Error> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO transactions (txid, height) VALUES (?1, ?2)",
+            params![&tx.txid.as_ref()[..], u32::from(tx.height)],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WalletDb;
+    use crate::asset_type::AssetType;
+    use crate::consensus::BlockHeight;
+    use crate::merkle_tree::{CommitmentTree, IncrementalWitness};
+    use crate::sapling::{Node, Nullifier, Rseed};
+    use crate::transaction::components::I128Sum;
+    use crate::transaction::TxId;
+    use crate::wallet::store::{WalletRead, WalletTx, WalletWrite};
+    use crate::wallet::PositionedNote;
+    use crate::zip32::{AccountId, ExtendedSpendingKey};
+
+    fn test_note(seed: &[u8], asset: &AssetType, value: u64, position: u64) -> PositionedNote {
+        let addr = ExtendedSpendingKey::master(seed).default_address().1;
+        PositionedNote {
+            diversifier: *addr.diversifier(),
+            note: addr
+                .create_note(*asset, value, Rseed::AfterZip212([position as u8; 32]))
+                .unwrap(),
+            position,
+        }
+    }
+
+    #[test]
+    fn notes_nullifiers_and_balance_round_trip() {
+        let mut db = WalletDb::open_in_memory().unwrap();
+        db.add_account(AccountId(0)).unwrap();
+        db.add_account(AccountId(0)).unwrap();
+        assert_eq!(db.accounts().unwrap(), vec![AccountId(0)]);
+
+        let btc = AssetType::new(b"BTC").unwrap();
+        let eth = AssetType::new(b"ETH").unwrap();
+
+        let a = test_note(&[], &btc, 100, 0);
+        let b = test_note(&[], &btc, 50, 1);
+        let c = test_note(&[], &eth, 7, 2);
+        let (nf_a, nf_b) = (Nullifier([1; 32]), Nullifier([2; 32]));
+        let height = BlockHeight::from(5);
+        db.put_received_note(AccountId(0), &a, Some(&nf_a), height)
+            .unwrap();
+        db.put_received_note(AccountId(0), &b, Some(&nf_b), height)
+            .unwrap();
+        db.put_received_note(AccountId(0), &c, None, height)
+            .unwrap();
+
+        // Rescans overwrite rather than duplicate.
+        db.put_received_note(AccountId(0), &a, Some(&nf_a), height)
+            .unwrap();
+
+        assert_eq!(db.watched_nullifiers().unwrap(), vec![nf_a, nf_b]);
+        assert_eq!(
+            db.balance(AccountId(0)).unwrap(),
+            I128Sum::from_pair(btc, 150) + I128Sum::from_pair(eth, 7)
+        );
+        assert_eq!(db.balance(AccountId(9)).unwrap(), I128Sum::zero());
+
+        // Spending a note removes it from the watch set and the balance.
+        assert!(db.mark_spent(&nf_a).unwrap());
+        assert!(!db.mark_spent(&Nullifier([9; 32])).unwrap());
+        assert_eq!(db.watched_nullifiers().unwrap(), vec![nf_b]);
+        assert_eq!(
+            db.balance(AccountId(0)).unwrap(),
+            I128Sum::from_pair(btc, 50) + I128Sum::from_pair(eth, 7)
+        );
+
+        let unspent = db.unspent_notes(AccountId(0)).unwrap();
+        assert_eq!(unspent.len(), 2);
+        assert_eq!(unspent[0].position, 1);
+        assert_eq!(unspent[0].note.value, 50);
+        assert_eq!(unspent[1].position, 2);
+        assert_eq!(unspent[1].note.asset_type, eth);
+    }
+
+    #[test]
+    fn witness_persistence_round_trip() {
+        let mut db = WalletDb::open_in_memory().unwrap();
+
+        let mut tree = CommitmentTree::empty();
+        tree.append(Node::new([1; 32])).unwrap();
+        let mut witness = IncrementalWitness::from_tree(&tree);
+        tree.append(Node::new([2; 32])).unwrap();
+        witness.append(Node::new([2; 32])).unwrap();
+
+        assert!(db.witness(0).unwrap().is_none());
+        db.put_witness(0, &witness, BlockHeight::from(1)).unwrap();
+        let restored = db.witness(0).unwrap().unwrap();
+        assert_eq!(restored.position(), witness.position());
+        assert_eq!(restored.root(), witness.root());
+    }
+
+    #[test]
+    fn spendable_notes_honor_confirmations_and_witness_anchor() {
+        let mut db = WalletDb::open_in_memory().unwrap();
+        let btc = AssetType::new(b"BTC").unwrap();
+        let account = AccountId(0);
+
+        let mut tree = CommitmentTree::empty();
+        let mut witness_at = |db: &mut WalletDb, position: u64, updated_at: u32| {
+            tree.append(Node::new([position as u8; 32])).unwrap();
+            let witness = IncrementalWitness::from_tree(&tree);
+            db.put_witness(position, &witness, BlockHeight::from(updated_at))
+                .unwrap();
+        };
+
+        // Mature note with a witness at the anchor: spendable.
+        let mature = test_note(&[], &btc, 100, 0);
+        db.put_received_note(account, &mature, None, BlockHeight::from(5))
+            .unwrap();
+        witness_at(&mut db, 0, 7);
+
+        // Too recent for three confirmations.
+        db.put_received_note(
+            account,
+            &test_note(&[], &btc, 50, 1),
+            None,
+            BlockHeight::from(9),
+        )
+        .unwrap();
+        witness_at(&mut db, 1, 7);
+
+        // Mature, but the witness lags the anchor.
+        db.put_received_note(
+            account,
+            &test_note(&[], &btc, 25, 2),
+            None,
+            BlockHeight::from(5),
+        )
+        .unwrap();
+        witness_at(&mut db, 2, 6);
+
+        // Target height 10 with 3 confirmations anchors at height 7.
+        let spendable = db.spendable_notes(&btc, 3, BlockHeight::from(10)).unwrap();
+        assert_eq!(spendable.len(), 1);
+        assert_eq!(spendable[0].position, 0);
+        assert_eq!(spendable[0].note.value, 100);
+
+        // An anchor below the genesis block yields nothing.
+        assert!(db
+            .spendable_notes(&btc, 20, BlockHeight::from(10))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn tx_history_round_trip() {
+        let mut db = WalletDb::open_in_memory().unwrap();
+        let newer = WalletTx {
+            txid: TxId::from_bytes([1; 32]),
+            height: BlockHeight::from(9),
+        };
+        let older = WalletTx {
+            txid: TxId::from_bytes([2; 32]),
+            height: BlockHeight::from(3),
+        };
+        db.put_tx(newer).unwrap();
+        db.put_tx(older).unwrap();
+        assert_eq!(db.tx_history().unwrap(), vec![older, newer]);
+    }
+}
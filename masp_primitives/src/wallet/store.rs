@@ -0,0 +1,370 @@
+//! Pluggable wallet storage.
+//!
+//! [`WalletRead`] and [`WalletWrite`] describe the persistence a syncing
+//! wallet needs — accounts, received notes, witnesses, watched nullifiers,
+//! transaction history and per-asset balances — without fixing a backend.
+//! Sync and transaction-building logic written against these traits runs
+//! unchanged on top of SQLite (`sqlite::WalletDb`, behind the `sqlite`
+//! feature), the in-memory [`MemoryWalletDb`] provided here, or any other
+//! store (RocksDB, browser storage, ...) a downstream crate implements.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+
+use super::PositionedNote;
+use crate::asset_type::AssetType;
+use crate::consensus::BlockHeight;
+use crate::merkle_tree::IncrementalWitness;
+use crate::sapling::{Node, Nullifier};
+use crate::transaction::components::I128Sum;
+use crate::transaction::TxId;
+use crate::zip32::AccountId;
+
+/// A transaction the wallet participated in, as recorded in its history.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WalletTx {
+    /// The transaction's identifier.
+    pub txid: TxId,
+    /// The height of the block that mined the transaction.
+    pub height: BlockHeight,
+}
+
+/// Read access to a wallet store.
+pub trait WalletRead {
+    /// The backend's storage error.
+    type Error;
+
+    /// Returns the accounts the wallet tracks, in ascending order.
+    fn accounts(&self) -> Result<Vec<AccountId>, Self::Error>;
+
+    /// Returns an account's unspent notes with their tree positions, in
+    /// position order.
+    fn unspent_notes(&self, account: AccountId) -> Result<Vec<PositionedNote>, Self::Error>;
+
+    /// Returns the stored witness for the note at the given position, if any.
+    fn witness(&self, position: u64) -> Result<Option<IncrementalWitness<Node>>, Self::Error>;
+
+    /// Returns the nullifiers of all unspent notes across accounts, i.e. the
+    /// set a sync loop should watch for in incoming blocks.
+    fn watched_nullifiers(&self) -> Result<Vec<Nullifier>, Self::Error>;
+
+    /// Returns an account's spendable balance per asset type.
+    fn balance(&self, account: AccountId) -> Result<I128Sum, Self::Error>;
+
+    /// Returns the notes of the given asset that are actually spendable in a
+    /// transaction targeting `target_height`.
+    ///
+    /// A note qualifies only if it is unspent, was mined at or before the
+    /// anchor height `target_height - min_confirmations`, and its witness has
+    /// been updated to exactly that anchor — a witness behind (or ahead of)
+    /// the anchor would produce a Merkle path that fails validation, so such
+    /// notes are excluded rather than allowed to poison a transaction.
+    fn spendable_notes(
+        &self,
+        asset: &AssetType,
+        min_confirmations: u32,
+        target_height: BlockHeight,
+    ) -> Result<Vec<PositionedNote>, Self::Error>;
+
+    /// Returns the wallet's transaction history, in block order.
+    fn tx_history(&self) -> Result<Vec<WalletTx>, Self::Error>;
+}
+
+/// Write access to a wallet store.
+pub trait WalletWrite: WalletRead {
+    /// Registers an account with the wallet. Adding an existing account is a
+    /// no-op.
+    fn add_account(&mut self, account: AccountId) -> Result<(), Self::Error>;
+
+    /// Stores a note received by the given account in the block at `height`,
+    /// together with its nullifier if the wallet can derive one (an
+    /// incoming-viewing-key-only wallet cannot).
+    ///
+    /// Storing a note twice at the same position replaces the earlier
+    /// record, so rescans are idempotent.
+    fn put_received_note(
+        &mut self,
+        account: AccountId,
+        note: &PositionedNote,
+        nullifier: Option<&Nullifier>,
+        height: BlockHeight,
+    ) -> Result<(), Self::Error>;
+
+    /// Persists the incremental witness for the note at the given position,
+    /// recording that it reflects the tree as of the block at `updated_at`.
+    fn put_witness(
+        &mut self,
+        position: u64,
+        witness: &IncrementalWitness<Node>,
+        updated_at: BlockHeight,
+    ) -> Result<(), Self::Error>;
+
+    /// Marks the note carrying the given nullifier as spent.
+    ///
+    /// Returns whether a note was affected; `false` means the nullifier does
+    /// not belong to this wallet.
+    fn mark_spent(&mut self, nullifier: &Nullifier) -> Result<bool, Self::Error>;
+
+    /// Records a transaction in the wallet's history.
+    fn put_tx(&mut self, tx: WalletTx) -> Result<(), Self::Error>;
+}
+
+struct MemoryNote {
+    account: AccountId,
+    note: PositionedNote,
+    nullifier: Option<Nullifier>,
+    height: BlockHeight,
+    spent: bool,
+}
+
+/// An in-memory [`WalletWrite`] implementation, for tests and ephemeral
+/// wallets.
+#[derive(Default)]
+pub struct MemoryWalletDb {
+    accounts: Vec<AccountId>,
+    notes: BTreeMap<u64, MemoryNote>,
+    witnesses: BTreeMap<u64, (IncrementalWitness<Node>, BlockHeight)>,
+    txs: Vec<WalletTx>,
+}
+
+impl MemoryWalletDb {
+    /// Constructs an empty in-memory wallet store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl WalletRead for MemoryWalletDb {
+    type Error = Infallible;
+
+    fn accounts(&self) -> Result<Vec<AccountId>, Self::Error> {
+        Ok(self.accounts.clone())
+    }
+
+    fn unspent_notes(&self, account: AccountId) -> Result<Vec<PositionedNote>, Self::Error> {
+        Ok(self
+            .notes
+            .values()
+            .filter(|n| n.account == account && !n.spent)
+            .map(|n| n.note.clone())
+            .collect())
+    }
+
+    fn witness(&self, position: u64) -> Result<Option<IncrementalWitness<Node>>, Self::Error> {
+        Ok(self.witnesses.get(&position).map(|(w, _)| w.clone()))
+    }
+
+    fn watched_nullifiers(&self) -> Result<Vec<Nullifier>, Self::Error> {
+        Ok(self
+            .notes
+            .values()
+            .filter(|n| !n.spent)
+            .filter_map(|n| n.nullifier)
+            .collect())
+    }
+
+    fn balance(&self, account: AccountId) -> Result<I128Sum, Self::Error> {
+        Ok(self
+            .notes
+            .values()
+            .filter(|n| n.account == account && !n.spent)
+            .fold(I128Sum::zero(), |acc, n| {
+                acc + I128Sum::from_pair(n.note.note.asset_type, n.note.note.value.into())
+            }))
+    }
+
+    fn spendable_notes(
+        &self,
+        asset: &AssetType,
+        min_confirmations: u32,
+        target_height: BlockHeight,
+    ) -> Result<Vec<PositionedNote>, Self::Error> {
+        let anchor = match u32::from(target_height).checked_sub(min_confirmations) {
+            Some(h) => BlockHeight::from(h),
+            None => return Ok(vec![]),
+        };
+        Ok(self
+            .notes
+            .values()
+            .filter(|n| !n.spent && n.note.note.asset_type == *asset && n.height <= anchor)
+            .filter(|n| {
+                self.witnesses
+                    .get(&n.note.position)
+                    .is_some_and(|(_, at)| *at == anchor)
+            })
+            .map(|n| n.note.clone())
+            .collect())
+    }
+
+    fn tx_history(&self) -> Result<Vec<WalletTx>, Self::Error> {
+        Ok(self.txs.clone())
+    }
+}
+
+impl WalletWrite for MemoryWalletDb {
+    fn add_account(&mut self, account: AccountId) -> Result<(), Self::Error> {
+        if let Err(at) = self.accounts.binary_search(&account) {
+            self.accounts.insert(at, account);
+        }
+        Ok(())
+    }
+
+    fn put_received_note(
+        &mut self,
+        account: AccountId,
+        note: &PositionedNote,
+        nullifier: Option<&Nullifier>,
+        height: BlockHeight,
+    ) -> Result<(), Self::Error> {
+        self.notes.insert(
+            note.position,
+            MemoryNote {
+                account,
+                note: note.clone(),
+                nullifier: nullifier.copied(),
+                height,
+                spent: false,
+            },
+        );
+        Ok(())
+    }
+
+    fn put_witness(
+        &mut self,
+        position: u64,
+        witness: &IncrementalWitness<Node>,
+        updated_at: BlockHeight,
+    ) -> Result<(), Self::Error> {
+        self.witnesses
+            .insert(position, (witness.clone(), updated_at));
+        Ok(())
+    }
+
+    fn mark_spent(&mut self, nullifier: &Nullifier) -> Result<bool, Self::Error> {
+        for note in self.notes.values_mut() {
+            if note.nullifier.as_ref() == Some(nullifier) {
+                note.spent = true;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn put_tx(&mut self, tx: WalletTx) -> Result<(), Self::Error> {
+        self.txs.push(tx);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryWalletDb, WalletRead, WalletTx, WalletWrite};
+    use crate::asset_type::AssetType;
+    use crate::consensus::BlockHeight;
+    use crate::sapling::{Nullifier, Rseed};
+    use crate::transaction::components::I128Sum;
+    use crate::transaction::TxId;
+    use crate::wallet::PositionedNote;
+    use crate::zip32::{AccountId, ExtendedSpendingKey};
+
+    fn test_note(asset: &AssetType, value: u64, position: u64) -> PositionedNote {
+        let addr = ExtendedSpendingKey::master(&[]).default_address().1;
+        PositionedNote {
+            diversifier: *addr.diversifier(),
+            note: addr
+                .create_note(*asset, value, Rseed::AfterZip212([position as u8; 32]))
+                .unwrap(),
+            position,
+        }
+    }
+
+    #[test]
+    fn memory_wallet_round_trip() {
+        let mut db = MemoryWalletDb::new();
+        db.add_account(AccountId(1)).unwrap();
+        db.add_account(AccountId(0)).unwrap();
+        db.add_account(AccountId(1)).unwrap();
+        assert_eq!(db.accounts().unwrap(), vec![AccountId(0), AccountId(1)]);
+
+        let btc = AssetType::new(b"BTC").unwrap();
+        let (nf_a, nf_b) = (Nullifier([1; 32]), Nullifier([2; 32]));
+        let height = BlockHeight::from(5);
+        db.put_received_note(AccountId(0), &test_note(&btc, 100, 0), Some(&nf_a), height)
+            .unwrap();
+        db.put_received_note(AccountId(1), &test_note(&btc, 50, 1), Some(&nf_b), height)
+            .unwrap();
+        // Rescans overwrite rather than duplicate.
+        db.put_received_note(AccountId(0), &test_note(&btc, 100, 0), Some(&nf_a), height)
+            .unwrap();
+
+        assert_eq!(db.watched_nullifiers().unwrap(), vec![nf_a, nf_b]);
+        assert_eq!(
+            db.balance(AccountId(0)).unwrap(),
+            I128Sum::from_pair(btc, 100)
+        );
+
+        assert!(db.mark_spent(&nf_a).unwrap());
+        assert!(!db.mark_spent(&Nullifier([9; 32])).unwrap());
+        assert_eq!(db.watched_nullifiers().unwrap(), vec![nf_b]);
+        assert_eq!(db.balance(AccountId(0)).unwrap(), I128Sum::zero());
+        assert!(db.unspent_notes(AccountId(0)).unwrap().is_empty());
+        assert_eq!(db.unspent_notes(AccountId(1)).unwrap().len(), 1);
+
+        let tx = WalletTx {
+            txid: TxId::from_bytes([3; 32]),
+            height: BlockHeight::from(7),
+        };
+        db.put_tx(tx).unwrap();
+        assert_eq!(db.tx_history().unwrap(), vec![tx]);
+    }
+
+    #[test]
+    fn spendable_notes_honor_confirmations_and_witness_anchor() {
+        let mut db = MemoryWalletDb::new();
+        let btc = AssetType::new(b"BTC").unwrap();
+        let eth = AssetType::new(b"ETH").unwrap();
+        let account = AccountId(0);
+
+        let mut tree = crate::merkle_tree::CommitmentTree::empty();
+        let mut witness_at = |db: &mut MemoryWalletDb, position: u64, updated_at: u32| {
+            tree.append(crate::sapling::Node::new([position as u8; 32]))
+                .unwrap();
+            let witness = crate::merkle_tree::IncrementalWitness::from_tree(&tree);
+            db.put_witness(position, &witness, BlockHeight::from(updated_at))
+                .unwrap();
+        };
+
+        // Mature note with a witness at the anchor: spendable.
+        let mature = test_note(&btc, 100, 0);
+        db.put_received_note(account, &mature, None, BlockHeight::from(5))
+            .unwrap();
+        witness_at(&mut db, 0, 7);
+
+        // Too recent for three confirmations.
+        db.put_received_note(account, &test_note(&btc, 50, 1), None, BlockHeight::from(9))
+            .unwrap();
+        witness_at(&mut db, 1, 7);
+
+        // Mature, but the witness lags the anchor.
+        db.put_received_note(account, &test_note(&btc, 25, 2), None, BlockHeight::from(5))
+            .unwrap();
+        witness_at(&mut db, 2, 6);
+
+        // Mature with a fresh witness, but the wrong asset.
+        db.put_received_note(account, &test_note(&eth, 10, 3), None, BlockHeight::from(5))
+            .unwrap();
+        witness_at(&mut db, 3, 7);
+
+        // Target height 10 with 3 confirmations anchors at height 7.
+        let spendable = db.spendable_notes(&btc, 3, BlockHeight::from(10)).unwrap();
+        assert_eq!(spendable.len(), 1);
+        assert_eq!(spendable[0].position, 0);
+        assert_eq!(spendable[0].note.value, 100);
+
+        // An anchor below the genesis block yields nothing.
+        assert!(db
+            .spendable_notes(&btc, 20, BlockHeight::from(10))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -11,8 +11,15 @@ use memuse::{self, DynamicUsage};
 use std::collections::BTreeMap;
 use std::convert::{TryFrom, TryInto};
 
+use crate::errors::Error;
 use crate::sapling::{Diversifier, NullifierDerivingKey, PaymentAddress, ViewingKey};
 
+pub mod accounts;
+pub mod arbitrary_kd;
+pub mod audit;
+pub mod backup;
+pub mod discovery;
+pub mod encrypted_store;
 pub mod sapling;
 
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -55,6 +62,116 @@ impl ChildIndex {
     }
 }
 
+/// A type-safe wrapper for ZIP 32 account indices, as used at the third level
+/// of the `m/32'/coin_type'/account'` derivation path. The index is always
+/// hardened during derivation.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    BorshSchema,
+)]
+pub struct AccountId(pub u32);
+
+impl From<u32> for AccountId {
+    fn from(id: u32) -> Self {
+        AccountId(id)
+    }
+}
+
+impl From<AccountId> for u32 {
+    fn from(id: AccountId) -> u32 {
+        id.0
+    }
+}
+
+/// A sequence of child indices describing a derivation path below a master
+/// key, expressible in the standard BIP-32-style notation `m/32'/877'/0'`
+/// (where `'` marks a hardened index) via its [`FromStr`] and
+/// [`std::fmt::Display`] implementations.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KeyPath(pub Vec<ChildIndex>);
+
+/// Errors produced when parsing a [`KeyPath`] from its string notation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyPathError {
+    /// The path does not begin with the master key marker `m`
+    MissingMasterMarker,
+    /// A path component is not a decimal child index below 2^31, optionally
+    /// suffixed with `'`
+    InvalidChildIndex(String),
+}
+
+impl std::fmt::Display for KeyPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            KeyPathError::MissingMasterMarker => {
+                write!(f, "Derivation path must begin with \"m\"")
+            }
+            KeyPathError::InvalidChildIndex(component) => {
+                write!(f, "Invalid child index \"{}\"", component)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeyPathError {}
+
+impl std::fmt::Display for KeyPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            match index {
+                ChildIndex::NonHardened(i) => write!(f, "/{}", i)?,
+                ChildIndex::Hardened(i) => write!(f, "/{}'", i)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for KeyPath {
+    type Err = KeyPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut components = s.split('/');
+        if components.next() != Some("m") {
+            return Err(KeyPathError::MissingMasterMarker);
+        }
+        components
+            .map(|component| {
+                let (digits, hardened) = match component.strip_suffix('\'') {
+                    Some(digits) => (digits, true),
+                    None => (component, false),
+                };
+                if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(KeyPathError::InvalidChildIndex(component.into()));
+                }
+                let i: u32 = digits
+                    .parse()
+                    .map_err(|_| KeyPathError::InvalidChildIndex(component.into()))?;
+                if i >= (1 << 31) {
+                    return Err(KeyPathError::InvalidChildIndex(component.into()));
+                }
+                Ok(if hardened {
+                    ChildIndex::Hardened(i)
+                } else {
+                    ChildIndex::NonHardened(i)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(KeyPath)
+    }
+}
+
 impl BorshSerialize for ChildIndex {
     fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
         self.value().serialize(writer)
@@ -91,7 +208,7 @@ pub struct ChainCode([u8; 32]);
 impl ChainCode {
     /// Returns byte representation of the chain code, as required for
     /// [ZIP 32](https://zips.z.cash/zip-0032) encoding.
-    fn as_bytes(&self) -> &[u8; 32] {
+    pub fn as_bytes(&self) -> &[u8; 32] {
         &self.0
     }
 }
@@ -134,7 +251,7 @@ impl DiversifierIndex {
         DiversifierIndex([0; 11])
     }
 
-    pub fn increment(&mut self) -> Result<(), ()> {
+    pub fn increment(&mut self) -> Result<(), Error> {
         for k in 0..11 {
             self.0[k] = self.0[k].wrapping_add(1);
             if self.0[k] != 0 {
@@ -143,7 +260,7 @@ impl DiversifierIndex {
             }
         }
         // Overflow
-        Err(())
+        Err(Error::DiversifierSpaceExhausted)
     }
 }
 
@@ -171,8 +288,36 @@ memuse::impl_no_dynamic_usage!(Scope);
 
 #[cfg(test)]
 mod tests {
-    use super::DiversifierIndex;
+    use super::{ChildIndex, DiversifierIndex, KeyPath, KeyPathError};
     use std::convert::TryFrom;
+    use std::str::FromStr;
+
+    #[test]
+    fn key_path_string_round_trip() {
+        let path = KeyPath(vec![
+            ChildIndex::Hardened(32),
+            ChildIndex::Hardened(877),
+            ChildIndex::Hardened(0),
+            ChildIndex::NonHardened(5),
+        ]);
+        assert_eq!(path.to_string(), "m/32'/877'/0'/5");
+        assert_eq!(KeyPath::from_str("m/32'/877'/0'/5"), Ok(path));
+
+        // The bare master marker denotes the empty path
+        assert_eq!(KeyPath::from_str("m"), Ok(KeyPath(vec![])));
+
+        // Rejected inputs
+        assert_eq!(
+            KeyPath::from_str("32'/877'"),
+            Err(KeyPathError::MissingMasterMarker)
+        );
+        for bad in ["m/", "m/32''", "m/+5", "m/x", &format!("m/{}", 1u64 << 31)] {
+            assert!(matches!(
+                KeyPath::from_str(bad),
+                Err(KeyPathError::InvalidChildIndex(_))
+            ));
+        }
+    }
 
     #[test]
     fn diversifier_index_to_u32() {
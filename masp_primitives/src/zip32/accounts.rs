@@ -0,0 +1,168 @@
+//! A container managing multiple ZIP 32 accounts derived from one seed.
+//!
+//! [`AccountKeys`] owns the master key for a `m/32'/coin_type'/account'`
+//! derivation tree and hands out per-account spending and viewing keys on
+//! demand, so wallets can model "Account 0, Account 1" without doing their
+//! own derivation bookkeeping. Only the master key and the number of created
+//! accounts are stored (and serialized); account keys are re-derived when
+//! asked for, so the container stays the same size however many accounts the
+//! wallet opens.
+
+use std::io::{self, Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use super::{
+    sapling::{DiversifiableFullViewingKey, ExtendedSpendingKey},
+    AccountId, ChildIndex,
+};
+
+/// The spending keys for a wallet's ZIP 32 accounts, all derived from one
+/// seed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountKeys {
+    master: ExtendedSpendingKey,
+    coin_type: u32,
+    account_count: u32,
+}
+
+impl AccountKeys {
+    /// Derives a container from a seed for the given coin type, starting with
+    /// a single account (account 0).
+    pub fn from_seed(seed: &[u8], coin_type: u32) -> Self {
+        AccountKeys {
+            master: ExtendedSpendingKey::master(seed),
+            coin_type,
+            account_count: 1,
+        }
+    }
+
+    /// The coin type this container derives under.
+    pub fn coin_type(&self) -> u32 {
+        self.coin_type
+    }
+
+    /// Opens the next account and returns its identifier.
+    pub fn create_account(&mut self) -> AccountId {
+        let account = AccountId(self.account_count);
+        self.account_count += 1;
+        account
+    }
+
+    /// Returns the identifiers of all opened accounts, in order.
+    pub fn accounts(&self) -> impl Iterator<Item = AccountId> {
+        (0..self.account_count).map(AccountId)
+    }
+
+    /// Derives the spending key of the given account, following the
+    /// `m/32'/coin_type'/account'` path.
+    ///
+    /// Returns `None` for accounts that have not been opened with
+    /// [`Self::create_account`].
+    pub fn spending_key(&self, account: AccountId) -> Option<ExtendedSpendingKey> {
+        if account.0 >= self.account_count {
+            return None;
+        }
+        Some(ExtendedSpendingKey::from_path(
+            &self.master,
+            &[
+                ChildIndex::Hardened(32),
+                ChildIndex::Hardened(self.coin_type),
+                ChildIndex::Hardened(account.0),
+            ],
+        ))
+    }
+
+    /// Exports the full viewing key of the given account, for handing to
+    /// scanners and watch-only wallets.
+    pub fn viewing_key(&self, account: AccountId) -> Option<DiversifiableFullViewingKey> {
+        self.spending_key(account)
+            .map(|extsk| extsk.to_diversifiable_full_viewing_key())
+    }
+
+    /// Reads a container from its serialized form.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let master = ExtendedSpendingKey::read(&mut reader)?;
+        let coin_type = reader.read_u32::<LittleEndian>()?;
+        let account_count = reader.read_u32::<LittleEndian>()?;
+        if account_count == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "account container holds no accounts",
+            ));
+        }
+        Ok(AccountKeys {
+            master,
+            coin_type,
+            account_count,
+        })
+    }
+
+    /// Writes the container in its serialized form.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.master.write(&mut writer)?;
+        writer.write_u32::<LittleEndian>(self.coin_type)?;
+        writer.write_u32::<LittleEndian>(self.account_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccountKeys;
+    use crate::zip32::{AccountId, ChildIndex, ExtendedSpendingKey};
+
+    #[test]
+    fn accounts_derive_along_the_zip32_path() {
+        let seed = [0x5a; 32];
+        let mut keys = AccountKeys::from_seed(&seed, 877);
+        assert_eq!(keys.accounts().collect::<Vec<_>>(), vec![AccountId(0)]);
+
+        assert_eq!(keys.create_account(), AccountId(1));
+        assert_eq!(keys.create_account(), AccountId(2));
+        assert_eq!(
+            keys.accounts().collect::<Vec<_>>(),
+            vec![AccountId(0), AccountId(1), AccountId(2)]
+        );
+
+        // Each account key matches direct derivation of m/32'/877'/account'.
+        let master = ExtendedSpendingKey::master(&seed);
+        for account in keys.accounts() {
+            let expected = ExtendedSpendingKey::from_path(
+                &master,
+                &[
+                    ChildIndex::Hardened(32),
+                    ChildIndex::Hardened(877),
+                    ChildIndex::Hardened(account.0),
+                ],
+            );
+            assert_eq!(keys.spending_key(account), Some(expected));
+            assert_eq!(
+                keys.viewing_key(account).unwrap().to_bytes(),
+                expected.to_diversifiable_full_viewing_key().to_bytes()
+            );
+        }
+
+        // Accounts are distinct, and unopened accounts are not derived.
+        assert_ne!(
+            keys.spending_key(AccountId(0)),
+            keys.spending_key(AccountId(1))
+        );
+        assert_eq!(keys.spending_key(AccountId(3)), None);
+        assert!(keys.viewing_key(AccountId(3)).is_none());
+    }
+
+    #[test]
+    fn container_round_trips_through_serialization() {
+        let mut keys = AccountKeys::from_seed(&[1; 32], 877);
+        keys.create_account();
+
+        let mut bytes = vec![];
+        keys.write(&mut bytes).unwrap();
+        assert_eq!(AccountKeys::read(&bytes[..]).unwrap(), keys);
+
+        // A container claiming zero accounts is malformed.
+        let len = bytes.len();
+        bytes[len - 4..].copy_from_slice(&0u32.to_le_bytes());
+        assert!(AccountKeys::read(&bytes[..]).is_err());
+    }
+}
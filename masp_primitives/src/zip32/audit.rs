@@ -0,0 +1,273 @@
+//! Signed attestations of deterministic address derivation.
+//!
+//! A custodian holding a seed can prove to an auditor which shielded addresses
+//! it controls without revealing any key material: it derives each requested
+//! (derivation path, diversifier index) pair to its payment address and signs
+//! the resulting mapping with the master spending authorization key. The
+//! auditor checks the signature against the `ak` from the custodian's
+//! previously registered full viewing key.
+
+use std::io::{self, Read, Write};
+
+use blake2b_simd::Params as Blake2bParams;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use rand_core::RngCore;
+use zcash_encoding::Vector;
+
+use super::{sapling::ExtendedSpendingKey, ChildIndex, DiversifierIndex};
+use crate::constants::SPENDING_KEY_GENERATOR;
+use crate::sapling::{
+    redjubjub::{PrivateKey, PublicKey, Signature},
+    PaymentAddress,
+};
+
+/// BLAKE2b personalization for the attestation signature digest.
+const AUDIT_SIG_PERSONALIZATION: &[u8; 16] = b"MASP__AddrAudit_";
+
+/// One derived address together with the derivation data that produced it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttestedAddress {
+    /// The derivation path from the master key, per ZIP 32.
+    pub path: Vec<ChildIndex>,
+    /// The diversifier index the address was derived at.
+    pub diversifier_index: DiversifierIndex,
+    /// The payment address at that path and index.
+    pub address: PaymentAddress,
+}
+
+impl AttestedAddress {
+    fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let path = Vector::read(&mut reader, |r| {
+            r.read_u32::<LittleEndian>().map(ChildIndex::from_index)
+        })?;
+        let mut j = [0u8; 11];
+        reader.read_exact(&mut j)?;
+        let mut addr = [0u8; 43];
+        reader.read_exact(&mut addr)?;
+        let address = PaymentAddress::from_bytes(&addr)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid payment address"))?;
+        Ok(AttestedAddress {
+            path,
+            diversifier_index: DiversifierIndex(j),
+            address,
+        })
+    }
+
+    fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        Vector::write(&mut writer, &self.path, |w, i| {
+            w.write_u32::<LittleEndian>(i.value())
+        })?;
+        writer.write_all(&self.diversifier_index.0)?;
+        writer.write_all(&self.address.to_bytes())
+    }
+}
+
+/// Errors that can occur while producing an attestation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditError {
+    /// The requested diversifier index does not produce a valid diversifier
+    /// for the key at the requested path.
+    InvalidDiversifierIndex,
+}
+
+impl std::fmt::Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditError::InvalidDiversifierIndex => write!(
+                f,
+                "diversifier index does not produce a valid diversifier at the requested path"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+/// A signed mapping from derivation paths and diversifier indices to payment
+/// addresses.
+///
+/// The signature covers every entry and is made with the master key's spend
+/// authorization key `ask`; its verification key is the `ak` component of the
+/// master full viewing key, which the auditor is expected to already know.
+#[derive(Clone, Debug)]
+pub struct AddressAttestation {
+    signer: PublicKey,
+    entries: Vec<AttestedAddress>,
+    sig: Signature,
+}
+
+impl AddressAttestation {
+    /// Derives and signs an attestation for the given (path, diversifier
+    /// index) pairs from the given seed.
+    ///
+    /// Returns an error if any requested diversifier index is invalid for the
+    /// key at its path; use [`DiversifiableFullViewingKey::find_address`] to
+    /// locate valid indices first.
+    ///
+    /// [`DiversifiableFullViewingKey::find_address`]:
+    /// crate::zip32::sapling::DiversifiableFullViewingKey::find_address
+    pub fn create<R: RngCore>(
+        seed: &[u8],
+        requests: &[(Vec<ChildIndex>, DiversifierIndex)],
+        rng: &mut R,
+    ) -> Result<Self, AuditError> {
+        let master = ExtendedSpendingKey::master(seed);
+
+        let mut entries = Vec::with_capacity(requests.len());
+        for (path, j) in requests {
+            let xsk = ExtendedSpendingKey::from_path(&master, path);
+            let address = xsk
+                .to_diversifiable_full_viewing_key()
+                .address(*j)
+                .ok_or(AuditError::InvalidDiversifierIndex)?;
+            entries.push(AttestedAddress {
+                path: path.clone(),
+                diversifier_index: *j,
+                address,
+            });
+        }
+
+        let sk = PrivateKey(master.expsk.ask);
+        let signer = PublicKey::from_private(&sk, SPENDING_KEY_GENERATOR);
+        let sig = sk.sign(
+            &Self::digest(&signer, &entries),
+            rng,
+            SPENDING_KEY_GENERATOR,
+        );
+
+        Ok(AddressAttestation {
+            signer,
+            entries,
+            sig,
+        })
+    }
+
+    /// The verification key the attestation is signed under; for an honest
+    /// custodian this is the `ak` of the master full viewing key.
+    pub fn signer(&self) -> &PublicKey {
+        &self.signer
+    }
+
+    /// The attested (path, diversifier index, address) entries.
+    pub fn entries(&self) -> &[AttestedAddress] {
+        &self.entries
+    }
+
+    /// Checks the attestation signature over all entries.
+    ///
+    /// A `true` result only proves the entries were signed by the holder of
+    /// the `ask` behind [`Self::signer`]; the auditor must separately compare
+    /// that key against the custodian's registered full viewing key.
+    pub fn verify(&self) -> bool {
+        self.signer.verify(
+            &Self::digest(&self.signer, &self.entries),
+            &self.sig,
+            SPENDING_KEY_GENERATOR,
+        )
+    }
+
+    fn digest(signer: &PublicKey, entries: &[AttestedAddress]) -> [u8; 32] {
+        let mut bytes = vec![];
+        signer.write(&mut bytes).unwrap();
+        Vector::write(&mut bytes, entries, |w, e| e.write(w)).unwrap();
+
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(
+            Blake2bParams::new()
+                .hash_length(32)
+                .personal(AUDIT_SIG_PERSONALIZATION)
+                .hash(&bytes)
+                .as_bytes(),
+        );
+        digest
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let signer = PublicKey::read(&mut reader)?;
+        let entries = Vector::read(&mut reader, |r| AttestedAddress::read(r))?;
+        let sig = Signature::read(&mut reader)?;
+        Ok(AddressAttestation {
+            signer,
+            entries,
+            sig,
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.signer.write(&mut writer)?;
+        Vector::write(&mut writer, &self.entries, |w, e| e.write(w))?;
+        self.sig.write(&mut writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use group::GroupEncoding;
+    use rand_core::OsRng;
+
+    use super::{AddressAttestation, AuditError};
+    use crate::zip32::{ChildIndex, DiversifierIndex, ExtendedSpendingKey};
+
+    #[test]
+    fn attestation_roundtrip_and_verification() {
+        let seed = [42u8; 32];
+        let master = ExtendedSpendingKey::master(&seed);
+
+        let path = vec![ChildIndex::Hardened(1), ChildIndex::NonHardened(3)];
+        let xsk = ExtendedSpendingKey::from_path(&master, &path);
+        let (j, expected) = xsk.default_address();
+
+        let attestation =
+            AddressAttestation::create(&seed, &[(path.clone(), j)], &mut OsRng).unwrap();
+
+        assert_eq!(attestation.entries().len(), 1);
+        assert_eq!(attestation.entries()[0].path, path);
+        assert_eq!(attestation.entries()[0].address, expected);
+        assert!(attestation.verify());
+
+        // The signer must match the ak of the master full viewing key.
+        let mut signer = vec![];
+        attestation.signer().write(&mut signer).unwrap();
+        #[allow(deprecated)]
+        let ak = master.to_extended_full_viewing_key().fvk.vk.ak;
+        assert_eq!(signer, ak.to_bytes());
+
+        // Serialization roundtrips and tampering breaks verification.
+        let mut bytes = vec![];
+        attestation.write(&mut bytes).unwrap();
+        let decoded = AddressAttestation::read(&bytes[..]).unwrap();
+        assert!(decoded.verify());
+
+        let mut tampered = decoded;
+        tampered.entries[0].diversifier_index = DiversifierIndex([0xff; 11]);
+        assert!(!tampered.verify());
+    }
+
+    #[test]
+    fn rejects_invalid_diversifier_index() {
+        let seed = [42u8; 32];
+        let master = ExtendedSpendingKey::master(&seed);
+        let (valid, _) = master.default_address();
+
+        // Find an index that does not map to a valid diversifier.
+        let mut invalid = None;
+        for i in 0u8..=255 {
+            let j = DiversifierIndex([i, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            if master
+                .to_diversifiable_full_viewing_key()
+                .address(j)
+                .is_none()
+            {
+                invalid = Some(j);
+                break;
+            }
+        }
+        let invalid = invalid.expect("roughly half of all indices are invalid");
+
+        assert!(AddressAttestation::create(&seed, &[(vec![], valid)], &mut OsRng).is_ok());
+        assert_eq!(
+            AddressAttestation::create(&seed, &[(vec![], invalid)], &mut OsRng).unwrap_err(),
+            AuditError::InvalidDiversifierIndex
+        );
+    }
+}
@@ -5,13 +5,15 @@
 //! [section 4.2.2]: https://zips.z.cash/protocol/protocol.pdf#saplingkeycomponents
 
 use super::{
-    ChainCode, ChildIndex, Diversifier, DiversifierIndex, NullifierDerivingKey, PaymentAddress,
-    Scope, ViewingKey,
+    AccountId, ChainCode, ChildIndex, Diversifier, DiversifierIndex, KeyPath, NullifierDerivingKey,
+    PaymentAddress, Scope, ViewingKey,
 };
 use crate::{
     constants::{PROOF_GENERATION_KEY_GENERATOR, SPENDING_KEY_GENERATOR},
+    errors,
     keys::{prf_expand, prf_expand_vec},
     sapling::keys::{DecodingError, ExpandedSpendingKey, FullViewingKey, OutgoingViewingKey},
+    sapling::note_encryption::PreparedIncomingViewingKey,
     sapling::{redjubjub::PrivateKey, ProofGenerationKey, SaplingIvk},
 };
 use aes::Aes256;
@@ -121,7 +123,8 @@ pub fn sapling_derive_internal_fvk(
 }
 
 /// A Sapling full viewing key fingerprint
-struct FvkFingerprint([u8; 32]);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FvkFingerprint([u8; 32]);
 
 impl From<&FullViewingKey> for FvkFingerprint {
     fn from(fvk: &FullViewingKey) -> Self {
@@ -137,11 +140,24 @@ impl From<&FullViewingKey> for FvkFingerprint {
 }
 
 impl FvkFingerprint {
-    fn tag(&self) -> FvkTag {
+    /// Returns the tag that child keys derived from the fingerprinted key
+    /// carry as their parent tag
+    pub fn tag(&self) -> FvkTag {
         let mut tag = [0u8; 4];
         tag.copy_from_slice(&self.0[..4]);
         FvkTag(tag)
     }
+
+    /// Returns the byte representation of the fingerprint
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for FvkFingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", hex::encode(self.0))
+    }
 }
 
 /// A Sapling full viewing key tag
@@ -149,18 +165,49 @@ impl FvkFingerprint {
 #[derive(
     Clone, Copy, Debug, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize, BorshSchema,
 )]
-struct FvkTag([u8; 4]);
+pub struct FvkTag([u8; 4]);
 
 impl FvkTag {
     fn master() -> Self {
         FvkTag([0u8; 4])
     }
 
-    fn as_bytes(&self) -> &[u8; 4] {
+    /// Returns the byte representation of the tag
+    pub fn as_bytes(&self) -> &[u8; 4] {
         &self.0
     }
 }
 
+impl std::fmt::Display for FvkTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl FullViewingKey {
+    /// Returns the ZIP 32 fingerprint of this full viewing key. Its leading
+    /// four bytes form the [`FvkTag`] recorded in keys derived from this one,
+    /// so fingerprints can be used to label accounts and to verify claimed
+    /// parent-child relationships between imported keys.
+    pub fn fingerprint(&self) -> FvkFingerprint {
+        FvkFingerprint::from(self)
+    }
+}
+
+/// The ZIP 32 derivation metadata common to extended spending keys and
+/// extended full viewing keys: where in the tree a key sits and which parent
+/// it was derived from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DerivationInfo {
+    /// The derivation depth below the master key
+    pub depth: u8,
+    /// The tag of the parent full viewing key, or the all-zero tag for a
+    /// master key
+    pub parent_fvk_tag: FvkTag,
+    /// The index at which this key was derived from its parent
+    pub child_index: ChildIndex,
+}
+
 /// A key used to derive diversifiers for a particular child key
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
@@ -304,6 +351,21 @@ impl ExtendedSpendingKey {
         }
     }
 
+    /// Derives the master key from the given [ZIP 339] (BIP-39) mnemonic
+    /// phrase and passphrase, validating the phrase's checksum and applying
+    /// the standard PBKDF2 seed stretching.
+    ///
+    /// Prefer this over [`FromStr`], which hashes the decoded hex string
+    /// directly as the seed with no stretching.
+    ///
+    /// [ZIP 339]: https://zips.z.cash/zip-0339
+    #[cfg(feature = "bip39")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bip39")))]
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self, crate::zip339::Error> {
+        let mnemonic = <crate::zip339::Mnemonic>::from_phrase(phrase)?;
+        Ok(Self::master(&mnemonic.to_seed(passphrase)))
+    }
+
     /// Decodes the extended spending key from its serialized representation as defined in
     /// [ZIP 32](https://zips.z.cash/zip-0032)
     pub fn from_bytes(b: &[u8]) -> Result<Self, DecodingError> {
@@ -383,6 +445,36 @@ impl ExtendedSpendingKey {
         writer.write_all(&self.to_bytes())
     }
 
+    /// Returns the tag of the full viewing key this key was derived from,
+    /// or the all-zero tag if this is a master key.
+    pub fn parent_fvk_tag(&self) -> FvkTag {
+        self.parent_fvk_tag
+    }
+
+    /// Returns the derivation depth of this key below the master key.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Returns the index at which this key was derived from its parent.
+    pub fn child_index(&self) -> ChildIndex {
+        self.child_index
+    }
+
+    /// Returns the chain code of this key.
+    pub fn chain_code(&self) -> ChainCode {
+        self.chain_code
+    }
+
+    /// Returns the derivation metadata of this key.
+    pub fn derivation_info(&self) -> DerivationInfo {
+        DerivationInfo {
+            depth: self.depth,
+            parent_fvk_tag: self.parent_fvk_tag,
+            child_index: self.child_index,
+        }
+    }
+
     /// Returns the child key corresponding to the path derived from the master key
     pub fn from_path(master: &ExtendedSpendingKey, path: &[ChildIndex]) -> Self {
         let mut xsk = *master;
@@ -392,6 +484,26 @@ impl ExtendedSpendingKey {
         xsk
     }
 
+    /// Returns the child key at the given derivation path below this key
+    #[must_use]
+    pub fn derive_path(&self, path: &KeyPath) -> Self {
+        Self::from_path(self, &path.0)
+    }
+
+    /// Derives the account-level spending key at the hardened-only path
+    /// `m/32'/coin_type'/account'` from the given seed, per the ZIP 32
+    /// Sapling key path convention.
+    pub fn from_seed_and_account(seed: &[u8], coin_type: u32, account: AccountId) -> Self {
+        Self::from_path(
+            &Self::master(seed),
+            &[
+                ChildIndex::Hardened(32),
+                ChildIndex::Hardened(coin_type),
+                ChildIndex::Hardened(account.0),
+            ],
+        )
+    }
+
     #[must_use]
     pub fn derive_child(&self, i: ChildIndex) -> Self {
         let fvk = FullViewingKey::from_expanded_spending_key(&self.expsk);
@@ -476,6 +588,32 @@ impl ExtendedSpendingKey {
         }
     }
 
+    /// Returns the outgoing viewing key for the external scope.
+    pub fn external_ovk(&self) -> OutgoingViewingKey {
+        self.expsk.ovk
+    }
+
+    /// Returns the outgoing viewing key for the internal scope, as used to
+    /// encrypt change outputs back to the wallet.
+    pub fn internal_ovk(&self) -> OutgoingViewingKey {
+        self.derive_internal().expsk.ovk
+    }
+
+    /// Returns the incoming viewing key for the external scope.
+    pub fn external_ivk(&self) -> SaplingIvk {
+        FullViewingKey::from_expanded_spending_key(&self.expsk)
+            .vk
+            .ivk()
+    }
+
+    /// Returns the incoming viewing key for the internal scope, as used to
+    /// decrypt change outputs sent back to the wallet.
+    pub fn internal_ivk(&self) -> SaplingIvk {
+        FullViewingKey::from_expanded_spending_key(&self.derive_internal().expsk)
+            .vk
+            .ivk()
+    }
+
     #[deprecated(note = "Use `to_diversifiable_full_viewing_key` instead.")]
     pub fn to_extended_full_viewing_key(&self) -> ExtendedFullViewingKey {
         ExtendedFullViewingKey {
@@ -609,6 +747,43 @@ impl Ord for ExtendedFullViewingKey {
 }
 
 impl ExtendedFullViewingKey {
+    /// Decodes the extended full viewing key from its serialized representation
+    /// as defined in [ZIP 32](https://zips.z.cash/zip-0032)
+    pub fn from_bytes(b: &[u8]) -> Result<Self, DecodingError> {
+        if b.len() != 169 {
+            return Err(DecodingError::LengthInvalid {
+                expected: 169,
+                actual: b.len(),
+            });
+        }
+
+        let depth = b[0];
+
+        let mut parent_fvk_tag = FvkTag([0; 4]);
+        parent_fvk_tag.0[..].copy_from_slice(&b[1..5]);
+
+        let mut ci_bytes = [0u8; 4];
+        ci_bytes[..].copy_from_slice(&b[5..9]);
+        let child_index = ChildIndex::from_index(u32::from_le_bytes(ci_bytes));
+
+        let mut chain_code = ChainCode([0u8; 32]);
+        chain_code.0[..].copy_from_slice(&b[9..41]);
+
+        let fvk = FullViewingKey::read(&b[41..137]).map_err(|_| DecodingError::InvalidFvk)?;
+
+        let mut dk = DiversifierKey([0u8; 32]);
+        dk.0[..].copy_from_slice(&b[137..169]);
+
+        Ok(ExtendedFullViewingKey {
+            depth,
+            parent_fvk_tag,
+            child_index,
+            chain_code,
+            fvk,
+            dk,
+        })
+    }
+
     pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
         let depth = reader.read_u8()?;
         let mut tag = [0; 4];
@@ -630,20 +805,56 @@ impl ExtendedFullViewingKey {
         })
     }
 
+    /// Encodes the extended full viewing key to its serialized representation
+    /// as defined in [ZIP 32](https://zips.z.cash/zip-0032)
+    pub fn to_bytes(&self) -> [u8; 169] {
+        let mut result = [0u8; 169];
+        result[0] = self.depth;
+        result[1..5].copy_from_slice(&self.parent_fvk_tag.as_bytes()[..]);
+        result[5..9].copy_from_slice(&self.child_index.value().to_le_bytes()[..]);
+        result[9..41].copy_from_slice(&self.chain_code.as_bytes()[..]);
+        result[41..137].copy_from_slice(&self.fvk.to_bytes()[..]);
+        result[137..169].copy_from_slice(&self.dk.as_bytes()[..]);
+        result
+    }
+
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_u8(self.depth)?;
-        writer.write_all(&self.parent_fvk_tag.0)?;
-        writer.write_u32::<LittleEndian>(self.child_index.value())?;
-        writer.write_all(&self.chain_code.0)?;
-        writer.write_all(&self.fvk.to_bytes())?;
-        writer.write_all(&self.dk.0)?;
+        writer.write_all(&self.to_bytes())
+    }
 
-        Ok(())
+    /// Returns the tag of the full viewing key this key was derived from,
+    /// or the all-zero tag if this is a master key.
+    pub fn parent_fvk_tag(&self) -> FvkTag {
+        self.parent_fvk_tag
+    }
+
+    /// Returns the derivation depth of this key below the master key.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Returns the index at which this key was derived from its parent.
+    pub fn child_index(&self) -> ChildIndex {
+        self.child_index
+    }
+
+    /// Returns the chain code of this key.
+    pub fn chain_code(&self) -> ChainCode {
+        self.chain_code
+    }
+
+    /// Returns the derivation metadata of this key.
+    pub fn derivation_info(&self) -> DerivationInfo {
+        DerivationInfo {
+            depth: self.depth,
+            parent_fvk_tag: self.parent_fvk_tag,
+            child_index: self.child_index,
+        }
     }
 
-    pub fn derive_child(&self, i: ChildIndex) -> Result<Self, ()> {
+    pub fn derive_child(&self, i: ChildIndex) -> Result<Self, errors::Error> {
         let tmp = match i {
-            ChildIndex::Hardened(_) => return Err(()),
+            ChildIndex::Hardened(_) => return Err(errors::Error::HardenedChildIndex),
             ChildIndex::NonHardened(i) => {
                 let mut le_i = [0; 4];
                 LittleEndian::write_u32(&mut le_i, i);
@@ -726,6 +937,92 @@ impl ExtendedFullViewingKey {
             dk: self.dk,
         }
     }
+
+    /// Derives the incoming viewing keys and diversifier keys of the
+    /// non-hardened children with indices `start..start + count`, for handing
+    /// to per-sub-account scanners.
+    ///
+    /// Returns [`errors::Error::HardenedChildIndex`] if the range extends
+    /// into the hardened index space.
+    pub fn export_sub_account_ivks(
+        &self,
+        start: u32,
+        count: u32,
+    ) -> Result<Vec<SubAccountIvk>, errors::Error> {
+        let end = start
+            .checked_add(count)
+            .ok_or(errors::Error::HardenedChildIndex)?;
+        if end > (1 << 31) {
+            return Err(errors::Error::HardenedChildIndex);
+        }
+        (start..end)
+            .map(|i| {
+                let child = self.derive_child(ChildIndex::NonHardened(i))?;
+                Ok(SubAccountIvk {
+                    child_index: i,
+                    ivk: child.fvk.vk.ivk(),
+                    dk: child.dk,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The incoming viewing key and diversifier key of a non-hardened child of an
+/// [`ExtendedFullViewingKey`], as exported by
+/// [`ExtendedFullViewingKey::export_sub_account_ivks`].
+///
+/// This is the minimal capability needed to detect a sub-account's incoming
+/// notes and derive its payment addresses; it cannot view outgoing
+/// transactions or spend.
+#[derive(Clone, Debug)]
+pub struct SubAccountIvk {
+    /// The non-hardened child index this key was derived at.
+    pub child_index: u32,
+    /// The child's incoming viewing key.
+    pub ivk: SaplingIvk,
+    /// The child's diversifier key.
+    pub dk: DiversifierKey,
+}
+
+impl SubAccountIvk {
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let child_index = reader.read_u32::<LittleEndian>()?;
+        let mut ivk = [0u8; 32];
+        reader.read_exact(&mut ivk)?;
+        let ivk = Option::from(jubjub::Fr::from_repr(ivk))
+            .map(SaplingIvk)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "ivk is not a valid scalar"))?;
+        let mut dk = [0u8; 32];
+        reader.read_exact(&mut dk)?;
+
+        Ok(SubAccountIvk {
+            child_index,
+            ivk,
+            dk: DiversifierKey(dk),
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_u32::<LittleEndian>(self.child_index)?;
+        writer.write_all(&self.ivk.to_repr())?;
+        writer.write_all(&self.dk.0)?;
+
+        Ok(())
+    }
+
+    /// Prepares the incoming viewing key for trial decryption in a scanner.
+    pub fn prepare(&self) -> PreparedIncomingViewingKey {
+        PreparedIncomingViewingKey::new(&self.ivk)
+    }
+
+    /// Attempts to produce the sub-account's payment address at the given
+    /// diversifier index.
+    pub fn address(&self, j: DiversifierIndex) -> Option<PaymentAddress> {
+        self.dk
+            .diversifier(j)
+            .and_then(|d| self.ivk.to_payment_address(d))
+    }
 }
 
 /// A Sapling key that provides the capability to view incoming and outgoing transactions.
@@ -902,6 +1199,9 @@ impl DiversifiableFullViewingKey {
 
 impl FromStr for ExtendedSpendingKey {
     type Err = std::io::Error;
+    /// Hashes the hex-decoded bytes directly as the master seed, with no
+    /// stretching. For seed phrases, use
+    /// [`ExtendedSpendingKey::from_mnemonic`] (`bip39` feature) instead.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let vec = hex::decode(s).map_err(|x| Error::new(ErrorKind::InvalidData, x))?;
         Ok(ExtendedSpendingKey::master(vec.as_ref()))
@@ -942,6 +1242,10 @@ pub trait ExtendedKey {
     /// mathematical relation between nk and nsk nor ak and ask in this
     /// collection.
     fn to_spending_key(&self) -> Option<ExtendedSpendingKey>;
+
+    /// Derive a throwaway instance of this key collection from the given seed.
+    /// Used to construct dummy spend descriptions when padding transactions.
+    fn from_dummy_seed(seed: &[u8; 32]) -> Self;
 }
 
 /// Represent an extended full viewing key as a collection of keys.
@@ -962,6 +1266,11 @@ impl ExtendedKey for ExtendedFullViewingKey {
     fn to_spending_key(&self) -> Option<ExtendedSpendingKey> {
         None
     }
+
+    /// Derive a throwaway extended full viewing key from the given seed
+    fn from_dummy_seed(seed: &[u8; 32]) -> Self {
+        (&ExtendedSpendingKey::master(seed)).into()
+    }
 }
 
 /// Represents an extended spending key as a collection of keys.
@@ -982,6 +1291,11 @@ impl ExtendedKey for ExtendedSpendingKey {
     fn to_spending_key(&self) -> Option<ExtendedSpendingKey> {
         Some(*self)
     }
+
+    /// Derive a throwaway extended spending key from the given seed
+    fn from_dummy_seed(seed: &[u8; 32]) -> Self {
+        ExtendedSpendingKey::master(seed)
+    }
 }
 
 /// An extended full viewing key bundled with partial authorizations
@@ -1070,6 +1384,12 @@ impl ExtendedKey for PseudoExtendedKey {
                 },
             })
     }
+
+    /// Derive a throwaway pseudo extended key, with both authorizations known,
+    /// from the given seed
+    fn from_dummy_seed(seed: &[u8; 32]) -> Self {
+        ExtendedSpendingKey::master(seed).into()
+    }
 }
 
 impl From<ExtendedSpendingKey> for PseudoExtendedKey {
@@ -1164,6 +1484,97 @@ mod tests {
     use ff::PrimeField;
     use group::GroupEncoding;
 
+    #[test]
+    #[allow(deprecated)]
+    fn zip32_failures_surface_typed_errors() {
+        let seed = [0; 32];
+        let xsk_m = ExtendedSpendingKey::master(&seed);
+        let xfvk_m = xsk_m.to_extended_full_viewing_key();
+
+        // A viewing key cannot derive hardened children
+        assert_eq!(
+            xfvk_m.derive_child(ChildIndex::Hardened(0)).err(),
+            Some(errors::Error::HardenedChildIndex)
+        );
+        assert_eq!(
+            xfvk_m.export_sub_account_ivks(1 << 31, 1).err(),
+            Some(errors::Error::HardenedChildIndex)
+        );
+
+        // The last diversifier index cannot be incremented
+        let mut j = DiversifierIndex([0xff; 11]);
+        assert_eq!(
+            j.increment().err(),
+            Some(errors::Error::DiversifierSpaceExhausted)
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn export_sub_account_ivks() {
+        let seed = [0; 32];
+        let xsk_m = ExtendedSpendingKey::master(&seed);
+        let xfvk_m = xsk_m.to_extended_full_viewing_key();
+
+        let exported = xfvk_m.export_sub_account_ivks(3, 4).expect("non-hardened");
+        assert_eq!(exported.len(), 4);
+
+        for (offset, sub) in exported.iter().enumerate() {
+            let i = 3 + offset as u32;
+            assert_eq!(sub.child_index, i);
+
+            // The exported keys must match direct child derivation.
+            let child = xfvk_m.derive_child(ChildIndex::NonHardened(i)).unwrap();
+            assert_eq!(sub.ivk.to_repr(), child.fvk.vk.ivk().to_repr());
+            assert_eq!(sub.dk, child.dk);
+
+            // The import path round-trips and derives the child's addresses.
+            let mut encoded = vec![];
+            sub.write(&mut encoded).unwrap();
+            let decoded = SubAccountIvk::read(&encoded[..]).unwrap();
+            assert_eq!(decoded.child_index, sub.child_index);
+            assert_eq!(decoded.ivk.to_repr(), sub.ivk.to_repr());
+            assert_eq!(decoded.dk, sub.dk);
+
+            let (j, addr) = child.default_address();
+            assert_eq!(decoded.address(j), Some(addr));
+        }
+
+        // A range crossing into the hardened index space is rejected.
+        assert!(xfvk_m.export_sub_account_ivks((1 << 31) - 1, 2).is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn xfvk_fixed_size_byte_encoding_round_trip() {
+        let seed = [0; 32];
+        let xsk_m = ExtendedSpendingKey::master(&seed);
+        let xfvk_m = xsk_m.to_extended_full_viewing_key();
+
+        let bytes = xfvk_m.to_bytes();
+        let mut written = vec![];
+        xfvk_m.write(&mut written).unwrap();
+        assert_eq!(&bytes[..], &written[..]);
+
+        let decoded = ExtendedFullViewingKey::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, xfvk_m);
+
+        assert_eq!(
+            ExtendedFullViewingKey::from_bytes(&bytes[..168]),
+            Err(DecodingError::LengthInvalid {
+                expected: 169,
+                actual: 168
+            })
+        );
+
+        let mut corrupt = bytes;
+        corrupt[41..137].copy_from_slice(&[0xff; 96]);
+        assert_eq!(
+            ExtendedFullViewingKey::from_bytes(&corrupt),
+            Err(DecodingError::InvalidFvk)
+        );
+    }
+
     #[test]
     #[allow(deprecated)]
     fn derive_nonhardened_child() {
@@ -1222,6 +1633,103 @@ mod tests {
             ),
             xsk_5h_7
         );
+
+        // String-notation paths derive the same keys
+        let key_path: KeyPath = "m/5'/7".parse().unwrap();
+        assert_eq!(xsk_m.derive_path(&key_path), xsk_5h_7);
+    }
+
+    #[cfg(feature = "bip39")]
+    #[test]
+    fn mnemonic_master_key() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon \
+                      abandon abandon abandon about";
+
+        // The same phrase and passphrase always derive the same key, and the
+        // passphrase is part of the seed stretching
+        let xsk = ExtendedSpendingKey::from_mnemonic(phrase, "").unwrap();
+        assert_eq!(ExtendedSpendingKey::from_mnemonic(phrase, "").unwrap(), xsk);
+        assert_ne!(
+            ExtendedSpendingKey::from_mnemonic(phrase, "TREZOR").unwrap(),
+            xsk
+        );
+
+        // Phrases with a bad checksum are rejected
+        let swapped = "abandon about abandon abandon abandon abandon abandon abandon \
+                       abandon abandon abandon abandon";
+        assert!(ExtendedSpendingKey::from_mnemonic(swapped, "").is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn derivation_info_accessors() {
+        let xsk_m = ExtendedSpendingKey::master(&[0; 32]);
+        let xsk_5h = xsk_m.derive_child(ChildIndex::Hardened(5));
+
+        assert_eq!(xsk_5h.depth(), 1);
+        assert_eq!(xsk_5h.child_index(), ChildIndex::Hardened(5));
+
+        let info = xsk_5h.derivation_info();
+        assert_eq!(info.depth, 1);
+        assert_eq!(info.child_index, ChildIndex::Hardened(5));
+        assert_eq!(info.parent_fvk_tag, xsk_5h.parent_fvk_tag());
+
+        // Both key types agree on the derivation metadata
+        let xfvk_5h = xsk_5h.to_extended_full_viewing_key();
+        assert_eq!(xfvk_5h.derivation_info(), info);
+        assert_eq!(xfvk_5h.chain_code(), xsk_5h.chain_code());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn fingerprint_tags_link_parent_and_child() {
+        let seed = [0; 32];
+        let xsk_m = ExtendedSpendingKey::master(&seed);
+        let xfvk_m = xsk_m.to_extended_full_viewing_key();
+        let xsk_5h = xsk_m.derive_child(ChildIndex::Hardened(5));
+
+        // A child's parent tag is the leading four bytes of the parent's
+        // fingerprint; the master key carries the all-zero tag
+        assert_eq!(xsk_m.parent_fvk_tag(), FvkTag::master());
+        assert_eq!(xsk_5h.parent_fvk_tag(), xfvk_m.fvk.fingerprint().tag(),);
+        assert_eq!(
+            xsk_5h.to_extended_full_viewing_key().parent_fvk_tag(),
+            xfvk_m.fvk.fingerprint().tag(),
+        );
+
+        // Fingerprints and tags display as hex
+        let fp = xfvk_m.fvk.fingerprint();
+        assert_eq!(fp.to_string(), hex::encode(fp.as_bytes()));
+        assert_eq!(fp.tag().to_string(), hex::encode(fp.tag().as_bytes()));
+    }
+
+    #[test]
+    fn seed_and_account_path() {
+        let seed = [0; 32];
+        let xsk_m = ExtendedSpendingKey::master(&seed);
+
+        // m/32'/1'/7', with every level hardened
+        assert_eq!(
+            ExtendedSpendingKey::from_seed_and_account(&seed, 1, AccountId(7)),
+            ExtendedSpendingKey::from_path(
+                &xsk_m,
+                &[
+                    ChildIndex::Hardened(32),
+                    ChildIndex::Hardened(1),
+                    ChildIndex::Hardened(7),
+                ],
+            )
+        );
+
+        // Distinct coin types and accounts yield distinct keys
+        assert_ne!(
+            ExtendedSpendingKey::from_seed_and_account(&seed, 1, AccountId(7)),
+            ExtendedSpendingKey::from_seed_and_account(&seed, 2, AccountId(7))
+        );
+        assert_ne!(
+            ExtendedSpendingKey::from_seed_and_account(&seed, 1, AccountId(7)),
+            ExtendedSpendingKey::from_seed_and_account(&seed, 1, AccountId(8))
+        );
     }
 
     #[test]
@@ -1319,6 +1827,70 @@ mod tests {
         assert_eq!(dfvk_parsed.to_bytes(), dfvk_bytes);
     }
 
+    #[test]
+    fn scoped_viewing_key_accessors() {
+        let xsk = ExtendedSpendingKey::master(&[0u8; 32]);
+        let dfvk = xsk.to_diversifiable_full_viewing_key();
+
+        // The accessors agree with the scope-based DFVK API.
+        assert_eq!(xsk.external_ovk(), dfvk.to_ovk(Scope::External));
+        assert_eq!(xsk.internal_ovk(), dfvk.to_ovk(Scope::Internal));
+        assert_eq!(
+            xsk.external_ivk().to_repr(),
+            dfvk.to_ivk(Scope::External).to_repr()
+        );
+        assert_eq!(
+            xsk.internal_ivk().to_repr(),
+            dfvk.to_ivk(Scope::Internal).to_repr()
+        );
+
+        // The internal scope yields distinct keys.
+        assert_ne!(xsk.external_ovk(), xsk.internal_ovk());
+        assert_ne!(xsk.external_ivk().to_repr(), xsk.internal_ivk().to_repr());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn delegated_proving_key_flow() {
+        let xsk = ExtendedSpendingKey::master(&[0u8; 32]);
+
+        // The proof generation key extracted from a spending key agrees with
+        // the key's viewing material.
+        let pgk = xsk.to_proof_generation_key().unwrap();
+        let vk = pgk.to_viewing_key();
+        assert_eq!(vk.ak, xsk.expsk.proof_generation_key().ak);
+        assert_eq!(vk.nk, xsk.to_extended_full_viewing_key().fvk.vk.nk);
+
+        // It serializes safely: the round trip preserves it, and out-of-field
+        // nsk bytes are rejected on read.
+        let bytes = borsh::to_vec(&pgk).unwrap();
+        let restored = ProofGenerationKey::deserialize(&mut &bytes[..]).unwrap();
+        assert_eq!(restored.ak, pgk.ak);
+        assert_eq!(restored.nsk, pgk.nsk);
+        let mut corrupted = bytes;
+        corrupted[32..].copy_from_slice(&[0xff; 32]);
+        assert!(ProofGenerationKey::deserialize(&mut &corrupted[..]).is_err());
+
+        // A proving machine starts from the viewing key alone and is handed
+        // only the proof generation key; it can prove but never sign.
+        let mut prover_key = PseudoExtendedKey::from(xsk.to_extended_full_viewing_key());
+        assert!(prover_key.to_proof_generation_key().is_none());
+
+        // A proof generation key for a different spending key is refused.
+        let other = ExtendedSpendingKey::master(&[1u8; 32])
+            .to_proof_generation_key()
+            .unwrap();
+        assert_eq!(prover_key.augment_proof_generation_key(other), Err(()));
+
+        prover_key
+            .augment_proof_generation_key(pgk.clone())
+            .unwrap();
+        let delegated = prover_key.to_proof_generation_key().unwrap();
+        assert_eq!(delegated.ak, pgk.ak);
+        assert_eq!(delegated.nsk, pgk.nsk);
+        assert_eq!(prover_key.to_spending_key(), None);
+    }
+
     #[test]
     fn address() {
         let seed = [0u8; 32];
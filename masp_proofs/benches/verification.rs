@@ -0,0 +1,161 @@
+#[macro_use]
+extern crate criterion;
+
+use bellman::{
+    gadgets::multipack,
+    groth16::{batch, generate_random_parameters, prepare_verifying_key},
+};
+use bls12_381::Bls12;
+use criterion::Criterion;
+use group::{ff::Field, Curve, GroupEncoding};
+use masp_primitives::{
+    asset_type::AssetType,
+    constants::SPENDING_KEY_GENERATOR,
+    merkle_tree::{CommitmentTree, IncrementalWitness},
+    sapling::{redjubjub::PrivateKey, Diversifier, Node, ProofGenerationKey, Rseed},
+};
+use masp_proofs::circuit::sapling::Spend;
+use masp_proofs::sapling::{SaplingProvingContext, SaplingVerificationContext};
+use rand_core::{OsRng, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+const TREE_DEPTH: usize = 32;
+const BATCH_SIZE: usize = 10;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let mut rng = XorShiftRng::from_seed([
+        0x59, 0x62, 0xbe, 0x3d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc,
+        0xe5,
+    ]);
+
+    let groth_params = generate_random_parameters::<Bls12, _, _>(
+        Spend {
+            value_commitment: None,
+            proof_generation_key: None,
+            payment_address: None,
+            commitment_randomness: None,
+            ar: None,
+            auth_path: vec![None; TREE_DEPTH],
+            anchor: None,
+        },
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = prepare_verifying_key(&groth_params.vk);
+
+    // Build a genuine witness so that the proof actually verifies.
+    let asset_type = AssetType::new(b"benchmark").unwrap();
+    let value = 1;
+
+    let ask = jubjub::Fr::random(&mut rng);
+    let proof_generation_key = ProofGenerationKey {
+        ak: SPENDING_KEY_GENERATOR * ask,
+        nsk: jubjub::Fr::random(&mut rng),
+    };
+
+    let viewing_key = proof_generation_key.to_viewing_key();
+
+    let payment_address;
+
+    loop {
+        let diversifier = {
+            let mut d = [0; 11];
+            rng.fill_bytes(&mut d);
+            Diversifier(d)
+        };
+
+        if let Some(p) = viewing_key.to_payment_address(diversifier) {
+            payment_address = p;
+            break;
+        }
+    }
+
+    let rseed = Rseed::BeforeZip212(jubjub::Fr::random(&mut rng));
+    let note = payment_address
+        .create_note(asset_type, value, rseed)
+        .unwrap();
+
+    let mut tree = CommitmentTree::<Node>::empty();
+    tree.append(Node::from_scalar(note.cmu())).unwrap();
+    let merkle_path = IncrementalWitness::from_tree(&tree).path().unwrap();
+    let anchor = tree.root().into();
+    let nullifier = note.nf(&viewing_key.nk, merkle_path.position);
+
+    let ar = jubjub::Fr::random(&mut rng);
+    let (proof, cv, rk) = SaplingProvingContext::new()
+        .spend_proof(
+            &mut OsRng,
+            proof_generation_key,
+            *payment_address.diversifier(),
+            rseed,
+            ar,
+            asset_type,
+            value,
+            anchor,
+            merkle_path,
+            &groth_params,
+            &pvk,
+            jubjub::Fr::random(&mut rng),
+        )
+        .expect("proving should not fail");
+
+    let sighash = [42u8; 32];
+    let mut data_to_be_signed = [0u8; 64];
+    data_to_be_signed[0..32].copy_from_slice(&rk.0.to_bytes());
+    data_to_be_signed[32..64].copy_from_slice(&sighash);
+    let spend_auth_sig =
+        PrivateKey(ask)
+            .randomize(ar)
+            .sign(&data_to_be_signed, &mut rng, SPENDING_KEY_GENERATOR);
+
+    c.bench_function("spend verification", |b| {
+        b.iter(|| {
+            let mut ctx = SaplingVerificationContext::new(true);
+            ctx.check_spend(
+                cv,
+                anchor,
+                &nullifier.0,
+                rk,
+                &sighash,
+                spend_auth_sig,
+                proof.clone(),
+                &pvk,
+            )
+        })
+    });
+
+    // The public inputs for the Groth16 proof, packed as the verifier does.
+    let mut public_input = [bls12_381::Scalar::zero(); 7];
+    {
+        let affine = rk.0.to_affine();
+        public_input[0] = affine.get_u();
+        public_input[1] = affine.get_v();
+    }
+    {
+        let affine = cv.to_affine();
+        public_input[2] = affine.get_u();
+        public_input[3] = affine.get_v();
+    }
+    public_input[4] = anchor;
+    {
+        let nullifier = multipack::compute_multipacking(&multipack::bytes_to_bits_le(&nullifier.0));
+        public_input[5] = nullifier[0];
+        public_input[6] = nullifier[1];
+    }
+
+    c.bench_function("spend batch verification", |b| {
+        b.iter(|| {
+            let mut validator = batch::Verifier::new();
+            for _ in 0..BATCH_SIZE {
+                validator.queue((proof.clone(), public_input.to_vec()));
+            }
+            validator.verify(OsRng, &groth_params.vk)
+        })
+    });
+}
+
+criterion_group!(
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = criterion_benchmark);
+criterion_main!(benches);
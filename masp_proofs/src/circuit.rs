@@ -2,5 +2,6 @@
 
 pub mod convert;
 pub mod ecc;
+pub mod metrics;
 pub mod pedersen_hash;
 pub mod sapling;
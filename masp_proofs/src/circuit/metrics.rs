@@ -0,0 +1,245 @@
+//! Synthesis metrics for the MASP circuits.
+//!
+//! The entry points in this module synthesize the Spend, Output, and Convert
+//! circuits into a metering constraint system and report their constraint
+//! counts, public input layouts, and per-gadget constraint breakdowns. The
+//! reports are deterministic, so auditors can diff the output between releases
+//! to pinpoint exactly which gadgets a circuit change touched.
+
+use std::collections::BTreeMap;
+
+use bellman::{Circuit, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable};
+use masp_primitives::ff::PrimeField;
+
+use super::convert::{Convert, TREE_DEPTH as CONVERT_TREE_DEPTH};
+use super::sapling::{Output, Spend, TREE_DEPTH};
+
+/// A report of the shape of a synthesized circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitMetrics {
+    /// The total number of constraints in the circuit.
+    pub constraints: usize,
+    /// The namespaced paths of the public inputs, in allocation order. The
+    /// first input is always the constant `ONE`.
+    pub inputs: Vec<String>,
+    /// The number of auxiliary (private) variables in the circuit.
+    pub aux: usize,
+    /// The number of constraints enforced under each top-level gadget
+    /// namespace. Constraints enforced outside any namespace are keyed by
+    /// their own annotation.
+    pub gadgets: BTreeMap<String, usize>,
+}
+
+/// A constraint system that records the shape of a circuit without computing
+/// an assignment. Like the parameter generator, it never invokes the witness
+/// closures, so circuits can be synthesized with all-`None` instances.
+struct MeteringConstraintSystem<Scalar: PrimeField> {
+    current_namespace: Vec<String>,
+    inputs: Vec<String>,
+    num_aux: usize,
+    num_constraints: usize,
+    gadgets: BTreeMap<String, usize>,
+    _marker: std::marker::PhantomData<Scalar>,
+}
+
+impl<Scalar: PrimeField> MeteringConstraintSystem<Scalar> {
+    fn new() -> Self {
+        MeteringConstraintSystem {
+            current_namespace: vec![],
+            inputs: vec!["ONE".into()],
+            num_aux: 0,
+            num_constraints: 0,
+            gadgets: BTreeMap::new(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    fn path(&self, annotation: &str) -> String {
+        let mut path = self.current_namespace.join("/");
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(annotation);
+        path
+    }
+}
+
+impl<Scalar: PrimeField> ConstraintSystem<Scalar> for MeteringConstraintSystem<Scalar> {
+    type Root = Self;
+
+    fn alloc<F, A, AR>(&mut self, _: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        // There is no assignment, so we don't even invoke the function for
+        // obtaining one.
+        let index = self.num_aux;
+        self.num_aux += 1;
+
+        Ok(Variable::new_unchecked(Index::Aux(index)))
+    }
+
+    fn alloc_input<F, A, AR>(&mut self, annotation: A, _: F) -> Result<Variable, SynthesisError>
+    where
+        F: FnOnce() -> Result<Scalar, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        let index = self.inputs.len();
+        let path = self.path(&annotation().into());
+        self.inputs.push(path);
+
+        Ok(Variable::new_unchecked(Index::Input(index)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, annotation: A, _: LA, _: LB, _: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LB: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+        LC: FnOnce(LinearCombination<Scalar>) -> LinearCombination<Scalar>,
+    {
+        self.num_constraints += 1;
+
+        let gadget = match self.current_namespace.first() {
+            Some(ns) => ns.clone(),
+            None => annotation().into(),
+        };
+        *self.gadgets.entry(gadget).or_insert(0) += 1;
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.current_namespace.push(name_fn().into());
+    }
+
+    fn pop_namespace(&mut self) {
+        self.current_namespace.pop();
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// Synthesizes the given circuit into a metering constraint system and
+/// reports its shape. Witness closures are never invoked, so all-`None`
+/// circuit instances can be measured.
+pub fn measure_circuit<Scalar, C>(circuit: C) -> Result<CircuitMetrics, SynthesisError>
+where
+    Scalar: PrimeField,
+    C: Circuit<Scalar>,
+{
+    let mut cs = MeteringConstraintSystem::new();
+    circuit.synthesize(&mut cs)?;
+
+    Ok(CircuitMetrics {
+        constraints: cs.num_constraints,
+        inputs: cs.inputs,
+        aux: cs.num_aux,
+        gadgets: cs.gadgets,
+    })
+}
+
+/// Reports the shape of the Spend circuit.
+pub fn spend_metrics() -> CircuitMetrics {
+    measure_circuit(Spend {
+        value_commitment: None,
+        proof_generation_key: None,
+        payment_address: None,
+        commitment_randomness: None,
+        ar: None,
+        auth_path: vec![None; TREE_DEPTH],
+        anchor: None,
+    })
+    .expect("Spend circuit synthesis is infallible without an assignment")
+}
+
+/// Reports the shape of the Output circuit.
+pub fn output_metrics() -> CircuitMetrics {
+    measure_circuit(Output {
+        value_commitment: None,
+        asset_identifier: vec![None; 256],
+        payment_address: None,
+        commitment_randomness: None,
+        esk: None,
+    })
+    .expect("Output circuit synthesis is infallible without an assignment")
+}
+
+/// Reports the shape of the Convert circuit.
+pub fn convert_metrics() -> CircuitMetrics {
+    measure_circuit(Convert {
+        value_commitment: None,
+        auth_path: vec![None; CONVERT_TREE_DEPTH],
+        anchor: None,
+    })
+    .expect("Convert circuit synthesis is infallible without an assignment")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert_metrics, output_metrics, spend_metrics};
+
+    #[test]
+    fn spend_circuit_shape() {
+        let metrics = spend_metrics();
+
+        assert_eq!(metrics.constraints, 100637);
+        assert_eq!(
+            metrics.inputs,
+            vec![
+                "ONE",
+                "rk/u/input variable",
+                "rk/v/input variable",
+                "value commitment/commitment point/u/input variable",
+                "value commitment/commitment point/v/input variable",
+                "anchor/input variable",
+                "pack nullifier/input 0",
+                "pack nullifier/input 1",
+            ]
+        );
+        assert_eq!(metrics.gadgets.values().sum::<usize>(), metrics.constraints);
+    }
+
+    #[test]
+    fn output_circuit_shape() {
+        let metrics = output_metrics();
+
+        assert_eq!(
+            metrics.inputs,
+            vec![
+                "ONE",
+                "value commitment/commitment point/u/input variable",
+                "value commitment/commitment point/v/input variable",
+                "epk/u/input variable",
+                "epk/v/input variable",
+                "commitment/input variable",
+            ]
+        );
+        assert_eq!(metrics.gadgets.values().sum::<usize>(), metrics.constraints);
+    }
+
+    #[test]
+    fn convert_circuit_shape() {
+        let metrics = convert_metrics();
+
+        assert_eq!(metrics.constraints, 47358);
+        assert_eq!(
+            metrics.inputs,
+            vec![
+                "ONE",
+                "value commitment/commitment point/u/input variable",
+                "value commitment/commitment point/v/input variable",
+                "anchor/input variable",
+            ]
+        );
+        assert_eq!(metrics.gadgets.values().sum::<usize>(), metrics.constraints);
+    }
+}
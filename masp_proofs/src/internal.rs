@@ -0,0 +1,44 @@
+//! Unstable access to verifier internals, for audit tooling.
+//!
+//! The functions here assemble the Groth16 statement for each MASP circuit —
+//! the public inputs that are fed into the pairing check (and hence the
+//! final exponentiation) when a proof is verified. They are exposed solely
+//! so that differential testing harnesses can compare this crate's input
+//! encoding against independent implementations; they are not needed to
+//! create or verify proofs, are not covered by any stability promise, and
+//! may change or disappear in any release.
+//!
+//! Requires the `internal-apis` feature. See also `masp_primitives`'
+//! `internal` module for the key and note derivation counterparts.
+
+use masp_primitives::sapling::redjubjub::PublicKey;
+
+use crate::sapling::verifier;
+
+/// Assembles the public inputs for a Spend proof, in the order the verifying
+/// key expects them: `rk`, `cv`, the anchor, then the multipacked nullifier.
+pub fn spend_public_inputs(
+    cv: &jubjub::ExtendedPoint,
+    anchor: bls12_381::Scalar,
+    nullifier: &[u8; 32],
+    rk: &PublicKey,
+) -> [bls12_381::Scalar; 7] {
+    verifier::spend_public_inputs(cv, anchor, nullifier, rk)
+}
+
+/// Assembles the public inputs for a Convert proof: `cv`, then the anchor.
+pub fn convert_public_inputs(
+    cv: &jubjub::ExtendedPoint,
+    anchor: bls12_381::Scalar,
+) -> [bls12_381::Scalar; 3] {
+    verifier::convert_public_inputs(cv, anchor)
+}
+
+/// Assembles the public inputs for an Output proof: `cv`, `epk`, then `cmu`.
+pub fn output_public_inputs(
+    cv: &jubjub::ExtendedPoint,
+    cmu: bls12_381::Scalar,
+    epk: &jubjub::ExtendedPoint,
+) -> [bls12_381::Scalar; 5] {
+    verifier::output_public_inputs(cv, cmu, epk)
+}
@@ -11,6 +11,7 @@
 
 use bellman::groth16::{prepare_verifying_key, Parameters, PreparedVerifyingKey};
 use bls12_381::Bls12;
+use std::fmt;
 use std::fs::File;
 use std::io::{self, BufReader};
 use std::path::Path;
@@ -28,11 +29,18 @@ use std::path::PathBuf;
 pub mod circuit;
 pub mod constants;
 pub mod hashreader;
+#[cfg(feature = "internal-apis")]
+#[cfg_attr(docsrs, doc(cfg(feature = "internal-apis")))]
+pub mod internal;
 pub mod sapling;
 
 #[cfg(feature = "embed-verifying-key")]
 pub mod params;
 
+#[cfg(feature = "params-gen")]
+#[cfg_attr(docsrs, doc(cfg(feature = "params-gen")))]
+pub mod params_gen;
+
 #[cfg(any(feature = "local-prover", feature = "bundled-prover"))]
 #[cfg_attr(
     docsrs,
@@ -262,6 +270,88 @@ pub struct MASPParameters {
     pub convert_vk: PreparedVerifyingKey<Bls12>,
 }
 
+/// An error encountered while loading or validating parameter files.
+#[derive(Debug)]
+pub enum ParameterError {
+    /// A parameter file could not be read.
+    Io {
+        /// The parameter file name.
+        name: &'static str,
+        /// The underlying I/O error.
+        error: io::Error,
+    },
+    /// A parameter file did not have the expected size.
+    FileSize {
+        /// The parameter file name.
+        name: &'static str,
+        /// The expected file size in bytes.
+        expected: u64,
+        /// The actual file size in bytes.
+        actual: u64,
+    },
+    /// A parameter file could not be deserialized.
+    Parse {
+        /// The parameter file name.
+        name: &'static str,
+        /// The underlying deserialization error.
+        error: io::Error,
+    },
+    /// A parameter file's BLAKE2b hash did not match the embedded digest,
+    /// indicating a corrupted or wrong-network parameter file.
+    HashMismatch {
+        /// The parameter file name.
+        name: &'static str,
+        /// The embedded expected BLAKE2b digest.
+        expected: &'static str,
+        /// The digest computed from the file.
+        actual: String,
+    },
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterError::Io { name, error } => {
+                write!(f, "{} failed reading: {}", name, error)
+            }
+            ParameterError::FileSize {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} failed validation:\n\
+                 expected: {} bytes,\n\
+                 actual:   {} bytes",
+                name, expected, actual,
+            ),
+            ParameterError::Parse { name, error } => {
+                write!(f, "{} failed to deserialize: {}", name, error)
+            }
+            ParameterError::HashMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} failed validation:\n\
+                 expected: {},\n\
+                 actual:   {}",
+                name, expected, actual,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParameterError::Io { error, .. } | ParameterError::Parse { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
 /// Load the specified parameters, checking the sizes and hashes of the files.
 ///
 /// Returns the loaded parameters.
@@ -314,82 +404,268 @@ pub fn load_parameters(
     )
 }
 
-/// Parse Bls12 keys from bytes as serialized by [`Parameters::write`].
-///
-/// This function will panic if it encounters unparseable data.
-pub fn parse_parameters<R: io::Read>(spend_fs: R, output_fs: R, convert_fs: R) -> MASPParameters {
-    let mut spend_fs = hashreader::HashReader::new(spend_fs);
-    let mut output_fs = hashreader::HashReader::new(output_fs);
-    let mut convert_fs = hashreader::HashReader::new(convert_fs);
-
-    // Deserialize params
-    let spend_params = Parameters::<Bls12>::read(&mut spend_fs, false)
-        .expect("couldn't deserialize MASP spend parameters file");
-    let output_params = Parameters::<Bls12>::read(&mut output_fs, false)
-        .expect("couldn't deserialize MASP output parameters file");
-    let convert_params = Parameters::<Bls12>::read(&mut convert_fs, false)
-        .expect("couldn't deserialize MASP convert parameters file");
+/// Load the specified parameters like [`load_parameters`], but return a typed
+/// [`ParameterError`] instead of panicking, so callers can catch corrupted or
+/// wrong-network parameter files before proving.
+pub fn try_load_parameters(
+    spend_path: &Path,
+    output_path: &Path,
+    convert_path: &Path,
+) -> Result<MASPParameters, ParameterError> {
+    let open = |path: &Path, expected_bytes: u64, name: &'static str| {
+        // Check the file size is correct before hashing large amounts of data.
+        let file_size = std::fs::metadata(path)
+            .map_err(|error| ParameterError::Io { name, error })?
+            .len();
+        if file_size != expected_bytes {
+            return Err(ParameterError::FileSize {
+                name,
+                expected: expected_bytes,
+                actual: file_size,
+            });
+        }
 
-    // There is extra stuff (the transcript) at the end of the parameter file which is
-    // used to verify the parameter validity, but we're not interested in that. We do
-    // want to read it, though, so that the BLAKE2b computed afterward is consistent
-    // with `b2sum` on the files.
-    let mut sink = io::sink();
+        let file = File::open(path).map_err(|error| ParameterError::Io { name, error })?;
+        Ok(BufReader::with_capacity(1024 * 1024, file))
+    };
 
-    // TODO: use the correct paths for Windows and macOS
-    //       use the actual file paths supplied by the caller
-    verify_hash(
-        spend_fs,
-        &mut sink,
-        MASP_SPEND_HASH,
-        MASP_SPEND_BYTES,
-        MASP_SPEND_NAME,
-        "a file",
+    try_parse_parameters(
+        open(spend_path, MASP_SPEND_BYTES, MASP_SPEND_NAME)?,
+        open(output_path, MASP_OUTPUT_BYTES, MASP_OUTPUT_NAME)?,
+        open(convert_path, MASP_CONVERT_BYTES, MASP_CONVERT_NAME)?,
     )
-    .expect(
-        "MASP spend parameter file is not correct, \
-         please clean your `~/.masp-params/` and re-run `fetch-params`.",
-    );
+}
 
-    verify_hash(
-        output_fs,
-        &mut sink,
-        MASP_OUTPUT_HASH,
-        MASP_OUTPUT_BYTES,
-        MASP_OUTPUT_NAME,
-        "a file",
-    )
-    .expect(
-        "MASP output parameter file is not correct, \
-         please clean your `~/.masp-params/` and re-run `fetch-params`.",
-    );
+/// A source from which the MASP proving parameters can be read.
+///
+/// Implementations are provided for parameter files on the local filesystem
+/// ([`FilesystemParameters`]) and for parameters embedded in memory
+/// ([`InMemoryParameters`]). Deployments that keep parameters elsewhere (an
+/// HTTP cache, a custom key-value store, a mobile asset bundle) can implement
+/// this trait and hand the source to
+/// [`LocalTxProver::from_source`](prover::LocalTxProver::from_source).
+pub trait ParameterSource {
+    /// The reader type yielding the raw parameter bytes.
+    type Read: io::Read;
+
+    /// Returns a reader over the MASP spend parameters.
+    fn spend_params(&self) -> io::Result<Self::Read>;
+
+    /// Returns a reader over the MASP output parameters.
+    fn output_params(&self) -> io::Result<Self::Read>;
+
+    /// Returns a reader over the MASP convert parameters.
+    fn convert_params(&self) -> io::Result<Self::Read>;
+}
 
-    verify_hash(
-        convert_fs,
-        &mut sink,
-        MASP_CONVERT_HASH,
-        MASP_CONVERT_BYTES,
-        MASP_CONVERT_NAME,
-        "a file",
-    )
-    .expect(
-        "MASP convert parameter file is not correct, \
-         please clean your `~/.masp-params/` and re-run `fetch-params`.",
-    );
+/// A [`ParameterSource`] reading the parameter files from local paths.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FilesystemParameters {
+    /// The path to the MASP spend parameter file.
+    pub spend: std::path::PathBuf,
+
+    /// The path to the MASP output parameter file.
+    pub output: std::path::PathBuf,
+
+    /// The path to the MASP convert parameter file.
+    pub convert: std::path::PathBuf,
+}
+
+impl ParameterSource for FilesystemParameters {
+    type Read = BufReader<File>;
+
+    fn spend_params(&self) -> io::Result<Self::Read> {
+        Ok(BufReader::with_capacity(
+            1024 * 1024,
+            File::open(&self.spend)?,
+        ))
+    }
+
+    fn output_params(&self) -> io::Result<Self::Read> {
+        Ok(BufReader::with_capacity(
+            1024 * 1024,
+            File::open(&self.output)?,
+        ))
+    }
+
+    fn convert_params(&self) -> io::Result<Self::Read> {
+        Ok(BufReader::with_capacity(
+            1024 * 1024,
+            File::open(&self.convert)?,
+        ))
+    }
+}
+
+/// A [`ParameterSource`] reading the parameters from in-memory byte slices,
+/// e.g. parameters embedded in the binary or fetched ahead of time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InMemoryParameters<'a> {
+    /// The raw MASP spend parameter bytes.
+    pub spend: &'a [u8],
 
-    // Prepare verifying keys
-    let spend_vk = prepare_verifying_key(&spend_params.vk);
-    let output_vk = prepare_verifying_key(&output_params.vk);
-    let convert_vk = prepare_verifying_key(&convert_params.vk);
+    /// The raw MASP output parameter bytes.
+    pub output: &'a [u8],
 
-    MASPParameters {
+    /// The raw MASP convert parameter bytes.
+    pub convert: &'a [u8],
+}
+
+impl<'a> ParameterSource for InMemoryParameters<'a> {
+    type Read = &'a [u8];
+
+    fn spend_params(&self) -> io::Result<Self::Read> {
+        Ok(self.spend)
+    }
+
+    fn output_params(&self) -> io::Result<Self::Read> {
+        Ok(self.output)
+    }
+
+    fn convert_params(&self) -> io::Result<Self::Read> {
+        Ok(self.convert)
+    }
+}
+
+/// A [`ParameterSource`] that memory-maps the parameter files from local
+/// paths instead of streaming them through a heap buffer.
+///
+/// Each accessor maps the corresponding file on demand, so a source handed to
+/// `LazyLocalTxProver` only ever touches the files whose circuits are
+/// actually proven. The mapped pages are backed by the files and can be
+/// evicted by the operating system under memory pressure, rather than sitting
+/// in the process heap while the parameters are parsed.
+#[cfg(feature = "mmap-params")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mmap-params")))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MmapParameters {
+    /// The path to the MASP spend parameter file.
+    pub spend: std::path::PathBuf,
+
+    /// The path to the MASP output parameter file.
+    pub output: std::path::PathBuf,
+
+    /// The path to the MASP convert parameter file.
+    pub convert: std::path::PathBuf,
+}
+
+#[cfg(feature = "mmap-params")]
+impl MmapParameters {
+    fn map(path: &Path) -> io::Result<io::Cursor<memmap2::Mmap>> {
+        let file = File::open(path)?;
+        // SAFETY: the parameter files are treated as read-only by everything
+        // in this crate. Modifying them while they are mapped is outside the
+        // supported usage, and a truncation or rewrite would be caught by the
+        // hash check performed while parsing.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(io::Cursor::new(map))
+    }
+}
+
+#[cfg(feature = "mmap-params")]
+impl ParameterSource for MmapParameters {
+    type Read = io::Cursor<memmap2::Mmap>;
+
+    fn spend_params(&self) -> io::Result<Self::Read> {
+        Self::map(&self.spend)
+    }
+
+    fn output_params(&self) -> io::Result<Self::Read> {
+        Self::map(&self.output)
+    }
+
+    fn convert_params(&self) -> io::Result<Self::Read> {
+        Self::map(&self.convert)
+    }
+}
+
+/// Load the parameters from the given [`ParameterSource`], checking the hashes
+/// of the parameter data.
+///
+/// Returns an error if any of the readers cannot be obtained; like
+/// [`parse_parameters`], this function will panic if the parameter data itself
+/// is unparseable or does not have the expected hashes.
+pub fn load_parameters_from<S: ParameterSource>(source: &S) -> io::Result<MASPParameters> {
+    Ok(parse_parameters(
+        source.spend_params()?,
+        source.output_params()?,
+        source.convert_params()?,
+    ))
+}
+
+/// Parse Bls12 keys from bytes as serialized by [`Parameters::write`].
+///
+/// This function will panic if it encounters unparseable data; use
+/// [`try_parse_parameters`] to get a typed error instead.
+pub fn parse_parameters<R: io::Read>(spend_fs: R, output_fs: R, convert_fs: R) -> MASPParameters {
+    try_parse_parameters(spend_fs, output_fs, convert_fs).unwrap_or_else(|e| {
+        panic!(
+            "{}\nplease clean your `~/.masp-params/` and re-run `fetch-params`.",
+            e
+        )
+    })
+}
+
+/// Parse Bls12 keys from bytes as serialized by [`Parameters::write`],
+/// recomputing the BLAKE2b hash of each parameter stream and comparing it
+/// against the embedded expected digest.
+pub fn try_parse_parameters<R: io::Read>(
+    spend_fs: R,
+    output_fs: R,
+    convert_fs: R,
+) -> Result<MASPParameters, ParameterError> {
+    let (spend_params, spend_vk) =
+        try_parse_circuit_parameters(spend_fs, MASP_SPEND_NAME, MASP_SPEND_HASH)?;
+    let (output_params, output_vk) =
+        try_parse_circuit_parameters(output_fs, MASP_OUTPUT_NAME, MASP_OUTPUT_HASH)?;
+    let (convert_params, convert_vk) =
+        try_parse_circuit_parameters(convert_fs, MASP_CONVERT_NAME, MASP_CONVERT_HASH)?;
+
+    Ok(MASPParameters {
         spend_params,
         spend_vk,
         output_params,
         output_vk,
         convert_params,
         convert_vk,
+    })
+}
+
+/// Parse a single circuit's Bls12 parameters as serialized by
+/// [`Parameters::write`], verifying the data against `expected_hash` and
+/// preparing the verifying key.
+///
+/// This is the per-circuit building block behind [`try_parse_parameters`]. It
+/// lets callers that only need one circuit (e.g. a wallet that only creates
+/// Outputs) avoid deserializing the other parameter files; see
+/// [`LazyLocalTxProver`](prover::LazyLocalTxProver) for a prover built on top
+/// of it.
+pub fn try_parse_circuit_parameters<R: io::Read>(
+    reader: R,
+    name: &'static str,
+    expected_hash: &'static str,
+) -> Result<(Parameters<Bls12>, PreparedVerifyingKey<Bls12>), ParameterError> {
+    let mut reader = hashreader::HashReader::new(reader);
+
+    let params = Parameters::<Bls12>::read(&mut reader, false)
+        .map_err(|error| ParameterError::Parse { name, error })?;
+
+    // There is extra stuff (the transcript) at the end of the parameter file which is
+    // used to verify the parameter validity, but we're not interested in that. We do
+    // want to read it, though, so that the BLAKE2b computed afterward is consistent
+    // with `b2sum` on the files.
+    io::copy(&mut reader, &mut io::sink()).map_err(|error| ParameterError::Io { name, error })?;
+
+    let actual = reader.into_hash();
+    if actual != expected_hash {
+        return Err(ParameterError::HashMismatch {
+            name,
+            expected: expected_hash,
+            actual,
+        });
     }
+
+    let vk = prepare_verifying_key(&params.vk);
+    Ok((params, vk))
 }
 
 /// Check if the size of the file at `params_path` matches `expected_bytes`,
@@ -426,6 +702,7 @@ fn verify_file_size(
 /// before verifying the hash using this function.
 ///
 /// Returns an error containing `name` and `params_source` on failure.
+#[cfg(feature = "download-params")]
 fn verify_hash<R: io::Read, W: io::Write>(
     mut hash_reader: hashreader::HashReader<R>,
     mut sink: W,
@@ -469,3 +746,100 @@ fn verify_hash<R: io::Read, W: io::Write>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use bellman::{
+        gadgets::num::AllocatedNum, groth16::generate_random_parameters, Circuit, ConstraintSystem,
+        SynthesisError,
+    };
+    use rand_core::OsRng;
+
+    use super::{try_parse_parameters, ParameterError, MASP_SPEND_NAME};
+
+    /// A toy circuit whose parameters parse like real ones but cannot match
+    /// the embedded digests.
+    struct Square {
+        root: Option<bls12_381::Scalar>,
+    }
+
+    impl Circuit<bls12_381::Scalar> for Square {
+        fn synthesize<CS: ConstraintSystem<bls12_381::Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let root = AllocatedNum::alloc(cs.namespace(|| "root"), || {
+                self.root.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let square = root.mul(cs.namespace(|| "square"), &root)?;
+            square.inputize(cs.namespace(|| "square input"))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn try_parse_parameters_rejects_garbage() {
+        let garbage = [0u8; 64];
+        match try_parse_parameters(&garbage[..], &garbage[..], &garbage[..]) {
+            Err(ParameterError::Parse { name, .. }) => assert_eq!(name, MASP_SPEND_NAME),
+            other => panic!("expected parse error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[cfg(feature = "mmap-params")]
+    #[test]
+    fn mmap_parameters_read_the_file_contents() {
+        use super::{MmapParameters, ParameterSource};
+        use std::io::Read;
+
+        let dir = std::env::temp_dir();
+        let source = MmapParameters {
+            spend: dir.join(format!("mmap-test-spend-{}.params", std::process::id())),
+            output: dir.join(format!("mmap-test-output-{}.params", std::process::id())),
+            convert: dir.join(format!("mmap-test-convert-{}.params", std::process::id())),
+        };
+        std::fs::write(&source.spend, b"spend bytes").unwrap();
+        std::fs::write(&source.output, b"output bytes").unwrap();
+        std::fs::write(&source.convert, b"convert bytes").unwrap();
+
+        let mut bytes = vec![];
+        source
+            .spend_params()
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes, b"spend bytes");
+        bytes.clear();
+        source
+            .output_params()
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes, b"output bytes");
+        bytes.clear();
+        source
+            .convert_params()
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes, b"convert bytes");
+
+        std::fs::remove_file(&source.spend).unwrap();
+        std::fs::remove_file(&source.output).unwrap();
+        std::fs::remove_file(&source.convert).unwrap();
+    }
+
+    #[test]
+    fn try_parse_parameters_rejects_wrong_hash() {
+        let params =
+            generate_random_parameters::<bls12_381::Bls12, _, _>(Square { root: None }, &mut OsRng)
+                .unwrap();
+        let mut bytes = vec![];
+        params.write(&mut bytes).unwrap();
+
+        match try_parse_parameters(&bytes[..], &bytes[..], &bytes[..]) {
+            Err(ParameterError::HashMismatch { name, .. }) => assert_eq!(name, MASP_SPEND_NAME),
+            other => panic!("expected hash mismatch, got {:?}", other.map(|_| ())),
+        }
+    }
+}
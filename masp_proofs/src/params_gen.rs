@@ -0,0 +1,327 @@
+//! Phase-2 parameter generation for the MASP circuits.
+//!
+//! This module lets forks of this crate run their own trusted setup ceremony
+//! for the Spend, Output, and Convert circuits without patching external
+//! powersoftau tooling. [`initial_parameters`] produces the starting
+//! transcript (and counts as the first contribution), each participant then
+//! calls [`contribute`] on the parameters in turn, and the coordinator checks
+//! every step with [`verify_contribution`]. The resulting parameters are
+//! secure as long as at least one participant discarded their randomness.
+//!
+//! This is a development feature: the initial transcript is sampled directly
+//! rather than being derived from a powers-of-tau phase 1, so the ceremony's
+//! security rests entirely on the contribution chain.
+
+use std::error;
+use std::fmt;
+
+use bellman::groth16::{generate_random_parameters, Parameters};
+use bls12_381::Bls12;
+use group::Curve;
+use masp_primitives::ff::Field;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::circuit::convert::{Convert, TREE_DEPTH as CONVERT_TREE_DEPTH};
+use crate::circuit::sapling::{Output, Spend, TREE_DEPTH};
+
+/// The parameters for all three MASP circuits, as produced by a ceremony.
+pub struct Phase2Parameters {
+    /// The Spend circuit parameters.
+    pub spend_params: Parameters<Bls12>,
+    /// The Output circuit parameters.
+    pub output_params: Parameters<Bls12>,
+    /// The Convert circuit parameters.
+    pub convert_params: Parameters<Bls12>,
+}
+
+/// An error while verifying a ceremony contribution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase2Error {
+    /// The contribution modified parts of the parameters other than the
+    /// delta-dependent elements.
+    ParametersMismatch,
+    /// The new delta is the identity, or delta was not updated consistently
+    /// between G1 and G2.
+    InvalidDelta,
+    /// The H query was not scaled consistently with the new delta.
+    InconsistentH,
+    /// The L query was not scaled consistently with the new delta.
+    InconsistentL,
+}
+
+impl fmt::Display for Phase2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase2Error::ParametersMismatch => {
+                write!(f, "contribution modified delta-independent parameters")
+            }
+            Phase2Error::InvalidDelta => write!(f, "invalid delta update"),
+            Phase2Error::InconsistentH => write!(f, "H query inconsistent with new delta"),
+            Phase2Error::InconsistentL => write!(f, "L query inconsistent with new delta"),
+        }
+    }
+}
+
+impl error::Error for Phase2Error {}
+
+/// Generates the starting transcript for a ceremony.
+///
+/// The caller's randomness acts as the first contribution; the caller should
+/// discard it like any other participant.
+pub fn initial_parameters<R: RngCore + CryptoRng>(rng: &mut R) -> Phase2Parameters {
+    let spend_params = generate_random_parameters::<Bls12, _, _>(
+        Spend {
+            value_commitment: None,
+            proof_generation_key: None,
+            payment_address: None,
+            commitment_randomness: None,
+            ar: None,
+            auth_path: vec![None; TREE_DEPTH],
+            anchor: None,
+        },
+        rng,
+    )
+    .expect("Spend circuit synthesis is infallible without an assignment");
+
+    let output_params = generate_random_parameters::<Bls12, _, _>(
+        Output {
+            value_commitment: None,
+            asset_identifier: vec![None; 256],
+            payment_address: None,
+            commitment_randomness: None,
+            esk: None,
+        },
+        rng,
+    )
+    .expect("Output circuit synthesis is infallible without an assignment");
+
+    let convert_params = generate_random_parameters::<Bls12, _, _>(
+        Convert {
+            value_commitment: None,
+            auth_path: vec![None; CONVERT_TREE_DEPTH],
+            anchor: None,
+        },
+        rng,
+    )
+    .expect("Convert circuit synthesis is infallible without an assignment");
+
+    Phase2Parameters {
+        spend_params,
+        output_params,
+        convert_params,
+    }
+}
+
+/// Contributes fresh randomness to the given circuit parameters.
+///
+/// This randomizes the delta trapdoor and rescales the delta-dependent
+/// elements accordingly, leaving the rest of the parameters untouched.
+/// Returns a hash binding this contribution, which the participant should
+/// publish so the coordinator can match it against the transcript.
+pub fn contribute<R: RngCore + CryptoRng>(params: &mut Parameters<Bls12>, rng: &mut R) -> [u8; 32] {
+    // Sample a non-zero delta; its inverse then exists.
+    let d = loop {
+        let d = bls12_381::Scalar::random(&mut *rng);
+        if !bool::from(d.is_zero()) {
+            break d;
+        }
+    };
+    let d_inv = d.invert().unwrap();
+
+    params.vk.delta_g1 = (params.vk.delta_g1 * d).to_affine();
+    params.vk.delta_g2 = (params.vk.delta_g2 * d).to_affine();
+
+    let scale = |query: &[bls12_381::G1Affine]| {
+        let scaled: Vec<_> = query.iter().map(|p| p * d_inv).collect();
+        let mut affine = vec![bls12_381::G1Affine::identity(); scaled.len()];
+        bls12_381::G1Projective::batch_normalize(&scaled, &mut affine);
+        affine
+    };
+
+    params.h = scale(&params.h).into();
+    params.l = scale(&params.l).into();
+
+    contribution_hash(params)
+}
+
+/// Verifies that `after` is a valid contribution on top of `before`, i.e.
+/// that only the delta trapdoor was randomized and that every delta-dependent
+/// element was rescaled consistently. Returns the contribution's hash on
+/// success.
+pub fn verify_contribution<R: RngCore + CryptoRng>(
+    before: &Parameters<Bls12>,
+    after: &Parameters<Bls12>,
+    rng: &mut R,
+) -> Result<[u8; 32], Phase2Error> {
+    // Everything that does not depend on delta must be unchanged.
+    if before.vk.alpha_g1 != after.vk.alpha_g1
+        || before.vk.beta_g1 != after.vk.beta_g1
+        || before.vk.beta_g2 != after.vk.beta_g2
+        || before.vk.gamma_g2 != after.vk.gamma_g2
+        || before.vk.ic != after.vk.ic
+        || before.a != after.a
+        || before.b_g1 != after.b_g1
+        || before.b_g2 != after.b_g2
+        || before.h.len() != after.h.len()
+        || before.l.len() != after.l.len()
+    {
+        return Err(Phase2Error::ParametersMismatch);
+    }
+
+    if bool::from(after.vk.delta_g1.is_identity()) || bool::from(after.vk.delta_g2.is_identity()) {
+        return Err(Phase2Error::InvalidDelta);
+    }
+
+    // delta_g1 and delta_g2 must have been scaled by the same factor:
+    // e(d * delta_g1, delta_g2) == e(delta_g1, d * delta_g2).
+    if bls12_381::pairing(&after.vk.delta_g1, &before.vk.delta_g2)
+        != bls12_381::pairing(&before.vk.delta_g1, &after.vk.delta_g2)
+    {
+        return Err(Phase2Error::InvalidDelta);
+    }
+
+    // The H and L queries must have been scaled by the inverse factor. A
+    // random linear combination (with the same coefficients on both sides)
+    // reduces each query to a single pairing check:
+    // e(sum r_i q_i / d, d * delta) == e(sum r_i q_i, delta).
+    let mut combined = |query_before: &[bls12_381::G1Affine],
+                        query_after: &[bls12_381::G1Affine]| {
+        let coeffs: Vec<_> = (0..query_before.len())
+            .map(|_| bls12_381::Scalar::random(&mut *rng))
+            .collect();
+        let combine = |query: &[bls12_381::G1Affine]| {
+            query
+                .iter()
+                .zip(&coeffs)
+                .map(|(p, r)| p * r)
+                .sum::<bls12_381::G1Projective>()
+                .to_affine()
+        };
+        (combine(query_before), combine(query_after))
+    };
+
+    let (h_before, h_after) = combined(&before.h, &after.h);
+    if bls12_381::pairing(&h_after, &after.vk.delta_g2)
+        != bls12_381::pairing(&h_before, &before.vk.delta_g2)
+    {
+        return Err(Phase2Error::InconsistentH);
+    }
+
+    let (l_before, l_after) = combined(&before.l, &after.l);
+    if bls12_381::pairing(&l_after, &after.vk.delta_g2)
+        != bls12_381::pairing(&l_before, &before.vk.delta_g2)
+    {
+        return Err(Phase2Error::InconsistentL);
+    }
+
+    Ok(contribution_hash(after))
+}
+
+/// Computes the hash binding a contribution: a hash of the resulting delta.
+fn contribution_hash(params: &Parameters<Bls12>) -> [u8; 32] {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(b"MASP_Phase2Hash!")
+        .to_state()
+        .update(&params.vk.delta_g1.to_compressed())
+        .update(&params.vk.delta_g2.to_compressed())
+        .finalize();
+
+    let mut out = [0u8; 32];
+    out.copy_from_slice(hash.as_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use bellman::{
+        gadgets::num::AllocatedNum,
+        groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+        },
+        Circuit, ConstraintSystem, SynthesisError,
+    };
+    use bls12_381::Bls12;
+    use group::Curve;
+    use masp_primitives::ff::Field;
+    use rand_core::OsRng;
+
+    use super::{contribute, verify_contribution, Phase2Error};
+
+    /// A toy circuit proving knowledge of a square root, small enough to run
+    /// a full mock ceremony in a test.
+    struct SquareRoot {
+        root: Option<bls12_381::Scalar>,
+    }
+
+    impl Circuit<bls12_381::Scalar> for SquareRoot {
+        fn synthesize<CS: ConstraintSystem<bls12_381::Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let root = AllocatedNum::alloc(cs.namespace(|| "root"), || {
+                self.root.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let square = root.mul(cs.namespace(|| "square"), &root)?;
+            square.inputize(cs.namespace(|| "square input"))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn contributions_verify_and_params_remain_usable() {
+        let mut rng = OsRng;
+
+        let mut params =
+            generate_random_parameters::<Bls12, _, _>(SquareRoot { root: None }, &mut rng).unwrap();
+
+        // Run two contributions and verify each transcript step.
+        for _ in 0..2 {
+            let before = params.clone();
+            let hash = contribute(&mut params, &mut rng);
+            assert_eq!(verify_contribution(&before, &params, &mut rng), Ok(hash));
+        }
+
+        // The final parameters must still produce verifying proofs.
+        let root = bls12_381::Scalar::random(&mut rng);
+        let square = root.square();
+        let proof =
+            create_random_proof(SquareRoot { root: Some(root) }, &params, &mut rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+        assert!(verify_proof(&pvk, &proof, &[square]).is_ok());
+    }
+
+    #[test]
+    fn tampered_contribution_is_rejected() {
+        let mut rng = OsRng;
+
+        let params =
+            generate_random_parameters::<Bls12, _, _>(SquareRoot { root: None }, &mut rng).unwrap();
+
+        // Changing delta without rescaling the queries must be caught.
+        let mut tampered = params.clone();
+        let d = bls12_381::Scalar::random(&mut rng);
+        tampered.vk.delta_g1 = (tampered.vk.delta_g1 * d).to_affine();
+        tampered.vk.delta_g2 = (tampered.vk.delta_g2 * d).to_affine();
+        assert_eq!(
+            verify_contribution(&params, &tampered, &mut rng),
+            Err(Phase2Error::InconsistentH)
+        );
+
+        // Changing a delta-independent element must be caught.
+        let mut tampered = params.clone();
+        tampered.vk.alpha_g1 = (tampered.vk.alpha_g1 * d).to_affine();
+        assert_eq!(
+            verify_contribution(&params, &tampered, &mut rng),
+            Err(Phase2Error::ParametersMismatch)
+        );
+
+        // Scaling delta inconsistently between G1 and G2 must be caught.
+        let mut tampered = params.clone();
+        tampered.vk.delta_g2 = (tampered.vk.delta_g2 * d).to_affine();
+        assert_eq!(
+            verify_contribution(&params, &tampered, &mut rng),
+            Err(Phase2Error::InvalidDelta)
+        );
+    }
+}
@@ -13,15 +13,24 @@ use masp_primitives::{
     },
     transaction::components::{I128Sum, GROTH_PROOF_SIZE},
 };
+use rand_core::{CryptoRng, RngCore};
+use std::io;
 use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
-use crate::{parse_parameters, sapling::SaplingProvingContext};
-
-#[cfg(feature = "local-prover")]
 use crate::{
-    default_params_folder, load_parameters, MASP_CONVERT_NAME, MASP_OUTPUT_NAME, MASP_SPEND_NAME,
+    load_parameters_from, parse_parameters, sapling::SaplingProvingContext,
+    try_parse_circuit_parameters, FilesystemParameters, ParameterSource, MASP_CONVERT_HASH,
+    MASP_CONVERT_NAME, MASP_OUTPUT_HASH, MASP_OUTPUT_NAME, MASP_SPEND_HASH, MASP_SPEND_NAME,
 };
 
+pub mod handle;
+
+use handle::{spawn, ProverHandle};
+
+#[cfg(feature = "local-prover")]
+use crate::{default_params_folder, load_parameters};
+
 /// An implementation of [`TxProver`] using Sapling Spend and Output parameters from
 /// locally-accessible paths.
 pub struct LocalTxProver {
@@ -94,6 +103,28 @@ impl LocalTxProver {
         }
     }
 
+    /// Creates a `LocalTxProver` using parameters read from the given
+    /// [`ParameterSource`].
+    ///
+    /// Returns an error if the source cannot produce readers for the parameter
+    /// data.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the data read from the source does not
+    /// contain valid parameters with the expected hashes.
+    pub fn from_source<S: ParameterSource>(source: &S) -> io::Result<Self> {
+        let p = load_parameters_from(source)?;
+
+        Ok(LocalTxProver {
+            spend_params: p.spend_params,
+            spend_vk: p.spend_vk,
+            output_params: p.output_params,
+            convert_params: p.convert_params,
+            convert_vk: p.convert_vk,
+        })
+    }
+
     /// Attempts to create a `LocalTxProver` using parameters from the default local
     /// location.
     ///
@@ -151,6 +182,133 @@ impl LocalTxProver {
     //         output_params: p.output_params,
     //     }
     //}
+
+    /// Creates a Spend proof on a background thread.
+    ///
+    /// The proving context is moved into the job and handed back alongside the
+    /// proof so that subsequent proofs (and the final binding signature) can
+    /// continue to accumulate into it. Cancelling the returned
+    /// [`ProverHandle`] before the job starts prevents the proof from being
+    /// created; an already-running proof cannot be interrupted.
+    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::type_complexity)]
+    pub fn spend_proof_async(
+        self: Arc<Self>,
+        mut ctx: SaplingProvingContext,
+        mut rng: impl CryptoRng + RngCore + Send + 'static,
+        proof_generation_key: ProofGenerationKey,
+        diversifier: Diversifier,
+        rseed: Rseed,
+        ar: jubjub::Fr,
+        asset_type: AssetType,
+        value: u64,
+        anchor: bls12_381::Scalar,
+        merkle_path: MerklePath<Node>,
+        rcv: jubjub::Fr,
+    ) -> ProverHandle<
+        Result<
+            (
+                SaplingProvingContext,
+                [u8; GROTH_PROOF_SIZE],
+                jubjub::ExtendedPoint,
+                PublicKey,
+            ),
+            (),
+        >,
+    > {
+        spawn(move |_| {
+            Ok(self
+                .spend_proof(
+                    &mut ctx,
+                    &mut rng,
+                    proof_generation_key,
+                    diversifier,
+                    rseed,
+                    ar,
+                    asset_type,
+                    value,
+                    anchor,
+                    merkle_path,
+                    rcv,
+                )
+                .map(|(zkproof, cv, rk)| (ctx, zkproof, cv, rk)))
+        })
+    }
+
+    /// Creates an Output proof on a background thread.
+    ///
+    /// See [`LocalTxProver::spend_proof_async`] for the threading and
+    /// cancellation semantics.
+    #[allow(clippy::too_many_arguments)]
+    pub fn output_proof_async(
+        self: Arc<Self>,
+        mut ctx: SaplingProvingContext,
+        mut rng: impl CryptoRng + RngCore + Send + 'static,
+        esk: jubjub::Fr,
+        payment_address: PaymentAddress,
+        rcm: jubjub::Fr,
+        asset_type: AssetType,
+        value: u64,
+        rcv: jubjub::Fr,
+    ) -> ProverHandle<(
+        SaplingProvingContext,
+        [u8; GROTH_PROOF_SIZE],
+        jubjub::ExtendedPoint,
+    )> {
+        spawn(move |_| {
+            let (zkproof, cv) = self.output_proof(
+                &mut ctx,
+                &mut rng,
+                esk,
+                payment_address,
+                rcm,
+                asset_type,
+                value,
+                rcv,
+            );
+            Ok((ctx, zkproof, cv))
+        })
+    }
+
+    /// Creates a Convert proof on a background thread.
+    ///
+    /// See [`LocalTxProver::spend_proof_async`] for the threading and
+    /// cancellation semantics.
+    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::type_complexity)]
+    pub fn convert_proof_async(
+        self: Arc<Self>,
+        mut ctx: SaplingProvingContext,
+        mut rng: impl CryptoRng + RngCore + Send + 'static,
+        allowed_conversion: AllowedConversion,
+        value: u64,
+        anchor: bls12_381::Scalar,
+        merkle_path: MerklePath<Node>,
+        rcv: jubjub::Fr,
+    ) -> ProverHandle<
+        Result<
+            (
+                SaplingProvingContext,
+                [u8; GROTH_PROOF_SIZE],
+                jubjub::ExtendedPoint,
+            ),
+            (),
+        >,
+    > {
+        spawn(move |_| {
+            Ok(self
+                .convert_proof(
+                    &mut ctx,
+                    &mut rng,
+                    allowed_conversion,
+                    value,
+                    anchor,
+                    merkle_path,
+                    rcv,
+                )
+                .map(|(zkproof, cv)| (ctx, zkproof, cv)))
+        })
+    }
 }
 
 impl TxProver for LocalTxProver {
@@ -163,6 +321,7 @@ impl TxProver for LocalTxProver {
     fn spend_proof(
         &self,
         ctx: &mut Self::SaplingProvingContext,
+        rng: &mut (impl CryptoRng + RngCore),
         proof_generation_key: ProofGenerationKey,
         diversifier: Diversifier,
         rseed: Rseed,
@@ -174,6 +333,7 @@ impl TxProver for LocalTxProver {
         rcv: jubjub::Fr,
     ) -> Result<([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint, PublicKey), ()> {
         let (proof, cv, rk) = ctx.spend_proof(
+            rng,
             proof_generation_key,
             diversifier,
             rseed,
@@ -198,6 +358,7 @@ impl TxProver for LocalTxProver {
     fn output_proof(
         &self,
         ctx: &mut Self::SaplingProvingContext,
+        rng: &mut (impl CryptoRng + RngCore),
         esk: jubjub::Fr,
         payment_address: PaymentAddress,
         rcm: jubjub::Fr,
@@ -206,6 +367,7 @@ impl TxProver for LocalTxProver {
         rcv: jubjub::Fr,
     ) -> ([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint) {
         let (proof, cv) = ctx.output_proof(
+            rng,
             esk,
             payment_address,
             rcm,
@@ -226,6 +388,7 @@ impl TxProver for LocalTxProver {
     fn convert_proof(
         &self,
         ctx: &mut Self::SaplingProvingContext,
+        rng: &mut (impl CryptoRng + RngCore),
         allowed_conversion: AllowedConversion,
         value: u64,
         anchor: bls12_381::Scalar,
@@ -233,6 +396,7 @@ impl TxProver for LocalTxProver {
         rcv: jubjub::Fr,
     ) -> Result<([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint), ()> {
         let (proof, cv) = ctx.convert_proof(
+            rng,
             allowed_conversion,
             value,
             anchor,
@@ -253,9 +417,259 @@ impl TxProver for LocalTxProver {
     fn binding_sig(
         &self,
         ctx: &mut Self::SaplingProvingContext,
+        rng: &mut (impl CryptoRng + RngCore),
         assets_and_values: &I128Sum,
         sighash: &[u8; 32],
     ) -> Result<Signature, ()> {
-        ctx.binding_sig(assets_and_values, sighash)
+        ctx.binding_sig(rng, assets_and_values, sighash)
+    }
+}
+
+/// An implementation of [`TxProver`] that loads each circuit's parameters
+/// lazily, on the first proof that needs them.
+///
+/// [`LocalTxProver`] parses and verifies all three parameter files up front,
+/// which costs on the order of 1.5 GB of memory even for callers that only
+/// ever use one circuit. `LazyLocalTxProver` defers that work per circuit: a
+/// wallet that only creates Outputs never reads the (much larger) Spend
+/// parameters at all. Combine it with the `MmapParameters` source from the
+/// `mmap-params` feature to also keep the raw parameter bytes out of the
+/// process heap while they are parsed.
+///
+/// Once loaded, a circuit's parameters stay resident for the lifetime of the
+/// prover, so repeated proofs pay the cost only once.
+///
+/// # Panics
+///
+/// Like [`LocalTxProver::new`], the proof methods panic if the parameter data
+/// read from the source is invalid or does not have the expected hashes; the
+/// failure simply surfaces at the first proof instead of at construction.
+pub struct LazyLocalTxProver<S> {
+    source: S,
+    spend: OnceLock<(Parameters<Bls12>, PreparedVerifyingKey<Bls12>)>,
+    output: OnceLock<(Parameters<Bls12>, PreparedVerifyingKey<Bls12>)>,
+    convert: OnceLock<(Parameters<Bls12>, PreparedVerifyingKey<Bls12>)>,
+}
+
+impl<S: ParameterSource> LazyLocalTxProver<S> {
+    /// Creates a `LazyLocalTxProver` over the given [`ParameterSource`].
+    ///
+    /// This performs no I/O; each circuit's parameters are read, verified and
+    /// parsed by the first proof that needs them.
+    pub fn from_source(source: S) -> Self {
+        LazyLocalTxProver {
+            source,
+            spend: OnceLock::new(),
+            output: OnceLock::new(),
+            convert: OnceLock::new(),
+        }
+    }
+
+    fn spend(&self) -> &(Parameters<Bls12>, PreparedVerifyingKey<Bls12>) {
+        self.spend.get_or_init(|| {
+            let reader = self
+                .source
+                .spend_params()
+                .expect("couldn't load MASP spend parameters file");
+            Self::parse(reader, MASP_SPEND_NAME, MASP_SPEND_HASH)
+        })
+    }
+
+    fn output(&self) -> &(Parameters<Bls12>, PreparedVerifyingKey<Bls12>) {
+        self.output.get_or_init(|| {
+            let reader = self
+                .source
+                .output_params()
+                .expect("couldn't load MASP output parameters file");
+            Self::parse(reader, MASP_OUTPUT_NAME, MASP_OUTPUT_HASH)
+        })
+    }
+
+    fn convert(&self) -> &(Parameters<Bls12>, PreparedVerifyingKey<Bls12>) {
+        self.convert.get_or_init(|| {
+            let reader = self
+                .source
+                .convert_params()
+                .expect("couldn't load MASP convert parameters file");
+            Self::parse(reader, MASP_CONVERT_NAME, MASP_CONVERT_HASH)
+        })
+    }
+
+    fn parse(
+        reader: S::Read,
+        name: &'static str,
+        expected_hash: &'static str,
+    ) -> (Parameters<Bls12>, PreparedVerifyingKey<Bls12>) {
+        try_parse_circuit_parameters(reader, name, expected_hash).unwrap_or_else(|e| {
+            panic!(
+                "{}\nplease clean your `~/.masp-params/` and re-run `fetch-params`.",
+                e
+            )
+        })
+    }
+}
+
+impl LazyLocalTxProver<FilesystemParameters> {
+    /// Attempts to create a `LazyLocalTxProver` over the parameter files in
+    /// the default local location.
+    ///
+    /// Returns `None` if any of the parameter files cannot be found in the
+    /// default local location; none of them are read until a proof needs
+    /// them.
+    #[cfg(feature = "local-prover")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "local-prover")))]
+    pub fn with_default_location() -> Option<Self> {
+        let params_dir = default_params_folder()?;
+        let source = FilesystemParameters {
+            spend: params_dir.join(MASP_SPEND_NAME),
+            output: params_dir.join(MASP_OUTPUT_NAME),
+            convert: params_dir.join(MASP_CONVERT_NAME),
+        };
+        if !(source.spend.exists() && source.output.exists() && source.convert.exists()) {
+            return None;
+        }
+
+        Some(LazyLocalTxProver::from_source(source))
+    }
+}
+
+impl<S: ParameterSource> TxProver for LazyLocalTxProver<S> {
+    type SaplingProvingContext = SaplingProvingContext;
+
+    fn new_sapling_proving_context(&self) -> Self::SaplingProvingContext {
+        SaplingProvingContext::new()
+    }
+
+    fn spend_proof(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        rng: &mut (impl CryptoRng + RngCore),
+        proof_generation_key: ProofGenerationKey,
+        diversifier: Diversifier,
+        rseed: Rseed,
+        ar: jubjub::Fr,
+        asset_type: AssetType,
+        value: u64,
+        anchor: bls12_381::Scalar,
+        merkle_path: MerklePath<Node>,
+        rcv: jubjub::Fr,
+    ) -> Result<([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint, PublicKey), ()> {
+        let (spend_params, spend_vk) = self.spend();
+        let (proof, cv, rk) = ctx.spend_proof(
+            rng,
+            proof_generation_key,
+            diversifier,
+            rseed,
+            ar,
+            asset_type,
+            value,
+            anchor,
+            merkle_path,
+            spend_params,
+            spend_vk,
+            rcv,
+        )?;
+
+        let mut zkproof = [0u8; GROTH_PROOF_SIZE];
+        proof
+            .write(&mut zkproof[..])
+            .expect("should be able to serialize a proof");
+
+        Ok((zkproof, cv, rk))
+    }
+
+    fn output_proof(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        rng: &mut (impl CryptoRng + RngCore),
+        esk: jubjub::Fr,
+        payment_address: PaymentAddress,
+        rcm: jubjub::Fr,
+        asset_type: AssetType,
+        value: u64,
+        rcv: jubjub::Fr,
+    ) -> ([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint) {
+        let (output_params, _) = self.output();
+        let (proof, cv) = ctx.output_proof(
+            rng,
+            esk,
+            payment_address,
+            rcm,
+            asset_type,
+            value,
+            output_params,
+            rcv,
+        );
+
+        let mut zkproof = [0u8; GROTH_PROOF_SIZE];
+        proof
+            .write(&mut zkproof[..])
+            .expect("should be able to serialize a proof");
+
+        (zkproof, cv)
+    }
+
+    fn convert_proof(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        rng: &mut (impl CryptoRng + RngCore),
+        allowed_conversion: AllowedConversion,
+        value: u64,
+        anchor: bls12_381::Scalar,
+        merkle_path: MerklePath<Node>,
+        rcv: jubjub::Fr,
+    ) -> Result<([u8; GROTH_PROOF_SIZE], jubjub::ExtendedPoint), ()> {
+        let (convert_params, convert_vk) = self.convert();
+        let (proof, cv) = ctx.convert_proof(
+            rng,
+            allowed_conversion,
+            value,
+            anchor,
+            merkle_path,
+            convert_params,
+            convert_vk,
+            rcv,
+        )?;
+
+        let mut zkproof = [0u8; GROTH_PROOF_SIZE];
+        proof
+            .write(&mut zkproof[..])
+            .expect("should be able to serialize a proof");
+
+        Ok((zkproof, cv))
+    }
+
+    fn binding_sig(
+        &self,
+        ctx: &mut Self::SaplingProvingContext,
+        rng: &mut (impl CryptoRng + RngCore),
+        assets_and_values: &I128Sum,
+        sighash: &[u8; 32],
+    ) -> Result<Signature, ()> {
+        ctx.binding_sig(rng, assets_and_values, sighash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use masp_primitives::sapling::prover::TxProver;
+
+    use super::LazyLocalTxProver;
+    use crate::FilesystemParameters;
+
+    #[test]
+    fn lazy_prover_construction_performs_no_io() {
+        // A prover over paths that do not exist can still be constructed and
+        // handed out contexts: nothing is read until a proof needs it, so a
+        // wallet that only creates Outputs never pays for the Spend
+        // parameters.
+        let prover = LazyLocalTxProver::from_source(FilesystemParameters {
+            spend: PathBuf::from("/nonexistent/masp-spend.params"),
+            output: PathBuf::from("/nonexistent/masp-output.params"),
+            convert: PathBuf::from("/nonexistent/masp-convert.params"),
+        });
+        let _ctx = prover.new_sapling_proving_context();
     }
 }
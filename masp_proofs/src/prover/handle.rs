@@ -0,0 +1,221 @@
+//! Background proving jobs with cancellation support.
+//!
+//! Proving a Spend or Convert takes on the order of seconds, which is too long
+//! to block a UI thread. [`spawn`] runs a proving job on a dedicated thread
+//! and returns a [`ProverHandle`] that can be awaited as a [`Future`], waited
+//! on synchronously, or cancelled. An individual Groth16 proof cannot be
+//! interrupted once started, so cancellation takes effect before the job
+//! starts and at any point where the job itself checks its
+//! [`CancellationToken`] (e.g. between the proofs of a multi-descriptor
+//! transaction).
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+/// The error returned by a proving job that was cancelled before it produced
+/// a result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "proving job was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// A token for requesting cancellation of a background proving job.
+///
+/// Tokens are cheap to clone; all clones observe the same cancellation state.
+#[derive(Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Requests cancellation of the associated job.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether cancellation has been requested.
+    ///
+    /// Long-running jobs should check this between proofs and return
+    /// `Err(Cancelled)` promptly when it is set.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+struct State<T> {
+    result: Option<Result<T, Cancelled>>,
+    waker: Option<Waker>,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    finished: Condvar,
+}
+
+/// A handle to a proving job running on a background thread.
+///
+/// The handle resolves — either by `.await`ing it or by calling
+/// [`ProverHandle::wait`] — to the job's output, or to [`Cancelled`] if the
+/// job was cancelled before completing. Dropping the handle requests
+/// cancellation but does not wait for the worker thread to notice.
+pub struct ProverHandle<T> {
+    shared: Arc<Shared<T>>,
+    token: CancellationToken,
+}
+
+impl<T> ProverHandle<T> {
+    /// Returns a token that can be handed to another thread (e.g. a UI
+    /// cancel button) to abort this job.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Requests cancellation of this job.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Blocks until the job completes and returns its result.
+    pub fn wait(self) -> Result<T, Cancelled> {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            if let Some(result) = state.result.take() {
+                return result;
+            }
+            state = self.shared.finished.wait(state).unwrap();
+        }
+    }
+}
+
+impl<T> Future for ProverHandle<T> {
+    type Output = Result<T, Cancelled>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for ProverHandle<T> {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+/// Runs `job` on a new background thread and returns a handle to its result.
+///
+/// The job receives a [`CancellationToken`] and should return `Err(Cancelled)`
+/// when it observes a cancellation request. If the job is cancelled before the
+/// worker thread starts it, it is not run at all.
+pub fn spawn<T, F>(job: F) -> ProverHandle<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&CancellationToken) -> Result<T, Cancelled> + Send + 'static,
+{
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            result: None,
+            waker: None,
+        }),
+        finished: Condvar::new(),
+    });
+    let token = CancellationToken::default();
+
+    {
+        let shared = Arc::clone(&shared);
+        let token = token.clone();
+        thread::spawn(move || {
+            let result = if token.is_cancelled() {
+                Err(Cancelled)
+            } else {
+                job(&token)
+            };
+
+            let mut state = shared.state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+            shared.finished.notify_all();
+        });
+    }
+
+    ProverHandle { shared, token }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::sync::mpsc;
+    use std::task::{RawWaker, RawWakerVTable, Waker};
+
+    use super::{spawn, Cancelled};
+
+    fn noop_waker() -> Waker {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(std::ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+        // SAFETY: the vtable functions are all no-ops on a null pointer.
+        unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+    }
+
+    #[test]
+    fn wait_returns_job_result() {
+        let handle = spawn(|_| Ok(6 * 7));
+        assert_eq!(handle.wait(), Ok(42));
+    }
+
+    #[test]
+    fn cancellation_is_observed_by_the_job() {
+        let (started_tx, started_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let handle = spawn(move |token| {
+            started_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            if token.is_cancelled() {
+                return Err(Cancelled);
+            }
+            Ok(())
+        });
+
+        started_rx.recv().unwrap();
+        handle.cancel();
+        release_tx.send(()).unwrap();
+        assert_eq!(handle.wait(), Err(Cancelled));
+    }
+
+    #[test]
+    fn handle_is_a_future() {
+        let handle = spawn(|_| Ok("done"));
+        // Poll the future to completion with a no-op waker.
+        let mut handle = Box::pin(handle);
+        let waker = noop_waker();
+        let mut cx = std::task::Context::from_waker(&waker);
+        loop {
+            match handle.as_mut().poll(&mut cx) {
+                std::task::Poll::Ready(result) => {
+                    assert_eq!(result, Ok("done"));
+                    break;
+                }
+                std::task::Poll::Pending => std::thread::yield_now(),
+            }
+        }
+    }
+}
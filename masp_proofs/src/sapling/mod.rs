@@ -1,13 +1,16 @@
 //! Helpers for creating MASP Sapling proofs.
 
-use masp_primitives::asset_type::AssetType;
+use masp_primitives::{asset_type::AssetType, transaction::components::I128Sum};
 
 mod prover;
-mod verifier;
+mod rerandomize;
+pub(crate) mod verifier;
 
 pub use self::prover::SaplingProvingContext;
+pub use self::rerandomize::rerandomize_proof;
 pub use self::verifier::{
-    BatchValidator, SaplingVerificationContext, SaplingVerificationContextInner,
+    BatchValidator, FailedCheck, SaplingVerificationContext, SaplingVerificationContextInner,
+    TransactionVerifier, VerificationError,
 };
 
 // This function computes `value` in the exponent of the value commitment base
@@ -36,3 +39,116 @@ fn masp_compute_value_balance(asset_type: AssetType, value: i128) -> Option<jubj
     // Convert to unknown order point
     Some(value_balance.into())
 }
+
+/// Checks the multi-asset balance equation over value commitments.
+///
+/// Computes the sum of the spend and convert value commitments, minus the
+/// output value commitments, minus `value_balance` in the exponent of each
+/// asset's value commitment generator, and returns whether the result is the
+/// identity. This holds exactly when the committed values balance per asset
+/// *and* the commitment randomness cancels, which makes it suitable for
+/// deterministic test vectors and cross-implementation checks of the balance
+/// equation.
+///
+/// Consensus code verifying real transactions (where the net randomness is
+/// nonzero) should instead accumulate the commitments in a
+/// [`SaplingVerificationContext`] and call its `final_check`, which absorbs
+/// the randomness via the binding signature.
+///
+/// Returns `false` if any `value_balance` component is `-i128::MAX - 1`,
+/// which has no valid commitment.
+pub fn check_value_balance(
+    spend_cvs: &[jubjub::ExtendedPoint],
+    convert_cvs: &[jubjub::ExtendedPoint],
+    output_cvs: &[jubjub::ExtendedPoint],
+    value_balance: &I128Sum,
+) -> bool {
+    let mut acc = jubjub::ExtendedPoint::identity();
+    for cv in spend_cvs.iter().chain(convert_cvs) {
+        acc += cv;
+    }
+    for cv in output_cvs {
+        acc -= cv;
+    }
+    for (asset_type, value) in value_balance.components() {
+        match masp_compute_value_balance(*asset_type, *value) {
+            Some(vb) => acc -= vb,
+            None => return false,
+        }
+    }
+    acc.is_identity().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_value_balance;
+    use group::ff::Field;
+    use masp_primitives::{asset_type::AssetType, transaction::components::I128Sum};
+    use rand_core::OsRng;
+
+    #[test]
+    fn value_balance_check() {
+        let mut rng = OsRng;
+        let btc = AssetType::new(b"BTC").unwrap();
+        let eth = AssetType::new(b"ETH").unwrap();
+
+        // One 100 BTC spend splits into a 60 BTC output, a 15 ETH output
+        // backed by an ETH spend, and a 40 BTC transparent value balance.
+        // The trapdoors are chosen to cancel.
+        let (r1, r2, r3) = (
+            jubjub::Fr::random(&mut rng),
+            jubjub::Fr::random(&mut rng),
+            jubjub::Fr::random(&mut rng),
+        );
+        let spend_cvs = [
+            btc.value_commitment(100, r1).commitment().into(),
+            eth.value_commitment(15, r2).commitment().into(),
+        ];
+        let output_cvs = [
+            btc.value_commitment(60, r3).commitment().into(),
+            eth.value_commitment(15, r1 + r2 - r3).commitment().into(),
+        ];
+        let value_balance = I128Sum::from_pair(btc, 40);
+
+        assert!(check_value_balance(
+            &spend_cvs,
+            &[],
+            &output_cvs,
+            &value_balance
+        ));
+
+        // A wrong value balance, or one against the wrong asset, fails.
+        assert!(!check_value_balance(
+            &spend_cvs,
+            &[],
+            &output_cvs,
+            &I128Sum::from_pair(btc, 41)
+        ));
+        assert!(!check_value_balance(
+            &spend_cvs,
+            &[],
+            &output_cvs,
+            &I128Sum::from_pair(eth, 40)
+        ));
+
+        // Unbalanced randomness fails even when the values balance.
+        let bad_output_cvs = [
+            btc.value_commitment(60, r3).commitment().into(),
+            eth.value_commitment(15, r2).commitment().into(),
+        ];
+        assert!(!check_value_balance(
+            &spend_cvs,
+            &[],
+            &bad_output_cvs,
+            &value_balance
+        ));
+
+        // The unrepresentable value balance is rejected.
+        assert!(!check_value_balance(
+            &spend_cvs,
+            &[],
+            &output_cvs,
+            &I128Sum::from_pair(btc, i128::MIN)
+        ));
+    }
+}
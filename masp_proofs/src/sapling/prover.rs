@@ -1,9 +1,8 @@
-use bellman::{
-    gadgets::multipack,
-    groth16::{create_random_proof, verify_proof, Parameters, PreparedVerifyingKey, Proof},
+use bellman::groth16::{
+    create_random_proof, verify_proof, Parameters, PreparedVerifyingKey, Proof,
 };
 use bls12_381::Bls12;
-use group::{Curve, GroupEncoding};
+use group::GroupEncoding;
 use masp_primitives::{
     asset_type::AssetType,
     constants::{SPENDING_KEY_GENERATOR, VALUE_COMMITMENT_RANDOMNESS_GENERATOR},
@@ -15,10 +14,11 @@ use masp_primitives::{
     },
     transaction::components::I128Sum,
 };
-use rand_core::OsRng;
+use rand_core::{CryptoRng, RngCore};
 use std::ops::{AddAssign, Neg};
 
 use super::masp_compute_value_balance;
+use super::verifier::{convert_public_inputs, spend_public_inputs};
 use crate::circuit::convert::Convert;
 use crate::circuit::sapling::{Output, Spend};
 
@@ -50,6 +50,7 @@ impl SaplingProvingContext {
     #[allow(clippy::too_many_arguments)]
     pub fn spend_proof(
         &mut self,
+        rng: &mut (impl CryptoRng + RngCore),
         proof_generation_key: ProofGenerationKey,
         diversifier: Diversifier,
         rseed: Rseed,
@@ -62,9 +63,6 @@ impl SaplingProvingContext {
         verifying_key: &PreparedVerifyingKey<Bls12>,
         rcv: jubjub::Fr,
     ) -> Result<(Proof<Bls12>, jubjub::ExtendedPoint, PublicKey), ()> {
-        // Initialize secure RNG
-        let mut rng = OsRng;
-
         // Accumulate the value commitment randomness in the context
         {
             let mut tmp = rcv;
@@ -87,13 +85,13 @@ impl SaplingProvingContext {
         let rk = PublicKey(proof_generation_key.ak.into()).randomize(ar, SPENDING_KEY_GENERATOR);
 
         // Let's compute the nullifier while we have the position
-        let note = Note {
+        let note = Note::from_parts(
             asset_type,
             value,
-            g_d: diversifier.g_d().expect("was a valid diversifier before"),
-            pk_d: *payment_address.pk_d(),
+            diversifier.g_d().expect("was a valid diversifier before"),
+            *payment_address.pk_d(),
             rseed,
-        };
+        );
 
         let nullifier = note.nf(&viewing_key.nk, merkle_path.position);
 
@@ -114,42 +112,18 @@ impl SaplingProvingContext {
 
         // Create proof
         let proof =
-            create_random_proof(instance, proving_key, &mut rng).expect("proving should not fail");
+            create_random_proof(instance, proving_key, rng).expect("proving should not fail");
 
         // Try to verify the proof:
-        // Construct public input for circuit
-        let mut public_input = [bls12_381::Scalar::zero(); 7];
-        {
-            let affine = rk.0.to_affine();
-            let (u, v) = (affine.get_u(), affine.get_v());
-            public_input[0] = u;
-            public_input[1] = v;
-        }
-        {
-            let affine = jubjub::ExtendedPoint::from(value_commitment.commitment()).to_affine();
-            let (u, v) = (affine.get_u(), affine.get_v());
-            public_input[2] = u;
-            public_input[3] = v;
-        }
-        public_input[4] = anchor;
-
-        // Add the nullifier through multiscalar packing
-        {
-            let nullifier = multipack::bytes_to_bits_le(&nullifier.0);
-            let nullifier = multipack::compute_multipacking(&nullifier);
-
-            assert_eq!(nullifier.len(), 2);
+        // Compute value commitment
+        let value_commitment: jubjub::ExtendedPoint = value_commitment.commitment().into();
 
-            public_input[5] = nullifier[0];
-            public_input[6] = nullifier[1];
-        }
+        // Construct public input for circuit
+        let public_input = spend_public_inputs(&value_commitment, anchor, &nullifier.0, &rk);
 
         // Verify the proof
         verify_proof(verifying_key, &proof, &public_input[..]).map_err(|_| ())?;
 
-        // Compute value commitment
-        let value_commitment: jubjub::ExtendedPoint = value_commitment.commitment().into();
-
         // Accumulate the value commitment in the context
         self.cv_sum += value_commitment;
 
@@ -162,6 +136,7 @@ impl SaplingProvingContext {
     #[allow(clippy::too_many_arguments)]
     pub fn output_proof(
         &mut self,
+        rng: &mut (impl CryptoRng + RngCore),
         esk: jubjub::Fr,
         payment_address: PaymentAddress,
         rcm: jubjub::Fr,
@@ -170,9 +145,6 @@ impl SaplingProvingContext {
         proving_key: &Parameters<Bls12>,
         rcv: jubjub::Fr,
     ) -> (Proof<Bls12>, jubjub::ExtendedPoint) {
-        // Initialize secure RNG
-        let mut rng = OsRng;
-
         // Accumulate the value commitment randomness in the context
         {
             let mut tmp = rcv.neg(); // Outputs subtract from the total.
@@ -199,7 +171,7 @@ impl SaplingProvingContext {
 
         // Create proof
         let proof =
-            create_random_proof(instance, proving_key, &mut rng).expect("proving should not fail");
+            create_random_proof(instance, proving_key, rng).expect("proving should not fail");
 
         // Accumulate the value commitment in the context. We do this to check internal consistency.
         self.cv_sum -= value_commitment_point; // Outputs subtract from the total.
@@ -213,6 +185,7 @@ impl SaplingProvingContext {
     #[allow(clippy::too_many_arguments)]
     pub fn convert_proof(
         &mut self,
+        rng: &mut (impl CryptoRng + RngCore),
         allowed_conversion: AllowedConversion,
         value: u64,
         anchor: bls12_381::Scalar,
@@ -221,9 +194,6 @@ impl SaplingProvingContext {
         verifying_key: &PreparedVerifyingKey<Bls12>,
         rcv: jubjub::Fr,
     ) -> Result<(Proof<Bls12>, jubjub::ExtendedPoint), ()> {
-        // Initialize secure RNG
-        let mut rng = OsRng;
-
         // Accumulate the value commitment randomness in the context
         {
             let mut tmp = rcv;
@@ -249,25 +219,18 @@ impl SaplingProvingContext {
 
         // Create proof
         let proof =
-            create_random_proof(instance, proving_key, &mut rng).expect("proving should not fail");
+            create_random_proof(instance, proving_key, rng).expect("proving should not fail");
 
         // Try to verify the proof:
+        // Compute value commitment
+        let value_commitment: jubjub::ExtendedPoint = value_commitment.commitment().into();
+
         // Construct public input for circuit
-        let mut public_input = [bls12_381::Scalar::zero(); 3];
-        {
-            let affine = jubjub::ExtendedPoint::from(value_commitment.commitment()).to_affine();
-            let (u, v) = (affine.get_u(), affine.get_v());
-            public_input[0] = u;
-            public_input[1] = v;
-        }
-        public_input[2] = anchor;
+        let public_input = convert_public_inputs(&value_commitment, anchor);
 
         // Verify the proof
         verify_proof(verifying_key, &proof, &public_input[..]).map_err(|_| ())?;
 
-        // Compute value commitment
-        let value_commitment: jubjub::ExtendedPoint = value_commitment.commitment().into();
-
         // Accumulate the value commitment in the context
         self.cv_sum += value_commitment;
 
@@ -278,12 +241,10 @@ impl SaplingProvingContext {
     /// and output_proof() must be completed before calling this function.
     pub fn binding_sig(
         &self,
+        rng: &mut (impl CryptoRng + RngCore),
         assets_and_values: &I128Sum,
         sighash: &[u8; 32],
     ) -> Result<Signature, ()> {
-        // Initialize secure RNG
-        let mut rng = OsRng;
-
         // Grab the current `bsk` from the context
         let bsk = PrivateKey(self.bsk);
 
@@ -320,7 +281,7 @@ impl SaplingProvingContext {
         // Sign
         Ok(bsk.sign(
             &data_to_be_signed,
-            &mut rng,
+            rng,
             VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
         ))
     }
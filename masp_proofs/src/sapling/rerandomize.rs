@@ -0,0 +1,122 @@
+//! Re-randomization of Groth16 proofs.
+
+use bellman::groth16::{Proof, VerifyingKey};
+use bls12_381::Bls12;
+use group::Curve;
+use masp_primitives::ff::Field;
+use rand_core::{CryptoRng, RngCore};
+
+/// Re-randomizes a Groth16 proof into a fresh, uniformly distributed proof of
+/// the same statement, without access to the witness.
+///
+/// Groth16 proofs are randomizable: anyone holding a valid proof can produce
+/// another valid proof of the same statement that is distributed identically
+/// to a freshly created one. A coordinator assembling a transaction from
+/// pre-made Spend, Output, or Convert proofs can use this to break the link
+/// between the broadcast proof bytes and the party that created them. The
+/// only circuit-specific input is the verifying key the proof was created
+/// under; the result verifies against the same public inputs.
+pub fn rerandomize_proof<R: RngCore + CryptoRng>(
+    proof: &Proof<Bls12>,
+    vk: &VerifyingKey<Bls12>,
+    rng: &mut R,
+) -> Proof<Bls12> {
+    let r1 = loop {
+        let r1 = bls12_381::Scalar::random(&mut *rng);
+        if !bool::from(r1.is_zero()) {
+            break r1;
+        }
+    };
+    let r2 = bls12_381::Scalar::random(&mut *rng);
+
+    // With A' = (1/r1)·A, B' = r1·B + r1·r2·δ and C' = C + r2·A, both sides
+    // of the verification equation gain exactly e(r2·A, δ):
+    //   e(A', B') = e(A, B) + e(r2·A, δ)
+    //   e(C', δ)  = e(C, δ) + e(r2·A, δ)
+    let a = (proof.a * r1.invert().unwrap()).to_affine();
+    let b = (proof.b * r1 + vk.delta_g2 * (r1 * r2)).to_affine();
+    let c = (proof.a * r2 + proof.c).to_affine();
+
+    Proof { a, b, c }
+}
+
+#[cfg(test)]
+mod tests {
+    use bellman::{
+        gadgets::num::AllocatedNum,
+        groth16::{
+            create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+        },
+        Circuit, ConstraintSystem, SynthesisError,
+    };
+    use bls12_381::Bls12;
+    use masp_primitives::ff::Field;
+    use rand_core::OsRng;
+
+    use super::rerandomize_proof;
+
+    /// A toy circuit proving knowledge of two factors of a public product.
+    struct Factors {
+        left: Option<bls12_381::Scalar>,
+        right: Option<bls12_381::Scalar>,
+    }
+
+    impl Circuit<bls12_381::Scalar> for Factors {
+        fn synthesize<CS: ConstraintSystem<bls12_381::Scalar>>(
+            self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let left = AllocatedNum::alloc(cs.namespace(|| "left"), || {
+                self.left.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let right = AllocatedNum::alloc(cs.namespace(|| "right"), || {
+                self.right.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let product = left.mul(cs.namespace(|| "product"), &right)?;
+            product.inputize(cs.namespace(|| "product input"))?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rerandomized_proof_verifies_and_is_unlinkable() {
+        let mut rng = OsRng;
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            Factors {
+                left: None,
+                right: None,
+            },
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let left = bls12_381::Scalar::random(&mut rng);
+        let right = bls12_381::Scalar::random(&mut rng);
+        let product = left * right;
+        let proof = create_random_proof(
+            Factors {
+                left: Some(left),
+                right: Some(right),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify_proof(&pvk, &proof, &[product]).is_ok());
+
+        // Two independent re-randomizations verify, differ from the original
+        // proof, and differ from each other.
+        let rerandomized = rerandomize_proof(&proof, &params.vk, &mut rng);
+        let again = rerandomize_proof(&proof, &params.vk, &mut rng);
+        assert!(verify_proof(&pvk, &rerandomized, &[product]).is_ok());
+        assert!(verify_proof(&pvk, &again, &[product]).is_ok());
+        assert_ne!(rerandomized, proof);
+        assert_ne!(again, proof);
+        assert_ne!(again, rerandomized);
+
+        // Re-randomization proves the same statement, not a different one.
+        assert!(verify_proof(&pvk, &rerandomized, &[product.square()]).is_err());
+    }
+}
@@ -1,5 +1,8 @@
 #![allow(clippy::new_without_default)]
 
+use std::error;
+use std::fmt;
+
 use bellman::{gadgets::multipack, groth16::Proof};
 use bls12_381::Bls12;
 use group::{Curve, GroupEncoding};
@@ -16,6 +19,162 @@ pub use single::SaplingVerificationContext;
 mod batch;
 pub use batch::BatchValidator;
 
+mod transaction;
+pub use transaction::TransactionVerifier;
+
+/// The specific consensus check that failed while verifying a Sapling
+/// description.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailedCheck {
+    /// The value commitment was of small order.
+    CvSmallOrder,
+    /// The randomized verification key was of small order.
+    RkSmallOrder,
+    /// The ephemeral key was of small order, or failed to deserialize.
+    EphemeralKey,
+    /// The spend authorization signature was invalid.
+    SpendAuthSig,
+    /// The zk-SNARK proof failed to deserialize or verify.
+    Proof,
+    /// A component of the value balance could not be computed in the exponent
+    /// of the value commitment base.
+    ValueBalance,
+    /// The binding signature was invalid.
+    BindingSig,
+}
+
+impl fmt::Display for FailedCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailedCheck::CvSmallOrder => write!(f, "value commitment is of small order"),
+            FailedCheck::RkSmallOrder => {
+                write!(f, "randomized verification key is of small order")
+            }
+            FailedCheck::EphemeralKey => write!(f, "invalid ephemeral key"),
+            FailedCheck::SpendAuthSig => write!(f, "invalid spend authorization signature"),
+            FailedCheck::Proof => write!(f, "invalid zk-SNARK proof"),
+            FailedCheck::ValueBalance => write!(f, "invalid value balance"),
+            FailedCheck::BindingSig => write!(f, "invalid binding signature"),
+        }
+    }
+}
+
+impl error::Error for FailedCheck {}
+
+/// A verification failure, identifying the description in which it occurred
+/// and the [`FailedCheck`] that rejected it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// A check failed in the Spend description at the given index.
+    Spend(usize, FailedCheck),
+    /// A check failed in the Convert description at the given index.
+    Convert(usize, FailedCheck),
+    /// A check failed in the Output description at the given index.
+    Output(usize, FailedCheck),
+    /// A whole-bundle check (value balance or binding signature) failed.
+    Bundle(FailedCheck),
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::Spend(i, check) => {
+                write!(f, "Spend description {}: {}", i, check)
+            }
+            VerificationError::Convert(i, check) => {
+                write!(f, "Convert description {}: {}", i, check)
+            }
+            VerificationError::Output(i, check) => {
+                write!(f, "Output description {}: {}", i, check)
+            }
+            VerificationError::Bundle(check) => write!(f, "bundle: {}", check),
+        }
+    }
+}
+
+impl error::Error for VerificationError {}
+
+/// Assembles the Groth16 public inputs for a Spend proof, in the order the
+/// verifying key expects them: `rk`, `cv`, the anchor, then the multipacked
+/// nullifier.
+pub(crate) fn spend_public_inputs(
+    cv: &jubjub::ExtendedPoint,
+    anchor: bls12_381::Scalar,
+    nullifier: &[u8; 32],
+    rk: &PublicKey,
+) -> [bls12_381::Scalar; 7] {
+    let mut public_input = [bls12_381::Scalar::zero(); 7];
+    {
+        let affine = rk.0.to_affine();
+        let (u, v) = (affine.get_u(), affine.get_v());
+        public_input[0] = u;
+        public_input[1] = v;
+    }
+    {
+        let affine = cv.to_affine();
+        let (u, v) = (affine.get_u(), affine.get_v());
+        public_input[2] = u;
+        public_input[3] = v;
+    }
+    public_input[4] = anchor;
+
+    // Add the nullifier through multiscalar packing
+    {
+        let nullifier = multipack::bytes_to_bits_le(nullifier);
+        let nullifier = multipack::compute_multipacking(&nullifier);
+
+        assert_eq!(nullifier.len(), 2);
+
+        public_input[5] = nullifier[0];
+        public_input[6] = nullifier[1];
+    }
+
+    public_input
+}
+
+/// Assembles the Groth16 public inputs for a Convert proof: `cv`, then the
+/// anchor.
+pub(crate) fn convert_public_inputs(
+    cv: &jubjub::ExtendedPoint,
+    anchor: bls12_381::Scalar,
+) -> [bls12_381::Scalar; 3] {
+    let mut public_input = [bls12_381::Scalar::zero(); 3];
+    {
+        let affine = cv.to_affine();
+        let (u, v) = (affine.get_u(), affine.get_v());
+        public_input[0] = u;
+        public_input[1] = v;
+    }
+    public_input[2] = anchor;
+
+    public_input
+}
+
+/// Assembles the Groth16 public inputs for an Output proof: `cv`, `epk`,
+/// then `cmu`.
+pub(crate) fn output_public_inputs(
+    cv: &jubjub::ExtendedPoint,
+    cmu: bls12_381::Scalar,
+    epk: &jubjub::ExtendedPoint,
+) -> [bls12_381::Scalar; 5] {
+    let mut public_input = [bls12_381::Scalar::zero(); 5];
+    {
+        let affine = cv.to_affine();
+        let (u, v) = (affine.get_u(), affine.get_v());
+        public_input[0] = u;
+        public_input[1] = v;
+    }
+    {
+        let affine = epk.to_affine();
+        let (u, v) = (affine.get_u(), affine.get_v());
+        public_input[2] = u;
+        public_input[3] = v;
+    }
+    public_input[4] = cmu;
+
+    public_input
+}
+
 /// A context object for verifying the Sapling components of a Zcash transaction.
 pub struct SaplingVerificationContextInner {
     // (sum of the Spend value commitments) - (sum of the Output value commitments)
@@ -45,57 +204,36 @@ impl SaplingVerificationContextInner {
         verifier_ctx: &mut C,
         spend_auth_sig_verifier: impl FnOnce(&mut C, PublicKey, [u8; 64], Signature) -> bool,
         proof_verifier: impl FnOnce(&mut C, Proof<Bls12>, [bls12_381::Scalar; 7]) -> bool,
-    ) -> bool {
-        if (cv.is_small_order() | rk.0.is_small_order()).into() {
-            return false;
+    ) -> Result<(), FailedCheck> {
+        if cv.is_small_order().into() {
+            return Err(FailedCheck::CvSmallOrder);
+        }
+        if rk.0.is_small_order().into() {
+            return Err(FailedCheck::RkSmallOrder);
         }
 
         // Accumulate the value commitment in the context
         self.cv_sum += cv;
 
-        // Grab the nullifier as a sequence of bytes
-        let nullifier = &nullifier[..];
-
         // Compute the signature's message for rk/spend_auth_sig
         let mut data_to_be_signed = [0u8; 64];
         data_to_be_signed[0..32].copy_from_slice(&rk.0.to_bytes());
         data_to_be_signed[32..64].copy_from_slice(&sighash_value[..]);
 
         // Verify the spend_auth_sig
-        let rk_affine = rk.0.to_affine();
         if !spend_auth_sig_verifier(verifier_ctx, rk, data_to_be_signed, spend_auth_sig) {
-            return false;
+            return Err(FailedCheck::SpendAuthSig);
         }
 
         // Construct public input for circuit
-        let mut public_input = [bls12_381::Scalar::zero(); 7];
-        {
-            let affine = rk_affine;
-            let (u, v) = (affine.get_u(), affine.get_v());
-            public_input[0] = u;
-            public_input[1] = v;
-        }
-        {
-            let affine = cv.to_affine();
-            let (u, v) = (affine.get_u(), affine.get_v());
-            public_input[2] = u;
-            public_input[3] = v;
-        }
-        public_input[4] = anchor;
-
-        // Add the nullifier through multiscalar packing
-        {
-            let nullifier = multipack::bytes_to_bits_le(nullifier);
-            let nullifier = multipack::compute_multipacking(&nullifier);
-
-            assert_eq!(nullifier.len(), 2);
+        let public_input = spend_public_inputs(&cv, anchor, nullifier, &rk);
 
-            public_input[5] = nullifier[0];
-            public_input[6] = nullifier[1];
+        // Verify the proof
+        if !proof_verifier(verifier_ctx, zkproof, public_input) {
+            return Err(FailedCheck::Proof);
         }
 
-        // Verify the proof
-        proof_verifier(verifier_ctx, zkproof, public_input)
+        Ok(())
     }
 
     /// Perform consensus checks on a Convert SpendDescription, while
@@ -108,26 +246,23 @@ impl SaplingVerificationContextInner {
         zkproof: Proof<Bls12>,
         verifier_ctx: &mut C,
         proof_verifier: impl FnOnce(&mut C, Proof<Bls12>, [bls12_381::Scalar; 3]) -> bool,
-    ) -> bool {
+    ) -> Result<(), FailedCheck> {
         if cv.is_small_order().into() {
-            return false;
+            return Err(FailedCheck::CvSmallOrder);
         }
 
         // Accumulate the value commitment in the context
         self.cv_sum += cv;
 
         // Construct public input for circuit
-        let mut public_input = [bls12_381::Scalar::zero(); 3];
-        {
-            let affine = cv.to_affine();
-            let (u, v) = (affine.get_u(), affine.get_v());
-            public_input[0] = u;
-            public_input[1] = v;
-        }
-        public_input[2] = anchor;
+        let public_input = convert_public_inputs(&cv, anchor);
 
         // Verify the proof
-        proof_verifier(verifier_ctx, zkproof, public_input)
+        if !proof_verifier(verifier_ctx, zkproof, public_input) {
+            return Err(FailedCheck::Proof);
+        }
+
+        Ok(())
     }
 
     /// Perform consensus checks on a Sapling OutputDescription, while
@@ -139,32 +274,26 @@ impl SaplingVerificationContextInner {
         epk: jubjub::ExtendedPoint,
         zkproof: Proof<Bls12>,
         proof_verifier: impl FnOnce(Proof<Bls12>, [bls12_381::Scalar; 5]) -> bool,
-    ) -> bool {
-        if (cv.is_small_order() | epk.is_small_order()).into() {
-            return false;
+    ) -> Result<(), FailedCheck> {
+        if cv.is_small_order().into() {
+            return Err(FailedCheck::CvSmallOrder);
+        }
+        if epk.is_small_order().into() {
+            return Err(FailedCheck::EphemeralKey);
         }
 
         // Accumulate the value commitment in the context
         self.cv_sum -= cv;
 
         // Construct public input for circuit
-        let mut public_input = [bls12_381::Scalar::zero(); 5];
-        {
-            let affine = cv.to_affine();
-            let (u, v) = (affine.get_u(), affine.get_v());
-            public_input[0] = u;
-            public_input[1] = v;
-        }
-        {
-            let affine = epk.to_affine();
-            let (u, v) = (affine.get_u(), affine.get_v());
-            public_input[2] = u;
-            public_input[3] = v;
-        }
-        public_input[4] = cmu;
+        let public_input = output_public_inputs(&cv, cmu, &epk);
 
         // Verify the proof
-        proof_verifier(zkproof, public_input)
+        if !proof_verifier(zkproof, public_input) {
+            return Err(FailedCheck::Proof);
+        }
+
+        Ok(())
     }
 
     /// Perform consensus checks on the valueBalance and bindingSig parts of a
@@ -176,7 +305,7 @@ impl SaplingVerificationContextInner {
         sighash_value: &[u8; 32],
         binding_sig: Signature,
         binding_sig_verifier: impl FnOnce(PublicKey, &[u8; 32], Signature) -> bool,
-    ) -> bool {
+    ) -> Result<(), FailedCheck> {
         // Obtain current cv_sum from the context
         let mut bvk = PublicKey(self.cv_sum);
 
@@ -195,10 +324,14 @@ impl SaplingVerificationContextInner {
                 // Compute cv_sum minus sum of all value balances
                 tmp - value_balance
             }),
-            Err(_) => return false,
+            Err(_) => return Err(FailedCheck::ValueBalance),
         };
 
         // Verify the binding_sig
-        binding_sig_verifier(bvk, sighash_value, binding_sig)
+        if !binding_sig_verifier(bvk, sighash_value, binding_sig) {
+            return Err(FailedCheck::BindingSig);
+        }
+
+        Ok(())
     }
 }
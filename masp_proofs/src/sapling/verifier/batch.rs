@@ -4,7 +4,7 @@ use group::GroupEncoding;
 use masp_primitives::transaction::components::sapling::{Authorized, Bundle};
 use rand_core::{CryptoRng, RngCore};
 
-use super::SaplingVerificationContextInner;
+use super::{FailedCheck, SaplingVerificationContextInner, VerificationError};
 
 /// Batch validation context for MASP/Sapling.
 ///
@@ -40,27 +40,41 @@ impl BatchValidator {
     /// Checks the bundle against Sapling-specific consensus rules, and adds its proof and
     /// signatures to the validator.
     ///
-    /// Returns `false` if the bundle doesn't satisfy all of the consensus rules. This
-    /// `BatchValidator` can continue to be used regardless, but some or all of the proofs
-    /// and signatures from this bundle may have already been added to the batch even if
-    /// it fails other consensus rules.
-    pub fn check_bundle(&mut self, bundle: Bundle<Authorized>, sighash: [u8; 32]) -> bool {
+    /// Returns an error identifying the description and check that failed if the bundle
+    /// doesn't satisfy all of the consensus rules. This `BatchValidator` can continue to
+    /// be used regardless, but some or all of the proofs and signatures from this bundle
+    /// may have already been added to the batch even if it fails other consensus rules.
+    pub fn check_bundle(
+        &mut self,
+        bundle: Bundle<Authorized>,
+        sighash: [u8; 32],
+    ) -> Result<(), VerificationError> {
+        let result = self.check_bundle_inner(bundle, sighash);
+        if let Err(e) = result {
+            tracing::debug!("Sapling bundle check failed: {}", e);
+        }
+        result
+    }
+
+    fn check_bundle_inner(
+        &mut self,
+        bundle: Bundle<Authorized>,
+        sighash: [u8; 32],
+    ) -> Result<(), VerificationError> {
         self.bundles_added = true;
 
         let mut ctx = SaplingVerificationContextInner::new();
 
-        for spend in bundle.shielded_spends {
+        for (index, spend) in bundle.shielded_spends.into_iter().enumerate() {
             // Deserialize the proof
-            let zkproof = match groth16::Proof::read(&spend.zkproof[..]) {
-                Ok(p) => p,
-                Err(_) => return false,
-            };
+            let zkproof = groth16::Proof::read(&spend.zkproof[..])
+                .map_err(|_| VerificationError::Spend(index, FailedCheck::Proof))?;
 
             // Check the Spend consensus rules, and batch its proof and spend
             // authorization signature.
-            let consensus_rules_passed = ctx.check_spend(
+            ctx.check_spend(
                 spend.cv,
-                spend.anchor,
+                spend.anchor.0,
                 &spend.nullifier.0,
                 spend.rk,
                 &sighash,
@@ -84,61 +98,50 @@ impl BatchValidator {
                     this.spend_proofs.queue((proof, public_inputs.to_vec()));
                     true
                 },
-            );
-            if !consensus_rules_passed {
-                return false;
-            }
+            )
+            .map_err(|check| VerificationError::Spend(index, check))?;
         }
-        for convert in bundle.shielded_converts {
+        for (index, convert) in bundle.shielded_converts.into_iter().enumerate() {
             // Deserialize the proof
-            let zkproof = match groth16::Proof::read(&convert.zkproof[..]) {
-                Ok(p) => p,
-                Err(_) => return false,
-            };
+            let zkproof = groth16::Proof::read(&convert.zkproof[..])
+                .map_err(|_| VerificationError::Convert(index, FailedCheck::Proof))?;
 
             // Check the Convert consensus rules, and batch its proof
-            let consensus_rules_passed = ctx.check_convert(
+            ctx.check_convert(
                 convert.cv,
-                convert.anchor,
+                convert.anchor.0,
                 zkproof,
                 self,
                 |this, proof, public_inputs| {
                     this.convert_proofs.queue((proof, public_inputs.to_vec()));
                     true
                 },
-            );
-            if !consensus_rules_passed {
-                return false;
-            }
+            )
+            .map_err(|check| VerificationError::Convert(index, check))?;
         }
 
-        for output in bundle.shielded_outputs {
+        for (index, output) in bundle.shielded_outputs.into_iter().enumerate() {
             // Deserialize the ephemeral key
-            let epk = match jubjub::ExtendedPoint::from_bytes(&output.ephemeral_key.0).into() {
-                Some(p) => p,
-                None => return false,
-            };
+            let epk: jubjub::ExtendedPoint =
+                Option::from(jubjub::ExtendedPoint::from_bytes(&output.ephemeral_key.0))
+                    .ok_or(VerificationError::Output(index, FailedCheck::EphemeralKey))?;
 
             // Deserialize the proof
-            let zkproof = match groth16::Proof::read(&output.zkproof[..]) {
-                Ok(p) => p,
-                Err(_) => return false,
-            };
+            let zkproof = groth16::Proof::read(&output.zkproof[..])
+                .map_err(|_| VerificationError::Output(index, FailedCheck::Proof))?;
 
             // Check the Output consensus rules, and batch its proof.
-            let consensus_rules_passed = ctx.check_output(
+            ctx.check_output(
                 output.cv,
-                output.cmu,
+                output.cmu.into(),
                 epk,
                 zkproof,
                 |proof, public_inputs| {
                     self.output_proofs.queue((proof, public_inputs.to_vec()));
                     true
                 },
-            );
-            if !consensus_rules_passed {
-                return false;
-            }
+            )
+            .map_err(|check| VerificationError::Output(index, check))?;
         }
 
         // Check the whole-bundle consensus rules, and batch the binding signature.
@@ -159,6 +162,7 @@ impl BatchValidator {
                 true
             },
         )
+        .map_err(VerificationError::Bundle)
     }
 
     /// Batch-validates the accumulated bundles.
@@ -7,7 +7,7 @@ use masp_primitives::{
     transaction::components::I128Sum,
 };
 
-use super::SaplingVerificationContextInner;
+use super::{FailedCheck, SaplingVerificationContextInner};
 
 /// A context object for verifying the Sapling components of a single Zcash transaction.
 pub struct SaplingVerificationContext {
@@ -37,7 +37,7 @@ impl SaplingVerificationContext {
         spend_auth_sig: Signature,
         zkproof: Proof<Bls12>,
         verifying_key: &PreparedVerifyingKey<Bls12>,
-    ) -> bool {
+    ) -> Result<(), FailedCheck> {
         let zip216_enabled = true;
         self.inner.check_spend(
             cv,
@@ -71,7 +71,7 @@ impl SaplingVerificationContext {
         anchor: bls12_381::Scalar,
         zkproof: Proof<Bls12>,
         verifying_key: &PreparedVerifyingKey<Bls12>,
-    ) -> bool {
+    ) -> Result<(), FailedCheck> {
         self.inner
             .check_convert(cv, anchor, zkproof, &mut (), |_, proof, public_inputs| {
                 verify_proof(verifying_key, &proof, &public_inputs[..]).is_ok()
@@ -87,7 +87,7 @@ impl SaplingVerificationContext {
         epk: jubjub::ExtendedPoint,
         zkproof: Proof<Bls12>,
         verifying_key: &PreparedVerifyingKey<Bls12>,
-    ) -> bool {
+    ) -> Result<(), FailedCheck> {
         self.inner
             .check_output(cv, cmu, epk, zkproof, |proof, public_inputs| {
                 verify_proof(verifying_key, &proof, &public_inputs[..]).is_ok()
@@ -102,7 +102,7 @@ impl SaplingVerificationContext {
         value_balance: I128Sum,
         sighash_value: &[u8; 32],
         binding_sig: Signature,
-    ) -> bool {
+    ) -> Result<(), FailedCheck> {
         self.inner.final_check(
             value_balance,
             sighash_value,
@@ -0,0 +1,142 @@
+use bellman::groth16::{prepare_verifying_key, verify_proof, PreparedVerifyingKey, Proof};
+use bls12_381::Bls12;
+use group::GroupEncoding;
+use masp_primitives::{
+    constants::{SPENDING_KEY_GENERATOR, VALUE_COMMITMENT_RANDOMNESS_GENERATOR},
+    transaction::components::sapling::{Authorized, Bundle},
+};
+
+use super::{FailedCheck, SaplingVerificationContextInner, VerificationError};
+use crate::MASPParameters;
+
+/// A transaction verifier that owns the prepared verifying keys for the
+/// Spend, Convert, and Output circuits.
+///
+/// Preparing a verifying key is expensive, so node implementers should
+/// construct one `TransactionVerifier` at startup and reuse it for every
+/// transaction. [`TransactionVerifier::check_bundle`] then performs all of the
+/// per-description consensus checks, the per-asset value-balance bookkeeping,
+/// and the final binding signature check in one call.
+///
+/// Signatures are verified assuming ZIP 216 is active.
+pub struct TransactionVerifier {
+    spend_vk: PreparedVerifyingKey<Bls12>,
+    convert_vk: PreparedVerifyingKey<Bls12>,
+    output_vk: PreparedVerifyingKey<Bls12>,
+}
+
+impl TransactionVerifier {
+    /// Constructs a verifier from prepared verifying keys.
+    pub fn new(
+        spend_vk: PreparedVerifyingKey<Bls12>,
+        convert_vk: PreparedVerifyingKey<Bls12>,
+        output_vk: PreparedVerifyingKey<Bls12>,
+    ) -> Self {
+        TransactionVerifier {
+            spend_vk,
+            convert_vk,
+            output_vk,
+        }
+    }
+
+    /// Constructs a verifier from loaded parameters, preparing and caching
+    /// their verifying keys.
+    pub fn from_parameters(params: &MASPParameters) -> Self {
+        TransactionVerifier {
+            spend_vk: prepare_verifying_key(&params.spend_params.vk),
+            convert_vk: prepare_verifying_key(&params.convert_params.vk),
+            output_vk: prepare_verifying_key(&params.output_params.vk),
+        }
+    }
+
+    /// Checks every consensus rule of the given Sapling bundle: the
+    /// per-description proofs and signatures, the accumulated value
+    /// commitments against the per-asset value balance, and the binding
+    /// signature over `sighash`.
+    pub fn check_bundle(
+        &self,
+        bundle: &Bundle<Authorized>,
+        sighash: [u8; 32],
+    ) -> Result<(), VerificationError> {
+        let mut ctx = SaplingVerificationContextInner::new();
+
+        for (index, spend) in bundle.shielded_spends.iter().enumerate() {
+            let zkproof = Proof::read(&spend.zkproof[..])
+                .map_err(|_| VerificationError::Spend(index, FailedCheck::Proof))?;
+
+            ctx.check_spend(
+                spend.cv,
+                spend.anchor.0,
+                &spend.nullifier.0,
+                spend.rk,
+                &sighash,
+                spend.spend_auth_sig,
+                zkproof,
+                &mut (),
+                |_, rk, msg, spend_auth_sig| {
+                    rk.verify_with_zip216(&msg, &spend_auth_sig, SPENDING_KEY_GENERATOR, true)
+                },
+                |_, proof, public_inputs| {
+                    verify_proof(&self.spend_vk, &proof, &public_inputs[..]).is_ok()
+                },
+            )
+            .map_err(|check| VerificationError::Spend(index, check))?;
+        }
+
+        for (index, convert) in bundle.shielded_converts.iter().enumerate() {
+            let zkproof = Proof::read(&convert.zkproof[..])
+                .map_err(|_| VerificationError::Convert(index, FailedCheck::Proof))?;
+
+            ctx.check_convert(
+                convert.cv,
+                convert.anchor.0,
+                zkproof,
+                &mut (),
+                |_, proof, public_inputs| {
+                    verify_proof(&self.convert_vk, &proof, &public_inputs[..]).is_ok()
+                },
+            )
+            .map_err(|check| VerificationError::Convert(index, check))?;
+        }
+
+        for (index, output) in bundle.shielded_outputs.iter().enumerate() {
+            let epk: jubjub::ExtendedPoint =
+                Option::from(jubjub::ExtendedPoint::from_bytes(&output.ephemeral_key.0))
+                    .ok_or(VerificationError::Output(index, FailedCheck::EphemeralKey))?;
+
+            let zkproof = Proof::read(&output.zkproof[..])
+                .map_err(|_| VerificationError::Output(index, FailedCheck::Proof))?;
+
+            ctx.check_output(
+                output.cv,
+                output.cmu.into(),
+                epk,
+                zkproof,
+                |proof, public_inputs| {
+                    verify_proof(&self.output_vk, &proof, &public_inputs[..]).is_ok()
+                },
+            )
+            .map_err(|check| VerificationError::Output(index, check))?;
+        }
+
+        ctx.final_check(
+            bundle.value_balance.clone(),
+            &sighash,
+            bundle.authorization.binding_sig,
+            |bvk, msg, binding_sig| {
+                // Compute the signature's message for bvk/binding_sig
+                let mut data_to_be_signed = [0u8; 64];
+                data_to_be_signed[0..32].copy_from_slice(&bvk.0.to_bytes());
+                data_to_be_signed[32..64].copy_from_slice(msg);
+
+                bvk.verify_with_zip216(
+                    &data_to_be_signed,
+                    &binding_sig,
+                    VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+                    true,
+                )
+            },
+        )
+        .map_err(VerificationError::Bundle)
+    }
+}